@@ -5,7 +5,7 @@ mod profile_enabled;
 mod profile_disabled;
 
 #[cfg(not(feature = "profile"))]
-pub use profile_disabled::{start_profiler, stop_profiler, PROFILER};
+pub use profile_disabled::{profile_gpu_scope, start_profiler, stop_profiler, PROFILER};
 
 #[cfg(feature = "profile")]
-pub use profile_enabled::{start_profiler, stop_profiler, PROFILER};
+pub use profile_enabled::{profile_gpu_scope, start_profiler, stop_profiler, PROFILER};
@@ -17,6 +17,11 @@ use std::{
     time::{Duration, Instant, SystemTime},
 };
 
+/// The trace track of the CPU scopes.
+const CPU_TRACK_ID: u32 = 1;
+/// The trace track of the GPU scopes, measured with timestamp queries.
+const GPU_TRACK_ID: u32 = 2;
+
 lazy_static! {
     /// This is an example for using doc comment attributes
     pub static ref PROFILER: Mutex<Profiler> = Mutex::new(Profiler::new());
@@ -79,10 +84,11 @@ impl Batch {
             {
                 write!(
                     result,
-                    "\n{},{},{}",
+                    "\n{},{},{},{}",
                     record.profile_fn,
                     record.duration.as_micros(),
-                    start_of_record.as_micros()
+                    start_of_record.as_micros(),
+                    record.track_id
                 );
             }
         }
@@ -138,6 +144,16 @@ impl Profiler {
         self.channel_tx.send(profile_object);
     }
 
+    /// Adds a scope that was measured on the GPU, it is written to the GPU track of the trace.
+    pub fn add_gpu_scope(&mut self, name: String, start_time: Instant, duration: Duration) {
+        self.channel_tx.send(ProfileObject {
+            start_time,
+            duration,
+            profile_fn: name,
+            track_id: GPU_TRACK_ID,
+        });
+    }
+
     pub fn start_session(&mut self) {
         self.is_profiling
             .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed);
@@ -189,6 +205,9 @@ impl Profiler {
                         results[0].to_owned(),
                         results[1].parse().unwrap(),
                         results[2].parse().unwrap(),
+                        results
+                            .get(3)
+                            .map_or(CPU_TRACK_ID, |track_id| track_id.parse().unwrap()),
                     )
                 })
                 .collect::<Vec<SerializedProfileObject>>();
@@ -197,10 +216,20 @@ impl Profiler {
 
             self.remove_file();
 
+            let mut events = vec![
+                SerializedProfileObject::track_name(CPU_TRACK_ID, "CPU"),
+                SerializedProfileObject::track_name(GPU_TRACK_ID, "GPU"),
+            ];
+            events.extend(
+                sorted_vec
+                    .iter()
+                    .map(|event| serde_json::to_value(event).unwrap()),
+            );
+
             Batch::write_to_file(self.output_path, "{\"traceEvents\":");
             Batch::write_to_file(
                 self.output_path,
-                &serde_json::ser::to_string(&sorted_vec).unwrap(),
+                &serde_json::ser::to_string(&events).unwrap(),
             );
             Batch::write_to_file(self.output_path, "}");
         }
@@ -231,17 +260,32 @@ struct SerializedProfileObject {
 }
 
 impl SerializedProfileObject {
-    pub fn new(name: String, duration: u128, start: u128) -> SerializedProfileObject {
+    pub fn new(name: String, duration: u128, start: u128, tid: u32) -> SerializedProfileObject {
         SerializedProfileObject {
-            cat: "function",
+            cat: if tid == GPU_TRACK_ID {
+                "gpu"
+            } else {
+                "function"
+            },
             dur: duration,
             name,
             ph: "X",
             pid: 0,
-            tid: 1,
+            tid,
             ts: start,
         }
     }
+
+    /// A metadata event that names the track in the trace viewer.
+    fn track_name(tid: u32, name: &str) -> serde_json::Value {
+        serde_json::json!({
+            "name": "thread_name",
+            "ph": "M",
+            "pid": 0,
+            "tid": tid,
+            "args": { "name": name },
+        })
+    }
 }
 
 #[derive(Clone)]
@@ -249,6 +293,7 @@ pub struct ProfileObject {
     pub start_time: Instant,
     pub duration: Duration,
     pub profile_fn: String,
+    pub track_id: u32,
 }
 
 impl ProfileObject {
@@ -257,6 +302,7 @@ impl ProfileObject {
             start_time: get_current_time_ns(),
             duration: Duration::default(),
             profile_fn,
+            track_id: CPU_TRACK_ID,
         }
     }
 
@@ -282,3 +328,13 @@ pub fn stop_profiler() {
     profiler.end_session();
     drop(profiler);
 }
+
+/// Adds a scope that was measured on the GPU to the trace.
+///
+/// # Arguments
+/// - `start_time`: When the GPU started the scope, on the CPU clock.
+pub fn profile_gpu_scope(name: &str, start_time: Instant, duration: Duration) {
+    let mut profiler = PROFILER.lock().unwrap();
+    profiler.add_gpu_scope(name.to_string(), start_time, duration);
+    drop(profiler);
+}
@@ -9,7 +9,12 @@ pub use push_constants::{
     GLTFMaterial, GltfPBRShaderConstants, MeshPushConstants, ObjectIdPushConstants,
     PBRMeshPushConstants, UIPushConstants,
 };
-pub use renderer::{RenderContext, RenderLayer, RenderPipeline, FRAGMENT_SHADER, VERTEX_SHADER};
+pub use renderer::{
+    AttachmentFormat, AttachmentInfo, AttachmentSize, FrameContext, FrameData,
+    FrameShaderResources, GraphPass, PassContext, PassKind, RecordPass, RenderContext, RenderGraph,
+    RenderLayer, RenderPipeline, RenderTarget, ResourceId, DEFAULT_FRAMES_IN_FLIGHT,
+    FRAGMENT_SHADER, MESH_PASS, UI_PASS, VERTEX_SHADER,
+};
 pub use uniform_objects::{
     GLTFLightUniformObject, LightUniformObject, UniformObjectClone, UniformObjectTemplate,
     ViewProjectionMatrixUniformObject,
@@ -21,6 +26,8 @@ mod camera;
 mod ecs;
 mod extensions;
 pub mod gltf;
+#[cfg(any(test, feature = "golden"))]
+pub mod golden_image;
 pub mod image;
 mod keycode;
 mod layer;
@@ -2,24 +2,25 @@ use crate::{
     engine::Extensions,
     libs::imgui::__core::fmt::Formatter,
     vulkan::{
-        structures::VkValidationInfo, Version, VkApplication, VkInstance, VkLogicalDevice, Window,
+        structures::VkValidationInfo, DebugMessage, DeviceSelection, Version, VkApplication,
+        VkInstance, VkLogicalDevice, VkSurfaceData, Window,
     },
     WINDOW_HEIGHT, WINDOW_WIDTH,
 };
-use ash::extensions::{ext::DebugUtils, khr};
+use ash::{
+    extensions::{ext::DebugUtils, khr},
+    vk,
+};
 use std::{fmt, fmt::Debug};
 use winit::event_loop::EventLoop;
 
-pub const VALIDATION: VkValidationInfo = VkValidationInfo {
-    is_enable: true,
-    required_validation_layers: ["VK_LAYER_KHRONOS_validation"],
-};
-
 /// Vulkan application with winit window, vulkan data such as instance, device and application.
+///
+/// A headless application has no window, frames are rendered to a `VkOffscreenTarget` instead.
 pub struct VulkanApplication {
     pub application: VkApplication,
     pub instance: VkInstance,
-    pub window: Window,
+    pub window: Option<Window>,
     pub device: VkLogicalDevice,
 }
 
@@ -31,15 +32,43 @@ impl VulkanApplication {
     /// - Instance
     /// - LogicalDevice
     /// - Device/Instance Extensions
-    pub fn new(name: &'static str, event_loop: &EventLoop<()>) -> Self {
-        let instance_extensions = Extensions::new(vec![
-            khr::Surface::name().to_str().unwrap().to_string(),
-            khr::Win32Surface::name().to_str().unwrap().to_string(),
-            DebugUtils::name().to_str().unwrap().to_string(),
-        ]);
+    ///
+    /// The physical device can be forced with the `ANASAIZI_GPU` environment variable and validation
+    /// turned off with `ANASAIZI_VALIDATION`.
+    pub fn new(name: &'static str, event_loop: &EventLoop<()>) -> crate::Result<Self> {
+        Self::with_device_selection(name, event_loop, DeviceSelection::from_env())
+    }
 
-        let device_extensions =
-            Extensions::new(vec![khr::Swapchain::name().to_str().unwrap().to_string()]);
+    /// Creates a new vulkan application that uses the physical device chosen by `selection`.
+    pub fn with_device_selection(
+        name: &'static str,
+        event_loop: &EventLoop<()>,
+        selection: DeviceSelection,
+    ) -> crate::Result<Self> {
+        Self::with_validation(name, event_loop, selection, VkValidationInfo::from_env())
+    }
+
+    /// Creates a new vulkan application with the given validation configuration.
+    pub fn with_validation(
+        name: &'static str,
+        event_loop: &EventLoop<()>,
+        selection: DeviceSelection,
+        validation: VkValidationInfo,
+    ) -> crate::Result<Self> {
+        let winit_window =
+            Window::build_winit_window("Engine", WINDOW_WIDTH, WINDOW_HEIGHT, event_loop);
+
+        let mut instance_extensions = VkSurfaceData::required_extensions(&winit_window);
+        instance_extensions.push(DebugUtils::name().to_str().unwrap().to_string());
+        let instance_extensions = Extensions::new(instance_extensions);
+
+        let device_extensions = Extensions::new(vec![
+            khr::Swapchain::name().to_str().unwrap().to_string(),
+            vk::ExtDescriptorIndexingFn::name()
+                .to_str()
+                .unwrap()
+                .to_string(),
+        ]);
 
         let application = VkApplication::new(
             name,
@@ -51,17 +80,108 @@ impl VulkanApplication {
             WINDOW_HEIGHT,
         );
 
-        let instance = VkInstance::new(VALIDATION, instance_extensions, &application);
+        let instance = VkInstance::with_optional_extensions(
+            validation,
+            instance_extensions,
+            Extensions::new(VkSurfaceData::optional_extensions()),
+            &application,
+        )?;
+
+        let window = Window::new(winit_window, &instance);
+
+        let device = VkLogicalDevice::new(
+            &instance,
+            device_extensions,
+            Some(window.surface_data()),
+            selection,
+        )?;
+
+        Ok(VulkanApplication {
+            application,
+            instance,
+            window: Some(window),
+            device,
+        })
+    }
+
+    /// Creates a new vulkan application without a window.
+    ///
+    /// No surface or swapchain is created and the device is not required to support presenting.
+    pub fn headless(name: &'static str) -> crate::Result<Self> {
+        Self::headless_with_validation(name, VkValidationInfo::from_env())
+    }
+
+    /// Creates a new vulkan application without a window, for tests.
+    ///
+    /// Validation is enabled in test mode, the test fails when a validation error was emitted
+    /// by the time the application is dropped.
+    pub fn headless_for_tests(name: &'static str) -> crate::Result<Self> {
+        Self::headless_with_validation(name, VkValidationInfo::for_tests())
+    }
+
+    /// Creates a new vulkan application without a window with the given validation configuration.
+    pub fn headless_with_validation(
+        name: &'static str,
+        validation: VkValidationInfo,
+    ) -> crate::Result<Self> {
+        let instance_extensions =
+            Extensions::new(vec![DebugUtils::name().to_str().unwrap().to_string()]);
+
+        let application = VkApplication::new(
+            name,
+            name,
+            Version::new(0, 0, 1),
+            Version::new(1, 2, 0),
+            Version::new(1, 2, 0),
+            WINDOW_WIDTH,
+            WINDOW_HEIGHT,
+        );
 
-        let window = Window::new("Engine", WINDOW_WIDTH, WINDOW_HEIGHT, &instance, event_loop);
+        let instance = VkInstance::new(validation, instance_extensions, &application)?;
 
-        let device = VkLogicalDevice::new(&instance, device_extensions, window.surface_data());
+        let device = VkLogicalDevice::new(
+            &instance,
+            Extensions::new(vec![vk::ExtDescriptorIndexingFn::name()
+                .to_str()
+                .unwrap()
+                .to_string()]),
+            None,
+            DeviceSelection::from_env(),
+        )?;
 
-        VulkanApplication {
+        Ok(VulkanApplication {
             application,
             instance,
-            window,
+            window: None,
             device,
+        })
+    }
+
+    /// Returns the window of this application.
+    pub fn window(&self) -> &Window {
+        self.window
+            .as_ref()
+            .expect("Application is headless and has no window!")
+    }
+
+    pub fn is_headless(&self) -> bool {
+        self.window.is_none()
+    }
+
+    /// The validation errors recorded so far in test mode.
+    pub fn validation_errors(&self) -> Vec<DebugMessage> {
+        self.instance
+            .debug_messenger()
+            .map_or_else(Vec::new, |messenger| messenger.validation_errors())
+    }
+
+    /// Destroys the pipeline cache and frees the device memory blocks.
+    ///
+    /// Call this after the layers are destroyed, the buffers and images must be freed before.
+    pub fn destroy(&self) {
+        unsafe {
+            self.device.destroy_pipeline_cache();
+            self.device.destroy_allocator();
         }
     }
 }
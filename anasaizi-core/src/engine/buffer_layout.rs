@@ -94,6 +94,13 @@ impl BufferLayout {
         self
     }
 
+    /// Adds an element with the given size and format to the layout.
+    pub fn add_element(mut self, layout_id: u8, stride: usize, format: vk::Format) -> Self {
+        self.layouts
+            .push(BufferLayoutElement::new(layout_id, stride, format));
+        self
+    }
+
     /// Gets a layout element by the given id.
     pub fn get(&self, layout_id: u8) -> Option<&BufferLayoutElement> {
         for layout in self.layouts.iter() {
@@ -124,6 +131,7 @@ impl BufferLayout {
 
     /// Returns the binding descriptions that describe how a single buffer element is laid out in the buffer.
     pub fn build_binding_description(&mut self) {
+        self.binding_desc.clear();
         self.binding_desc.push(vk::VertexInputBindingDescription {
             binding: 0,
             stride: self.layouts.iter().map(|x| x.stride).sum::<usize>() as u32,
@@ -135,6 +143,9 @@ impl BufferLayout {
     pub fn build_attrib_description(&mut self) {
         let mut offset: usize = 0;
 
+        // Pipelines are rebuilt on refresh, start over instead of appending.
+        self.attrib_desc.clear();
+
         for layout in self.layouts.iter() {
             self.attrib_desc.push(vk::VertexInputAttributeDescription {
                 binding: 0,
@@ -72,9 +72,32 @@ impl Camera {
         }
     }
 
-    pub fn update_screen_resize(&mut self, aspect_ratio: f32, fov: f32) {
-        self.aspect_ratio = aspect_ratio;
-        self.field_of_view = fov;
+    /// Updates the aspect ratio for the new extent of the screen, the field of view is kept.
+    pub fn update_screen_resize(&mut self, width: u32, height: u32) {
+        self.aspect_ratio = Self::aspect_ratio_of(width, height);
+        self.mark_dirty();
+    }
+
+    pub fn aspect_ratio(&self) -> f32 {
+        self.aspect_ratio
+    }
+
+    /// The aspect ratio of a screen with the given extent.
+    pub fn aspect_ratio_of(width: u32, height: u32) -> f32 {
+        width as f32 / height.max(1) as f32
+    }
+
+    /// Places the camera at `position` looking towards `target`.
+    pub fn look_at(&mut self, position: Vector3, target: Vector3) {
+        let direction = (target - position).normalize();
+
+        self.position = Point3::new(position.x, position.y, position.z);
+        self.camera_front = direction;
+
+        // Keep yaw and pitch in sync so mouse movement continues from this direction.
+        self.pitch = (direction.y as f64).asin().to_degrees();
+        self.yaw = (direction.z as f64).atan2(direction.x as f64).to_degrees();
+
         self.mark_dirty();
     }
 
@@ -225,3 +248,18 @@ impl Camera {
         degrees * (std::f64::consts::PI as f32 / 180.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::Camera;
+
+    #[test]
+    fn resize_updates_the_aspect_ratio() {
+        let mut camera = Camera::new(Camera::aspect_ratio_of(800, 600), 1.0, 0.1, 100.0);
+        assert!((camera.aspect_ratio() - 4.0 / 3.0).abs() < f32::EPSILON);
+
+        camera.update_screen_resize(1920, 1080);
+        assert!((camera.aspect_ratio() - 16.0 / 9.0).abs() < f32::EPSILON);
+        assert!(camera.needs_recalculation());
+    }
+}
@@ -1,3 +1,5 @@
+use ash::vk;
+
 pub struct PBRMaps {
     pub albedo: i32,
     pub ao: i32,
@@ -6,3 +8,53 @@ pub struct PBRMaps {
     pub roughness: i32,
     pub displacement: i32,
 }
+
+/// A descriptor set that is bound for a single mesh, for sets with `DescriptorFrequency::Object`.
+pub struct ObjectDescriptorSet {
+    pub set: u32,
+    /// One set per frame in flight, or a single set that is used for every frame.
+    pub descriptor_sets: Vec<vk::DescriptorSet>,
+}
+
+impl ObjectDescriptorSet {
+    pub fn descriptor_set(&self, frame: usize) -> vk::DescriptorSet {
+        if self.descriptor_sets.len() == 1 {
+            self.descriptor_sets[0]
+        } else {
+            self.descriptor_sets[frame]
+        }
+    }
+}
+
+/// Dynamic offsets for a single mesh, one for every dynamic buffer of the set, in binding order.
+///
+/// When the set is the mesh's `ObjectDescriptorSet` the offsets are used to bind it,
+/// otherwise the shader's own set is bound again with them.
+pub struct DynamicOffsets {
+    pub set: u32,
+    pub offsets: Vec<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::ObjectDescriptorSet;
+    use ash::vk::{self, Handle};
+
+    #[test]
+    fn object_descriptor_set_per_frame() {
+        let shared = ObjectDescriptorSet {
+            set: 2,
+            descriptor_sets: vec![vk::DescriptorSet::from_raw(1)],
+        };
+        let per_frame = ObjectDescriptorSet {
+            set: 2,
+            descriptor_sets: vec![
+                vk::DescriptorSet::from_raw(1),
+                vk::DescriptorSet::from_raw(2),
+            ],
+        };
+
+        assert_eq!(shared.descriptor_set(1), vk::DescriptorSet::from_raw(1));
+        assert_eq!(per_frame.descriptor_set(1), vk::DescriptorSet::from_raw(2));
+    }
+}
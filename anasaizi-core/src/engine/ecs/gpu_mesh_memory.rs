@@ -54,8 +54,9 @@ impl GpuMeshMemory {
     /// Updates the mesh with the given imgui `DrawData`.
     ///
     /// This function will either reallocate, destroy, extend memory based up on the given `DrawData`.
+    /// The mesh must not be in use by a frame in flight.
     pub fn update_from_draw_data(&mut self, render_context: &RenderContext, draw_data: &DrawData) {
-        let device = render_context.device();
+        let device = render_context.logical_device();
 
         let (vertices, indices) = Self::get_vertices_and_indices(draw_data);
 
@@ -68,7 +69,7 @@ impl GpuMeshMemory {
             self.vertex_buffer = vertex_buffer;
         } else {
             // Update buffer content with new draw data.
-            self.vertex_buffer.update_buffer_content(device, &vertices);
+            self.vertex_buffer.update_buffer_content(&vertices);
         }
 
         // if index buffer is outdated
@@ -79,7 +80,7 @@ impl GpuMeshMemory {
             self.index_buffer = index_buffer;
         } else {
             // Update buffer content with new draw data.
-            self.index_buffer.update_buffer_content(device, &indices);
+            self.index_buffer.update_buffer_content(&indices);
         }
     }
 
@@ -1,3 +1,4 @@
+use crate::Result;
 use std::{
     collections::HashMap,
     path::Path,
@@ -27,7 +28,7 @@ impl GltfBufferStorage {
 
 pub struct GltfBufferLoader {
     storage: Arc<Mutex<GltfBufferStorage>>,
-    load_tasks: Vec<JoinHandle<()>>,
+    load_tasks: Vec<JoinHandle<Result<()>>>,
 }
 
 impl GltfBufferLoader {
@@ -48,25 +49,34 @@ impl GltfBufferLoader {
         lock.add_buffer(id, buffer.to_vec());
     }
 
-    pub async fn wait_loading(self) -> GltfBufferStorage {
-        futures::future::join_all(self.load_tasks).await;
+    /// Waits for all buffers, fails with the first error of a load task.
+    pub async fn wait_loading(self) -> Result<GltfBufferStorage> {
+        for result in futures::future::join_all(self.load_tasks).await {
+            result??;
+        }
 
         if let Ok(lock) = Arc::try_unwrap(self.storage) {
             let lock = lock.into_inner().expect("Mutex cannot be locked");
-            return lock;
+            return Ok(lock);
         }
         panic!("Could not acquire storage inner value");
     }
 }
 
-async fn load_buffer_file(path: String, index: usize, storage: Arc<Mutex<GltfBufferStorage>>) {
+async fn load_buffer_file(
+    path: String,
+    index: usize,
+    storage: Arc<Mutex<GltfBufferStorage>>,
+) -> Result<()> {
     let base = Path::new(&path);
-    let file = fs::File::open(base).await.unwrap();
+    let file = fs::File::open(base).await?;
     let mut reader = io::BufReader::new(file);
 
     let mut bytes = Vec::new();
-    reader.read_to_end(&mut bytes).await.unwrap();
+    reader.read_to_end(&mut bytes).await?;
 
     let mut lock = storage.lock().unwrap();
     lock.add_buffer(index, bytes);
+
+    Ok(())
 }
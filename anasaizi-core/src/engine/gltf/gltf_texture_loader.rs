@@ -1,7 +1,15 @@
-use crate::engine::{image::Texture, resources::StoredTexture, RenderContext};
+use crate::{
+    engine::{
+        image::{Texture, TextureColorSpace},
+        resources::StoredTexture,
+        RenderContext,
+    },
+    Error, Result,
+};
 use image::GenericImageView;
 use std::{
     collections::HashMap,
+    path::Path,
     sync::{Arc, Mutex},
 };
 use tokio::task::JoinHandle;
@@ -25,7 +33,23 @@ impl GltfTextureStorage {
         &self.buffers[&index]
     }
 
-    pub fn to_vulkan_textures(mut self, render_context: &mut RenderContext) -> Vec<Texture> {
+    /// Sets the color space the image with the given index is uploaded in.
+    ///
+    /// An image that is a color map of any material stays sRGB.
+    pub fn set_color_space(&mut self, index: usize, color_space: TextureColorSpace) {
+        if let Some((_, texture)) = self.buffers.get_mut(&index) {
+            if texture.color_space != TextureColorSpace::Srgb {
+                texture.color_space = color_space;
+            }
+        }
+    }
+
+    /// Uploads the textures in image order, they are named `{scene_name}/{image file stem}`.
+    pub fn to_vulkan_textures(
+        mut self,
+        render_context: &mut RenderContext,
+        scene_name: &str,
+    ) -> Vec<Texture> {
         let mut values = self
             .buffers
             .values_mut()
@@ -37,6 +61,16 @@ impl GltfTextureStorage {
             .iter_mut()
             .map(|x| {
                 x.1.load_textures(render_context);
+
+                let file_stem = Path::new(&x.1.path).file_stem().map_or_else(
+                    || x.1.id.clone(),
+                    |stem| stem.to_string_lossy().into_owned(),
+                );
+                x.1.texture().set_debug_name(
+                    render_context.logical_device(),
+                    &format!("{}/{}", scene_name, file_stem),
+                );
+
                 x.1.texture().clone()
             })
             .collect()
@@ -45,7 +79,7 @@ impl GltfTextureStorage {
 
 pub struct GltfTextureLoader {
     storage: Arc<Mutex<GltfTextureStorage>>,
-    load_tasks: Vec<JoinHandle<()>>,
+    load_tasks: Vec<JoinHandle<Result<()>>>,
 }
 
 impl GltfTextureLoader {
@@ -66,12 +100,15 @@ impl GltfTextureLoader {
         self.load_tasks.push(task);
     }
 
-    pub async fn wait_loading(self) -> GltfTextureStorage {
-        futures::future::join_all(self.load_tasks).await;
+    /// Waits for all images, fails with the first error of a load task.
+    pub async fn wait_loading(self) -> Result<GltfTextureStorage> {
+        for result in futures::future::join_all(self.load_tasks).await {
+            result??;
+        }
 
         if let Ok(lock) = Arc::try_unwrap(self.storage) {
             let lock = lock.into_inner().expect("Mutex cannot be locked");
-            return lock;
+            return Ok(lock);
         }
         panic!("Could not acquire storage inner value");
     }
@@ -82,16 +119,14 @@ pub async fn load_image_path(
     id: usize,
     image_results: Arc<Mutex<GltfTextureStorage>>,
     debug: bool,
-) {
+) -> Result<()> {
     let contents = if !debug {
-        tokio::fs::read(path.clone()).await.unwrap()
+        tokio::fs::read(path.clone()).await?
     } else {
-        tokio::fs::read("assets/textures/debug/albedo.jpg")
-            .await
-            .unwrap()
+        tokio::fs::read("assets/textures/debug/albedo.jpg").await?
     };
 
-    let mut image_object = image::load_from_memory(&contents).unwrap();
+    let mut image_object = image::load_from_memory(&contents)?;
     image_object = image_object.flipv();
 
     let (image_width, image_height) = (image_object.width(), image_object.height());
@@ -104,7 +139,10 @@ pub async fn load_image_path(
         | image::DynamicImage::ImageBgra8(_)
         | image::DynamicImage::ImageRgba8(_) => image_object.to_bytes(),
         _ => {
-            panic!("Invalid image format, image should be rgba compatible");
+            return Err(Error::Unsupported(format!(
+                "image format of {}, image should be rgba compatible",
+                path
+            )));
         }
     };
 
@@ -112,6 +150,8 @@ pub async fn load_image_path(
         image_data,
         image_width,
         image_height,
+        // The material import marks the color maps as sRGB.
+        TextureColorSpace::Linear,
         path,
         id.to_string(),
         None,
@@ -119,4 +159,6 @@ pub async fn load_image_path(
 
     let mut lock = image_results.lock().unwrap();
     lock.add_texture(id, stored_texture);
+
+    Ok(())
 }
@@ -7,9 +7,10 @@ use crate::{
             root::GLTFRoot,
             scene::GLTFScene,
         },
-        RenderContext,
+        GLTFMaterial, RenderContext,
     },
     libs::tokio::{fs, io},
+    Error, Result,
 };
 use gltf::Gltf;
 
@@ -20,23 +21,30 @@ pub async fn load_gltf_scene(
     mut render_context: RenderContext,
     source: &str,
     scene_index: usize,
-) -> (GLTFRoot, GLTFScene) {
+) -> Result<(GLTFRoot, GLTFScene)> {
     let base = Path::new(&source).parent().unwrap_or(Path::new("./"));
     let gltf_file = Path::new(source);
 
-    let file = fs::File::open(gltf_file).await.unwrap();
+    let file = fs::File::open(gltf_file).await?;
     let mut reader = io::BufReader::new(file);
 
     let mut bytes = Vec::new();
-    reader.read_to_end(&mut bytes).await.unwrap();
+    reader.read_to_end(&mut bytes).await?;
 
-    let mut gltf = Gltf::from_slice(&bytes).unwrap();
+    let mut gltf = Gltf::from_slice(&bytes)?;
 
     let texture_loader = GltfTextureLoader::new();
     let buffer_loader = GltfBufferLoader::new();
 
-    let buffer_storage = load_buffers(&mut gltf, buffer_loader, base).await;
-    let texture_storage = load_images(&mut gltf, texture_loader, base).await;
+    let buffer_storage = load_buffers(&mut gltf, buffer_loader, base).await?;
+    let mut texture_storage = load_images(&mut gltf, texture_loader, base).await?;
+
+    // Color maps are uploaded as sRGB, data maps stay linear.
+    for g_material in gltf.document.materials() {
+        for (image, color_space) in GLTFMaterial::texture_color_spaces(&g_material) {
+            texture_storage.set_color_space(image, color_space);
+        }
+    }
 
     let data = ImportData {
         buffer_storage,
@@ -45,22 +53,35 @@ pub async fn load_gltf_scene(
     };
 
     let base_path = Path::new(source);
+    let g_scene =
+        data.doc.scenes().nth(scene_index).ok_or_else(|| {
+            Error::Unsupported(format!("scene index {} in {}", scene_index, source))
+        })?;
+
     let mut root = GLTFRoot::from_gltf(&mut render_context, &data, base_path);
-    let scene = GLTFScene::from_gltf(&data.doc.scenes().nth(scene_index).unwrap(), &mut root);
+    let scene = GLTFScene::from_gltf(&g_scene, &mut root);
 
-    root.textures = data.texture_storage.to_vulkan_textures(&mut render_context);
-    (root, scene)
+    let scene_name = gltf_file.file_stem().map_or_else(
+        || source.to_string(),
+        |stem| stem.to_string_lossy().into_owned(),
+    );
+    root.textures = data
+        .texture_storage
+        .to_vulkan_textures(&mut render_context, &scene_name);
+    Ok((root, scene))
 }
 
 async fn load_buffers(
     gltf: &mut Gltf,
     mut buffer_loader: GltfBufferLoader,
     base: &Path,
-) -> GltfBufferStorage {
+) -> Result<GltfBufferStorage> {
     for buffer in gltf.buffers() {
         match buffer.source() {
             gltf::buffer::Source::Bin => {
-                panic!("GLTF buffer binary source is not supported.")
+                return Err(Error::Unsupported(String::from(
+                    "glTF binary buffer source",
+                )));
                 // let begin = buffer.offset();
                 // let end = begin + buffer.length();
                 // let data: &[u8] = &raw_buffers[begin..end];
@@ -80,7 +101,7 @@ async fn load_images(
     gltf: &mut Gltf,
     mut texture_loader: GltfTextureLoader,
     base: &Path,
-) -> GltfTextureStorage {
+) -> Result<GltfTextureStorage> {
     for image in gltf.images() {
         match image.source() {
             gltf::image::Source::View {
@@ -90,7 +111,7 @@ async fn load_images(
                 // texture_loader.load_buffer(view.index(), view.stride(), view.offset(), view.length(), view.buffer(), &image.index().to_string(), false);
                 //
                 // crate::engine::image::Texture::from_bytes(render_context, &image_data, image_object.width(), image_object.height())
-                panic!("GLTF image view source is not supported.")
+                return Err(Error::Unsupported(String::from("glTF image view source")));
             }
             gltf::image::Source::Uri { mime_type: _, uri } => {
                 texture_loader.load_path(base.join(uri).to_str().unwrap(), image.index());
@@ -1,5 +1,5 @@
 use crate::{
-    engine::{gltf::mappers::ImportData, GLTFMaterial},
+    engine::{gltf::mappers::ImportData, image::TextureColorSpace, GLTFMaterial},
     math::{Matrix4, Vector3, Vector4},
     vulkan::ShaderFlags,
 };
@@ -82,6 +82,37 @@ impl GLTFMaterial {
         material
     }
 
+    /// The images the material samples with their color space.
+    ///
+    /// Base color and emissive maps hold colors and are sRGB, the other maps hold data and are linear.
+    pub(crate) fn texture_color_spaces(
+        g_material: &gltf::Material<'_>,
+    ) -> Vec<(usize, TextureColorSpace)> {
+        let pbr = g_material.pbr_metallic_roughness();
+
+        let color_maps = [
+            pbr.base_color_texture().map(|info| info.texture()),
+            g_material.emissive_texture().map(|info| info.texture()),
+        ];
+        let data_maps = [
+            pbr.metallic_roughness_texture().map(|info| info.texture()),
+            g_material.normal_texture().map(|info| info.texture()),
+            g_material.occlusion_texture().map(|info| info.texture()),
+        ];
+
+        color_maps
+            .iter()
+            .flatten()
+            .map(|texture| (texture.source().index(), TextureColorSpace::Srgb))
+            .chain(
+                data_maps
+                    .iter()
+                    .flatten()
+                    .map(|texture| (texture.source().index(), TextureColorSpace::Linear)),
+            )
+            .collect()
+    }
+
     pub fn shader_flags(&self) -> ShaderFlags {
         let mut flags = ShaderFlags::empty();
         if self.base_color_texture != -1 {
@@ -99,8 +130,30 @@ impl GLTFMaterial {
         if self.occlusion_texture != -1 {
             flags |= ShaderFlags::HAS_OCCLUSIONMAP;
         }
+        // Textures are created with a full mip chain.
+        if !flags.is_empty() {
+            flags |= ShaderFlags::USE_TEX_LOD;
+        }
         flags
     }
+
+    /// Moves the texture indices, which index the textures of the scene, to a texture table where the
+    /// scene textures start at `first_texture`. Missing textures stay `-1`.
+    pub fn offset_textures(&mut self, first_texture: i32) {
+        for texture in [
+            &mut self.base_color_texture,
+            &mut self.normal_texture,
+            &mut self.metallic_roughness_texture,
+            &mut self.occlusion_texture,
+            &mut self.emissive_texture,
+        ]
+        .iter_mut()
+        {
+            if **texture != -1 {
+                **texture += first_texture;
+            }
+        }
+    }
 }
 
 fn load_texture(g_texture: &gltf::texture::Texture<'_>, imp: &ImportData) -> i32 {
@@ -110,3 +163,21 @@ fn load_texture(g_texture: &gltf::texture::Texture<'_>, imp: &ImportData) -> i32
 
     *id as i32
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::GLTFMaterial;
+
+    #[test]
+    fn offset_textures_keeps_missing_textures() {
+        let mut material = GLTFMaterial::default();
+        material.base_color_texture = 2;
+        material.normal_texture = -1;
+
+        material.offset_textures(5);
+
+        assert_eq!(material.base_color_texture, 7);
+        assert_eq!(material.normal_texture, -1);
+        assert_eq!(material.emissive_texture, 5);
+    }
+}
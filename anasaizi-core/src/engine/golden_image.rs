@@ -0,0 +1,172 @@
+use image::{Rgba, RgbaImage};
+use std::path::{Path, PathBuf};
+
+/// Set this environment variable to `1` to overwrite the stored reference images with the rendered frames.
+pub const UPDATE_GOLDEN_IMAGES_ENV: &str = "ANASAIZI_UPDATE_GOLDEN";
+
+/// The result of comparing a rendered frame against a reference image.
+pub struct ImageComparison {
+    /// The number of pixels where a channel differs more than the tolerance.
+    pub mismatched_pixels: usize,
+    /// The largest channel difference found in the image.
+    pub max_difference: u8,
+    /// Mismatched pixels in red on top of a dimmed copy of the reference.
+    pub diff_image: RgbaImage,
+}
+
+impl ImageComparison {
+    pub fn is_match(&self) -> bool {
+        self.mismatched_pixels == 0
+    }
+}
+
+/// Compares two images of equal size pixel by pixel.
+///
+/// # Arguments
+/// - `tolerance`: The maximum difference allowed per color channel.
+pub fn compare_images(actual: &RgbaImage, reference: &RgbaImage, tolerance: u8) -> ImageComparison {
+    assert_eq!(
+        actual.dimensions(),
+        reference.dimensions(),
+        "Rendered image and reference image differ in size!"
+    );
+
+    let mut mismatched_pixels = 0;
+    let mut max_difference = 0;
+    let mut diff_image = RgbaImage::new(reference.width(), reference.height());
+
+    for ((actual_pixel, reference_pixel), diff_pixel) in actual
+        .pixels()
+        .zip(reference.pixels())
+        .zip(diff_image.pixels_mut())
+    {
+        let difference = actual_pixel
+            .0
+            .iter()
+            .zip(reference_pixel.0.iter())
+            .map(|(a, r)| (*a as i16 - *r as i16).abs() as u8)
+            .max()
+            .unwrap();
+
+        max_difference = max_difference.max(difference);
+
+        *diff_pixel = if difference > tolerance {
+            mismatched_pixels += 1;
+            Rgba([255, 0, 0, 255])
+        } else {
+            let [r, g, b, _] = reference_pixel.0;
+            let gray = ((r as u16 + g as u16 + b as u16) / 3 / 4) as u8;
+            Rgba([gray, gray, gray, 255])
+        };
+    }
+
+    ImageComparison {
+        mismatched_pixels,
+        max_difference,
+        diff_image,
+    }
+}
+
+/// Asserts that a rendered frame matches the reference image at `reference_path`.
+///
+/// A missing reference fails the assertion. Set `ANASAIZI_UPDATE_GOLDEN=1` to write the rendered
+/// frame as the reference instead of comparing against it.
+/// On a mismatch the rendered frame and a diff image are written next to the reference
+/// as `<name>.actual.png` and `<name>.diff.png`.
+pub fn assert_golden_image(actual: &RgbaImage, reference_path: &Path, tolerance: u8) {
+    if update_golden_images() {
+        if let Some(directory) = reference_path.parent() {
+            std::fs::create_dir_all(directory).expect("Failed to create reference directory!");
+        }
+
+        actual
+            .save(reference_path)
+            .expect("Failed to write reference image!");
+        log::info!("Stored reference image at {:?}", reference_path);
+        return;
+    }
+
+    if !reference_path.exists() {
+        let actual_path = sibling_path(reference_path, "actual");
+        actual
+            .save(&actual_path)
+            .expect("Failed to write rendered image!");
+
+        panic!(
+            "Reference image {:?} does not exist, the rendered frame is at {:?}. Run with {}=1 to record it.",
+            reference_path, actual_path, UPDATE_GOLDEN_IMAGES_ENV
+        );
+    }
+
+    let reference = image::open(reference_path)
+        .expect("Failed to read reference image!")
+        .to_rgba8();
+
+    let comparison = compare_images(actual, &reference, tolerance);
+
+    if !comparison.is_match() {
+        let actual_path = sibling_path(reference_path, "actual");
+        let diff_path = sibling_path(reference_path, "diff");
+
+        actual
+            .save(&actual_path)
+            .expect("Failed to write rendered image!");
+        comparison
+            .diff_image
+            .save(&diff_path)
+            .expect("Failed to write diff image!");
+
+        panic!(
+            "{} pixels differ more than {} from {:?} (max difference {}), see {:?}",
+            comparison.mismatched_pixels,
+            tolerance,
+            reference_path,
+            comparison.max_difference,
+            diff_path
+        );
+    }
+}
+
+fn update_golden_images() -> bool {
+    std::env::var(UPDATE_GOLDEN_IMAGES_ENV)
+        .map(|value| value.trim() == "1")
+        .unwrap_or(false)
+}
+
+fn sibling_path(path: &Path, suffix: &str) -> PathBuf {
+    let stem = path.file_stem().unwrap().to_str().unwrap();
+    path.with_file_name(format!("{}.{}.png", stem, suffix))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::golden_image::compare_images;
+    use image::{Rgba, RgbaImage};
+
+    #[test]
+    fn compare_within_tolerance() {
+        let reference = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+        let actual = RgbaImage::from_pixel(4, 4, Rgba([102, 98, 100, 255]));
+
+        let comparison = compare_images(&actual, &reference, 2);
+
+        assert!(comparison.is_match());
+        assert_eq!(comparison.max_difference, 2);
+    }
+
+    #[test]
+    fn compare_outside_tolerance() {
+        let reference = RgbaImage::from_pixel(4, 4, Rgba([100, 100, 100, 255]));
+        let mut actual = reference.clone();
+        actual.put_pixel(1, 2, Rgba([200, 100, 100, 255]));
+
+        let comparison = compare_images(&actual, &reference, 2);
+
+        assert_eq!(comparison.mismatched_pixels, 1);
+        assert_eq!(comparison.max_difference, 100);
+        assert_eq!(
+            *comparison.diff_image.get_pixel(1, 2),
+            Rgba([255, 0, 0, 255])
+        );
+    }
+}
@@ -1,31 +1,70 @@
 use crate::{
     engine::RenderContext,
     vulkan::{
-        begin_single_time_command, create_allocate_vk_buffer, end_single_time_command, VkImageView,
+        submit_single_time_command, Allocation, AllocationKind, MipGeneration, VkImageView,
         VkLogicalDevice,
     },
+    Error, Result,
 };
 use ash::{version::DeviceV1_0, vk};
 use image::GenericImageView;
 use std::{path::Path, ptr};
 
+/// How the texel values of a texture are encoded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TextureColorSpace {
+    /// Colors, like base color and emissive maps, are sampled as linear values.
+    Srgb,
+    /// Data, like normal, metallic-roughness and occlusion maps, is sampled as stored.
+    Linear,
+}
+
+impl TextureColorSpace {
+    /// The format of rgba8 images in this color space.
+    pub fn format(&self) -> vk::Format {
+        match self {
+            TextureColorSpace::Srgb => vk::Format::R8G8B8A8_SRGB,
+            TextureColorSpace::Linear => vk::Format::R8G8B8A8_UNORM,
+        }
+    }
+}
+
 /// A vulkan texture that contains an image, imageview, and device memory.
 #[derive(Clone)]
 pub struct Texture {
     pub image: vk::Image,
-    pub device_memory: vk::DeviceMemory,
+    pub device_memory: Allocation,
     pub image_view: VkImageView,
+    /// The number of mip levels of the image, the image view covers all of them.
+    pub mip_levels: u32,
+    pub color_space: TextureColorSpace,
 }
 
 impl Texture {
+    /// Names the image and its view in validation messages and captures.
+    pub fn set_debug_name(&self, device: &VkLogicalDevice, name: &str) {
+        device.set_debug_name(self.image, name);
+        device.set_debug_name(*self.image_view, name);
+    }
+
     /// Creates a texture from a path to the rgba image.
-    pub fn create(render_context: &RenderContext, image_path: &Path) -> Texture {
-        Self::from_path(render_context, image_path)
+    pub fn create(
+        render_context: &RenderContext,
+        image_path: &Path,
+        color_space: TextureColorSpace,
+    ) -> Result<Texture> {
+        Self::from_path(render_context, image_path, color_space)
     }
 
     /// Creates a texture from a path to the rgba image.
-    pub fn from_path(render_context: &RenderContext, image_path: &Path) -> Texture {
-        let mut image_object = image::open(image_path).unwrap(); // this function is slow in debug mode.
+    ///
+    /// Fails when the image can not be read or decoded.
+    pub fn from_path(
+        render_context: &RenderContext,
+        image_path: &Path,
+        color_space: TextureColorSpace,
+    ) -> Result<Texture> {
+        let mut image_object = image::open(image_path)?; // this function is slow in debug mode.
         image_object = image_object.flipv();
 
         let (image_width, image_height) = (image_object.width(), image_object.height());
@@ -38,22 +77,41 @@ impl Texture {
             | image::DynamicImage::ImageBgra8(_)
             | image::DynamicImage::ImageRgba8(_) => image_object.to_bytes(),
             _ => {
-                panic!("Invalid image format, image should be rgba compatible");
+                return Err(Error::Unsupported(format!(
+                    "image format of {:?}, image should be rgba compatible",
+                    image_path
+                )));
             }
         };
 
-        Self::from_bytes(render_context, &image_data, image_width, image_height)
+        Ok(Self::from_bytes(
+            render_context,
+            &image_data,
+            image_width,
+            image_height,
+            color_space,
+        ))
+    }
+
+    /// The number of mip levels of a full mip chain, down to a level of 1x1 texels.
+    pub fn mip_level_count(width: u32, height: u32) -> u32 {
+        32 - width.max(height).max(1).leading_zeros()
     }
 
     /// Creates a texture from an `u8` array containing an rgba image.
+    ///
+    /// The texture has a full mip chain, it is blitted when the format supports linear blits and
+    /// downsampled on the CPU otherwise.
+    ///
+    /// # Arguments
+    /// - `color_space`: Whether the texture holds colors, `Srgb`, or data, `Linear`.
     pub fn from_bytes(
         render_context: &RenderContext,
         texture_data: &[u8],
         image_width: u32,
         image_height: u32,
+        color_space: TextureColorSpace,
     ) -> Texture {
-        let device = render_context.device().clone();
-
         let image_size =
             (std::mem::size_of::<u8>() as u32 * image_width * image_height * 4) as vk::DeviceSize;
 
@@ -61,82 +119,60 @@ impl Texture {
             panic!("Failed to load texture image! Texture image is of size 0.")
         }
 
-        // Create staging buffer on the CPU.
-        // The buffer should be in host visible memory so that we can map it
-        // and it should be usable as a transfer source so that we can copy it to an image later on:
-        let (staging_buffer, staging_buffer_memory) = create_allocate_vk_buffer(
-            render_context,
-            image_size,
-            vk::BufferUsageFlags::TRANSFER_SRC,
-            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-        );
-
-        // Copy the pixel values from the loaded image to the staging buffer.
-        unsafe {
-            let data_ptr = device
-                .map_memory(
-                    staging_buffer_memory,
-                    0,
-                    image_size,
-                    vk::MemoryMapFlags::empty(),
-                )
-                .expect("Failed to Map Memory") as *mut u8;
-
-            data_ptr.copy_from_nonoverlapping(texture_data.as_ptr(), texture_data.len());
-
-            device.unmap_memory(staging_buffer_memory);
-        }
+        let format = color_space.format();
+        let mip_levels = Self::mip_level_count(image_width, image_height);
 
-        let (texture_image, texture_image_memory) = Self::create_image(
+        let (texture_image, texture_image_memory) = Self::create_mipmapped_image(
             render_context,
             image_width,
             image_height,
-            vk::Format::R8G8B8A8_UNORM,
+            mip_levels,
+            format,
             vk::ImageTiling::OPTIMAL,
-            vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED,
+            vk::ImageUsageFlags::TRANSFER_SRC
+                | vk::ImageUsageFlags::TRANSFER_DST
+                | vk::ImageUsageFlags::SAMPLED,
             vk::MemoryPropertyFlags::DEVICE_LOCAL,
         );
 
-        Self::transition_image_layout(
-            render_context,
-            texture_image,
-            vk::ImageLayout::UNDEFINED,
-            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-        );
+        let mip_generation = if render_context.logical_device().supports_format_features(
+            render_context.raw_instance(),
+            format,
+            vk::ImageTiling::OPTIMAL,
+            vk::FormatFeatureFlags::BLIT_SRC
+                | vk::FormatFeatureFlags::BLIT_DST
+                | vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR,
+        ) {
+            MipGeneration::Blit
+        } else {
+            MipGeneration::Cpu
+        };
 
-        Self::copy_buffer_to_image(
-            render_context,
-            staging_buffer,
+        // The copy, the mip levels and layout transitions are submitted with the next upload flush.
+        render_context.upload_context().upload_image(
+            texture_data,
             texture_image,
             image_width,
             image_height,
+            mip_levels,
+            mip_generation,
         );
 
-        Self::transition_image_layout(
-            render_context,
-            texture_image,
-            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-        );
-
-        // Cleanup the staging buffer because the texture is now uploaded to the GPU.
-        unsafe {
-            device.destroy_buffer(staging_buffer, None);
-            device.free_memory(staging_buffer_memory, None);
-        }
-
         // Crate an imageview for this image.
-        let image_view = VkImageView::create(
+        let image_view = VkImageView::create_with_mip_levels(
             render_context.device(),
             texture_image,
-            vk::Format::R8G8B8A8_UNORM,
+            format,
             vk::ImageAspectFlags::COLOR,
+            mip_levels,
         );
 
         Texture {
             image: texture_image,
             device_memory: texture_image_memory,
             image_view,
+            mip_levels,
+            color_space,
         }
     }
 
@@ -155,7 +191,87 @@ impl Texture {
         tiling: vk::ImageTiling,
         usage: vk::ImageUsageFlags,
         required_memory_properties: vk::MemoryPropertyFlags,
-    ) -> (vk::Image, vk::DeviceMemory) {
+    ) -> (vk::Image, Allocation) {
+        Self::create_multisampled_image(
+            render_context,
+            width,
+            height,
+            format,
+            tiling,
+            usage,
+            required_memory_properties,
+            vk::SampleCountFlags::TYPE_1,
+        )
+    }
+
+    /// Creates a vulkan image with the given number of samples per texel, used for MSAA attachments.
+    ///
+    /// # Arguments
+    /// - `samples`: The sample count, has to match the render pass attachment.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_multisampled_image(
+        render_context: &RenderContext,
+        width: u32,
+        height: u32,
+        format: vk::Format,
+        tiling: vk::ImageTiling,
+        usage: vk::ImageUsageFlags,
+        required_memory_properties: vk::MemoryPropertyFlags,
+        samples: vk::SampleCountFlags,
+    ) -> (vk::Image, Allocation) {
+        Self::allocate_image(
+            render_context,
+            width,
+            height,
+            1,
+            format,
+            tiling,
+            usage,
+            required_memory_properties,
+            samples,
+        )
+    }
+
+    /// Creates a vulkan image with the given number of mip levels.
+    ///
+    /// # Arguments
+    /// - `mip_levels`: The number of mip levels, see `Texture::mip_level_count` for a full chain.
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_mipmapped_image(
+        render_context: &RenderContext,
+        width: u32,
+        height: u32,
+        mip_levels: u32,
+        format: vk::Format,
+        tiling: vk::ImageTiling,
+        usage: vk::ImageUsageFlags,
+        required_memory_properties: vk::MemoryPropertyFlags,
+    ) -> (vk::Image, Allocation) {
+        Self::allocate_image(
+            render_context,
+            width,
+            height,
+            mip_levels,
+            format,
+            tiling,
+            usage,
+            required_memory_properties,
+            vk::SampleCountFlags::TYPE_1,
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn allocate_image(
+        render_context: &RenderContext,
+        width: u32,
+        height: u32,
+        mip_levels: u32,
+        format: vk::Format,
+        tiling: vk::ImageTiling,
+        usage: vk::ImageUsageFlags,
+        required_memory_properties: vk::MemoryPropertyFlags,
+        samples: vk::SampleCountFlags,
+    ) -> (vk::Image, Allocation) {
         // Crate image.
         let image_create_info = vk::ImageCreateInfo::builder()
             .image_type(vk::ImageType::TYPE_2D)
@@ -165,9 +281,9 @@ impl Texture {
                 height,
                 depth: 1,
             })
-            .mip_levels(1)
+            .mip_levels(mip_levels)
             .array_layers(1)
-            .samples(vk::SampleCountFlags::TYPE_1)
+            .samples(samples)
             .tiling(tiling)
             .usage(usage)
             .sharing_mode(vk::SharingMode::EXCLUSIVE)
@@ -181,53 +297,67 @@ impl Texture {
                 .expect("Failed to create Texture Image!")
         };
 
-        // Allocate image memory.
+        // Place the image in a shared memory block.
         let image_memory_requirement = unsafe {
             render_context
                 .device()
                 .get_image_memory_requirements(texture_image)
         };
 
-        let memory_allocate_info = vk::MemoryAllocateInfo::builder()
-            .allocation_size(image_memory_requirement.size)
-            .memory_type_index(render_context.find_memory_type(
-                image_memory_requirement.memory_type_bits,
-                required_memory_properties,
-            ))
-            .build();
-
-        let texture_image_memory = unsafe {
-            render_context
-                .device()
-                .allocate_memory(&memory_allocate_info, None)
-                .expect("Failed to allocate Texture Image memory!")
-        };
+        let texture_image_memory = render_context.logical_device().allocate(
+            image_memory_requirement,
+            required_memory_properties,
+            AllocationKind::Image,
+        );
 
         unsafe {
             render_context
                 .device()
-                .bind_image_memory(texture_image, texture_image_memory, 0)
+                .bind_image_memory(
+                    texture_image,
+                    texture_image_memory.memory(),
+                    texture_image_memory.offset(),
+                )
                 .expect("Failed to bind Image Memmory!");
         }
 
         (texture_image, texture_image_memory)
     }
 
-    /// Transitions image layout.
+    /// Transitions image layout and waits until the transition is executed.
     ///
     /// # Arguments
     /// - `old_layout`: The current layout of the image.
     /// - `new_layout`: The desired layout of the image.
-    /// - `command_pool`: Command pool that is used to allocate a commandbuffer, to perform the transition, from.
-    /// - `submit_queue`: The queue on which the commandbuffer will be queued.
     pub fn transition_image_layout(
         render_context: &RenderContext,
         image: vk::Image,
         old_layout: vk::ImageLayout,
         new_layout: vk::ImageLayout,
     ) {
-        let command_buffer = begin_single_time_command(render_context);
+        submit_single_time_command(render_context, |command_buffer| {
+            Self::record_layout_transition(
+                render_context.device(),
+                command_buffer,
+                image,
+                old_layout,
+                new_layout,
+            )
+        });
+    }
 
+    /// Records an image layout transition into the given command buffer.
+    ///
+    /// # Arguments
+    /// - `old_layout`: The current layout of the image.
+    /// - `new_layout`: The desired layout of the image.
+    pub fn record_layout_transition(
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        image: vk::Image,
+        old_layout: vk::ImageLayout,
+        new_layout: vk::ImageLayout,
+    ) {
         let src_access_mask;
         let dst_access_mask;
         let source_stage;
@@ -279,7 +409,7 @@ impl Texture {
         }];
 
         unsafe {
-            render_context.device().cmd_pipeline_barrier(
+            device.cmd_pipeline_barrier(
                 command_buffer,
                 source_stage,
                 destination_stage,
@@ -289,52 +419,14 @@ impl Texture {
                 &image_barriers,
             );
         }
-
-        end_single_time_command(render_context, &command_buffer);
-    }
-
-    fn copy_buffer_to_image(
-        render_context: &RenderContext,
-        buffer: vk::Buffer,
-        image: vk::Image,
-        width: u32,
-        height: u32,
-    ) {
-        let command_buffer = begin_single_time_command(render_context);
-
-        let buffer_image_regions = [vk::BufferImageCopy {
-            image_subresource: vk::ImageSubresourceLayers {
-                aspect_mask: vk::ImageAspectFlags::COLOR,
-                mip_level: 0,
-                base_array_layer: 0,
-                layer_count: 1,
-            },
-            image_extent: vk::Extent3D {
-                width,
-                height,
-                depth: 1,
-            },
-            buffer_offset: 0,
-            buffer_image_height: 0,
-            buffer_row_length: 0,
-            image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
-        }];
-
-        unsafe {
-            render_context.device().cmd_copy_buffer_to_image(
-                command_buffer,
-                buffer,
-                image,
-                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-                &buffer_image_regions,
-            );
-        }
-
-        end_single_time_command(render_context, &command_buffer);
     }
 
     /// Creates a texture sampler that can be used to sample texel data from.
-    pub fn create_texture_sampler(device: &VkLogicalDevice) -> vk::Sampler {
+    ///
+    /// # Arguments
+    /// - `max_lod`: The last mip level that is sampled, `vk::LOD_CLAMP_NONE` samples every level of
+    /// the image view.
+    pub fn create_texture_sampler(device: &VkLogicalDevice, max_lod: f32) -> vk::Sampler {
         let sampler_create_info = vk::SamplerCreateInfo {
             s_type: vk::StructureType::SAMPLER_CREATE_INFO,
             p_next: ptr::null(),
@@ -351,7 +443,7 @@ impl Texture {
             compare_enable: vk::FALSE,
             compare_op: vk::CompareOp::ALWAYS,
             min_lod: 0.0,
-            max_lod: 0.0,
+            max_lod,
             border_color: vk::BorderColor::INT_OPAQUE_BLACK,
             unnormalized_coordinates: vk::FALSE,
         };
@@ -366,6 +458,19 @@ impl Texture {
     pub unsafe fn destroy(&self, device: &VkLogicalDevice) {
         self.image_view.destroy(device);
         device.destroy_image(self.image, None);
-        device.free_memory(self.device_memory, None);
+        device.free_allocation(&self.device_memory);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::engine::image::Texture;
+
+    #[test]
+    fn mip_level_count_reaches_one_texel() {
+        assert_eq!(Texture::mip_level_count(1, 1), 1);
+        assert_eq!(Texture::mip_level_count(2048, 2048), 12);
+        assert_eq!(Texture::mip_level_count(1024, 300), 11);
+        assert_eq!(Texture::mip_level_count(3, 5), 3);
     }
 }
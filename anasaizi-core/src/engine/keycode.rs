@@ -1,6 +1,6 @@
 use std::sync::Arc;
 use winit::{
-    dpi::PhysicalPosition,
+    dpi::{PhysicalPosition, PhysicalSize},
     event::{ElementState, KeyboardInput, ModifiersState, MouseButton},
 };
 
@@ -11,6 +11,8 @@ pub enum Event {
     MouseScroll(f32, f32),
     Keyboard(KeyboardInput),
     MouseInput(ElementState, MouseButton),
+    /// The window was resized to the inner size, a minimized window has a zero size.
+    Resize(PhysicalSize<u32>),
     Raw(Arc<winit::event::Event<'static, ()>>),
     Shutdown,
 }
@@ -72,4 +72,5 @@ pub struct GltfPBRShaderConstants {
     pub has_uvs: u32,
 
     pub texture_array_lenght: u32,
+    pub use_tex_lod: u32,
 }
@@ -1,5 +1,16 @@
+mod frame_data;
+mod render_graph;
 mod render_pipeline;
+mod render_target;
 mod renderer;
 
+pub use frame_data::{FrameData, FrameShaderResources};
+pub use render_graph::{
+    AttachmentFormat, AttachmentInfo, AttachmentSize, FrameContext, GraphPass, PassContext,
+    PassKind, RecordPass, RenderGraph, ResourceId,
+};
 pub use render_pipeline::{RenderContext, RenderPipeline};
-pub use renderer::{RenderLayer, FRAGMENT_SHADER, VERTEX_SHADER};
+pub use render_target::RenderTarget;
+pub use renderer::{
+    RenderLayer, DEFAULT_FRAMES_IN_FLIGHT, FRAGMENT_SHADER, MESH_PASS, UI_PASS, VERTEX_SHADER,
+};
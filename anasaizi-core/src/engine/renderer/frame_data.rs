@@ -0,0 +1,201 @@
+use crate::vulkan::{
+    Allocation, CommandBuffers, ShaderIo, VkCommandPool, VkLogicalDevice, VkPipeline,
+};
+use ash::{version::DeviceV1_0, vk};
+use std::{collections::HashMap, time::Instant};
+
+/// Handles to the uniform buffers and descriptor sets a shader created for one frame in flight,
+/// the shader io owns them.
+#[derive(Debug, Clone, Default)]
+pub struct FrameShaderResources {
+    /// The sets that are bound with the pipeline while rendering the frame, with their set index.
+    pub descriptor_sets: Vec<(u32, vk::DescriptorSet)>,
+    /// The uniform buffers of the frame with their memory, in the order they were added to the shader io.
+    pub uniform_buffers: Vec<(vk::Buffer, Allocation)>,
+}
+
+impl FrameShaderResources {
+    fn new(io: &ShaderIo, frame: usize) -> FrameShaderResources {
+        FrameShaderResources {
+            descriptor_sets: io.frame_descriptor_sets(frame),
+            uniform_buffers: io
+                .uniform_buffers
+                .iter()
+                .map(|buffer| (buffer.buffers(frame), buffer.buffers_memory(frame).clone()))
+                .collect(),
+        }
+    }
+
+    /// The descriptor set bound at the given set index.
+    pub fn descriptor_set(&self, set: u32) -> Option<vk::DescriptorSet> {
+        self.descriptor_sets
+            .iter()
+            .find(|(index, _)| *index == set)
+            .map(|(_, descriptor_set)| *descriptor_set)
+    }
+}
+
+/// The resources of one frame in flight, they are reused once the GPU finished the frame.
+///
+/// The uniform buffers and descriptor sets of the shaders are created once for every frame in flight,
+/// see `RenderLayer::frame_count`. The frame holds the handles of its own, by pipeline id, they may only
+/// be written once `wait` returned. `RenderLayer::on_update` waits for the next frame before it returns.
+pub struct FrameData {
+    index: usize,
+    command_pool: VkCommandPool,
+    command_buffers: CommandBuffers,
+
+    shader_resources: HashMap<u32, FrameShaderResources>,
+
+    image_available: vk::Semaphore,
+    render_finished: vk::Semaphore,
+    in_flight: vk::Fence,
+
+    /// When the frame was last submitted, its GPU scopes are placed relative to it.
+    submitted_at: Option<Instant>,
+}
+
+impl FrameData {
+    /// Creates the resources of the frame with the given index, the fence starts signaled.
+    pub fn new(device: &VkLogicalDevice, index: usize) -> FrameData {
+        let command_pool = VkCommandPool::create(device);
+        let command_buffers = CommandBuffers::create(device, &command_pool, 1);
+
+        let semaphore_create_info = vk::SemaphoreCreateInfo::builder();
+        let fence_create_info =
+            vk::FenceCreateInfo::builder().flags(vk::FenceCreateFlags::SIGNALED);
+
+        let (image_available, render_finished, in_flight) = unsafe {
+            (
+                device
+                    .create_semaphore(&semaphore_create_info, None)
+                    .expect("Failed to create Semaphore Object!"),
+                device
+                    .create_semaphore(&semaphore_create_info, None)
+                    .expect("Failed to create Semaphore Object!"),
+                device
+                    .create_fence(&fence_create_info, None)
+                    .expect("Failed to create Fence Object!"),
+            )
+        };
+
+        device.set_debug_name(command_buffers.get(0), &format!("Frame {}", index));
+        device.set_debug_name(in_flight, &format!("Frame {}/in flight", index));
+
+        FrameData {
+            index,
+            command_pool,
+            command_buffers,
+            shader_resources: HashMap::new(),
+            image_available,
+            render_finished,
+            in_flight,
+            submitted_at: None,
+        }
+    }
+
+    /// The index of the frame, the shader io keeps its per frame resources in this order.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Takes the handles of this frame's uniform buffers and descriptor sets from the shader of the pipeline.
+    ///
+    /// A pipeline registered before with the same id is replaced.
+    pub(crate) fn register_pipeline(&mut self, pipeline: &VkPipeline) {
+        self.shader_resources.insert(
+            pipeline.pipeline_id(),
+            FrameShaderResources::new(&pipeline.shader.io, self.index),
+        );
+    }
+
+    /// The uniform buffers and descriptor sets of the pipeline with the given id in this frame.
+    ///
+    /// Panics when the pipeline was not created by the `RenderLayer`.
+    pub fn shader_resources(&self, pipeline_id: u32) -> &FrameShaderResources {
+        self.shader_resources
+            .get(&pipeline_id)
+            .unwrap_or_else(|| panic!("Pipeline {} has no resources in this frame!", pipeline_id))
+    }
+
+    pub fn command_buffers(&self) -> &CommandBuffers {
+        &self.command_buffers
+    }
+
+    /// Signaled when the acquired swapchain image can be rendered to.
+    pub fn image_available(&self) -> vk::Semaphore {
+        self.image_available
+    }
+
+    /// Signaled when the frame finished rendering, presentation waits for it.
+    pub fn render_finished(&self) -> vk::Semaphore {
+        self.render_finished
+    }
+
+    /// Waits until the GPU finished the last submit of this frame, its resources can be reused after.
+    ///
+    /// Returns when that submit happened, only the first wait after a submit returns it.
+    pub fn wait(&mut self, device: &VkLogicalDevice) -> Option<Instant> {
+        unsafe {
+            device
+                .wait_for_fences(&[self.in_flight], true, u64::MAX)
+                .expect("Failed to wait for Fence!");
+        }
+
+        self.submitted_at.take()
+    }
+
+    /// Resets the command pool and begins recording the command buffer of the frame.
+    pub fn begin_recording(&mut self, device: &VkLogicalDevice) {
+        unsafe {
+            device
+                .reset_command_pool(*self.command_pool, vk::CommandPoolResetFlags::empty())
+                .expect("Failed to reset Command Pool!");
+        }
+
+        self.command_buffers.begin_recording(device, 0);
+    }
+
+    /// Submits the recorded command buffer, the fence is signaled when the GPU finished it.
+    ///
+    /// # Arguments
+    /// - `wait_semaphores`: Waited for before the color attachments are written.
+    /// - `signal_semaphores`: Signaled when the command buffer finished.
+    pub fn submit(
+        &mut self,
+        device: &VkLogicalDevice,
+        queue: vk::Queue,
+        wait_semaphores: &[vk::Semaphore],
+        signal_semaphores: &[vk::Semaphore],
+    ) {
+        let wait_stages =
+            vec![vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT; wait_semaphores.len()];
+        let command_buffers = [self.command_buffers.current()];
+
+        let submit_infos = [vk::SubmitInfo::builder()
+            .wait_semaphores(wait_semaphores)
+            .wait_dst_stage_mask(&wait_stages)
+            .command_buffers(&command_buffers)
+            .signal_semaphores(signal_semaphores)
+            .build()];
+
+        self.submitted_at = Some(Instant::now());
+
+        unsafe {
+            device
+                .reset_fences(&[self.in_flight])
+                .expect("Failed to reset Fence!");
+
+            device
+                .queue_submit(queue, &submit_infos, self.in_flight)
+                .expect("Failed to execute queue submit.");
+        }
+    }
+
+    pub(crate) unsafe fn destroy(&self, device: &VkLogicalDevice) {
+        device.destroy_semaphore(self.image_available, None);
+        device.destroy_semaphore(self.render_finished, None);
+        device.destroy_fence(self.in_flight, None);
+        self.command_pool.destroy(device);
+    }
+}
@@ -0,0 +1,1139 @@
+use crate::{
+    engine::{
+        image::Texture, renderer::render_target::RenderTarget, FrameData, GpuMeshMemory,
+        RenderContext, RenderPipeline, World,
+    },
+    libs::imgui::DrawData,
+    vulkan::{
+        cmd_buffer_barrier, cmd_image_barrier, Allocation, AttachmentUsage, CommandBuffers,
+        FrameBuffers, ImageAccess, MemoryAccess, TimestampQueries, VkImageView, VkLogicalDevice,
+        VkPipeline, VkRenderPass, VkRenderPassBuilder, VkSubpassDescriptor,
+    },
+    Error, Result,
+};
+use ash::{version::DeviceV1_0, vk};
+
+/// An image or buffer of a render graph, passes declare how they use it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ResourceId(usize);
+
+/// The format of a graph attachment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AttachmentFormat {
+    /// The format of the render target images.
+    Target,
+    /// The depth format the device supports.
+    Depth,
+    Format(vk::Format),
+}
+
+/// The size of a graph attachment.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AttachmentSize {
+    /// The extent of the render target.
+    Target,
+    /// The extent of the render target scaled by the factor, for example 0.5 for half resolution effects.
+    Scaled(f32),
+    Fixed(vk::Extent2D),
+}
+
+impl AttachmentSize {
+    fn extent(&self, target_extent: vk::Extent2D) -> vk::Extent2D {
+        match *self {
+            AttachmentSize::Target => target_extent,
+            AttachmentSize::Scaled(scale) => vk::Extent2D {
+                width: ((target_extent.width as f32 * scale) as u32).max(1),
+                height: ((target_extent.height as f32 * scale) as u32).max(1),
+            },
+            AttachmentSize::Fixed(extent) => extent,
+        }
+    }
+}
+
+/// Describes an image the render graph creates and owns, it is recreated when the target is resized.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AttachmentInfo {
+    pub format: AttachmentFormat,
+    pub size: AttachmentSize,
+    pub samples: vk::SampleCountFlags,
+}
+
+impl AttachmentInfo {
+    /// A single sampled color attachment with the extent of the render target.
+    pub fn color(format: AttachmentFormat) -> AttachmentInfo {
+        AttachmentInfo {
+            format,
+            size: AttachmentSize::Target,
+            samples: vk::SampleCountFlags::TYPE_1,
+        }
+    }
+
+    /// A single sampled depth attachment with the extent of the render target.
+    pub fn depth() -> AttachmentInfo {
+        AttachmentInfo::color(AttachmentFormat::Depth)
+    }
+
+    pub fn with_size(mut self, size: AttachmentSize) -> AttachmentInfo {
+        self.size = size;
+        self
+    }
+
+    pub fn with_samples(mut self, samples: vk::SampleCountFlags) -> AttachmentInfo {
+        self.samples = samples;
+        self
+    }
+}
+
+enum Resource {
+    /// The render target image of the frame.
+    Backbuffer,
+    Attachment {
+        name: String,
+        info: AttachmentInfo,
+    },
+    Buffer {
+        buffer: vk::Buffer,
+    },
+}
+
+/// Whether a pass renders with a render pass or dispatches compute work outside of one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PassKind {
+    Graphics,
+    Compute,
+}
+
+/// How a pass uses a resource.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ResourceUse {
+    ColorAttachment,
+    DepthAttachment,
+    ResolveAttachment,
+    /// Sampled by the shaders of the pass.
+    Sampled,
+    /// Read and written as storage image by the shaders of the pass.
+    Storage,
+    Buffer(MemoryAccess),
+}
+
+impl ResourceUse {
+    fn access(self, kind: PassKind) -> ImageAccess {
+        let shader_stage = match kind {
+            PassKind::Graphics => vk::PipelineStageFlags::FRAGMENT_SHADER,
+            PassKind::Compute => vk::PipelineStageFlags::COMPUTE_SHADER,
+        };
+
+        match self {
+            ResourceUse::ColorAttachment | ResourceUse::ResolveAttachment => ImageAccess::new(
+                MemoryAccess::COLOR_ATTACHMENT,
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            ),
+            ResourceUse::DepthAttachment => ImageAccess::new(
+                MemoryAccess::DEPTH_ATTACHMENT,
+                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            ),
+            ResourceUse::Sampled => ImageAccess::new(
+                MemoryAccess {
+                    stage: shader_stage,
+                    access: vk::AccessFlags::SHADER_READ,
+                },
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            ),
+            ResourceUse::Storage => ImageAccess::new(
+                MemoryAccess {
+                    stage: shader_stage,
+                    access: vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+                },
+                vk::ImageLayout::GENERAL,
+            ),
+            // Buffers have no layout.
+            ResourceUse::Buffer(access) => ImageAccess::new(access, vk::ImageLayout::UNDEFINED),
+        }
+    }
+
+    fn attachment_usage(self) -> Option<AttachmentUsage> {
+        match self {
+            ResourceUse::ColorAttachment => Some(AttachmentUsage::Color),
+            ResourceUse::DepthAttachment => Some(AttachmentUsage::Depth),
+            ResourceUse::ResolveAttachment => Some(AttachmentUsage::Resolve),
+            _ => None,
+        }
+    }
+
+    fn image_usage(self) -> vk::ImageUsageFlags {
+        match self {
+            ResourceUse::ColorAttachment | ResourceUse::ResolveAttachment => {
+                vk::ImageUsageFlags::COLOR_ATTACHMENT
+            }
+            ResourceUse::DepthAttachment => vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            ResourceUse::Sampled => vk::ImageUsageFlags::SAMPLED,
+            ResourceUse::Storage => vk::ImageUsageFlags::STORAGE,
+            ResourceUse::Buffer(_) => vk::ImageUsageFlags::empty(),
+        }
+    }
+}
+
+/// Records the commands of a pass.
+pub type RecordPass = Box<dyn FnMut(&mut PassContext)>;
+
+/// A pass of a render graph, it declares the resources it uses and records its commands.
+pub struct GraphPass {
+    name: String,
+    kind: PassKind,
+    resources: Vec<(ResourceId, ResourceUse)>,
+    record: Option<RecordPass>,
+}
+
+impl GraphPass {
+    /// A pass that draws inside a render pass of its attachments.
+    pub fn graphics(name: &str) -> GraphPass {
+        GraphPass::new(name, PassKind::Graphics)
+    }
+
+    /// A pass that dispatches compute work, it has no attachments.
+    pub fn compute(name: &str) -> GraphPass {
+        GraphPass::new(name, PassKind::Compute)
+    }
+
+    fn new(name: &str, kind: PassKind) -> GraphPass {
+        GraphPass {
+            name: name.to_string(),
+            kind,
+            resources: vec![],
+            record: None,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> PassKind {
+        self.kind
+    }
+
+    /// Renders into the color attachment, the attachments are bound in the order they are added.
+    pub fn write_color(self, resource: ResourceId) -> GraphPass {
+        self.with_use(resource, ResourceUse::ColorAttachment)
+    }
+
+    pub fn write_depth(self, resource: ResourceId) -> GraphPass {
+        self.with_use(resource, ResourceUse::DepthAttachment)
+    }
+
+    /// Resolves the multisampled color attachments into the attachment at the end of the pass,
+    /// one resolve attachment for every color attachment.
+    pub fn resolve_into(self, resource: ResourceId) -> GraphPass {
+        self.with_use(resource, ResourceUse::ResolveAttachment)
+    }
+
+    /// Samples an attachment that an earlier pass rendered.
+    pub fn read_texture(self, resource: ResourceId) -> GraphPass {
+        self.with_use(resource, ResourceUse::Sampled)
+    }
+
+    pub fn write_storage_image(self, resource: ResourceId) -> GraphPass {
+        self.with_use(resource, ResourceUse::Storage)
+    }
+
+    /// Reads an imported buffer, `access` is the stage and access the pass reads it with.
+    pub fn read_buffer(self, resource: ResourceId, access: MemoryAccess) -> GraphPass {
+        self.with_use(resource, ResourceUse::Buffer(access))
+    }
+
+    /// Writes an imported buffer, `access` is the stage and access the pass writes it with.
+    pub fn write_buffer(self, resource: ResourceId, access: MemoryAccess) -> GraphPass {
+        self.with_use(resource, ResourceUse::Buffer(access))
+    }
+
+    /// Sets the function that records the commands of the pass.
+    pub fn with_record<F: FnMut(&mut PassContext) + 'static>(mut self, record: F) -> GraphPass {
+        self.record = Some(Box::new(record));
+        self
+    }
+
+    fn with_use(mut self, resource: ResourceId, resource_use: ResourceUse) -> GraphPass {
+        self.resources.push((resource, resource_use));
+        self
+    }
+}
+
+/// What every pass of a frame records with.
+pub struct FrameContext<'a> {
+    pub device: &'a VkLogicalDevice,
+    pub command_buffers: &'a CommandBuffers,
+    /// The frame in flight that is recorded, the passes bind its descriptor sets.
+    pub frame_data: &'a FrameData,
+    pub world: &'a World,
+    pub pipelines: &'a [VkPipeline],
+    pub ui_pipeline: Option<&'a VkPipeline>,
+    pub ui_mesh: Option<&'a GpuMeshMemory>,
+    pub ui_data: Option<&'a DrawData>,
+}
+
+/// The context a pass records its commands with.
+pub struct PassContext<'a, 'f> {
+    pub frame: &'f FrameContext<'a>,
+    pub render_pipeline: RenderPipeline,
+    /// The extent of the attachments of the pass.
+    pub extent: vk::Extent2D,
+    image_views: &'f [vk::ImageView],
+}
+
+impl<'a, 'f> PassContext<'a, 'f> {
+    pub fn command_buffer(&self) -> vk::CommandBuffer {
+        self.frame.command_buffers.current()
+    }
+
+    /// The image view of a graph image in this frame, for example to sample the output of an earlier pass.
+    pub fn image_view(&self, resource: ResourceId) -> vk::ImageView {
+        self.image_views[resource.0]
+    }
+}
+
+/// A layout transition or memory dependency that is recorded before a pass.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Barrier {
+    resource: ResourceId,
+    src: ImageAccess,
+    dst: ImageAccess,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AttachmentPlan {
+    resource: ResourceId,
+    usage: AttachmentUsage,
+    load_op: vk::AttachmentLoadOp,
+    store_op: vk::AttachmentStoreOp,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PassPlan {
+    barriers: Vec<Barrier>,
+    /// The attachments of the render pass, in attachment order.
+    attachments: Vec<AttachmentPlan>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct GraphPlan {
+    passes: Vec<PassPlan>,
+    final_barriers: Vec<Barrier>,
+}
+
+/// The accesses made to a resource since its last write, used to derive barriers.
+#[derive(Clone, Copy)]
+struct ResourceState {
+    layout: vk::ImageLayout,
+    last_write: Option<MemoryAccess>,
+    reads: Option<MemoryAccess>,
+}
+
+impl ResourceState {
+    /// Updates the state for the next access and returns the barrier it needs, if any.
+    fn access(&mut self, dst: ImageAccess) -> Option<ImageAccess> {
+        let previous = union(self.last_write, self.reads);
+        let is_write = dst.access.is_write();
+
+        let needs_barrier = self.layout != dst.layout
+            || (is_write && previous.is_some())
+            || (!is_write && self.last_write.is_some() && !covers(self.reads, dst.access));
+
+        let src = ImageAccess::new(previous.unwrap_or(MemoryAccess::NONE), self.layout);
+
+        if is_write {
+            self.last_write = Some(dst.access);
+            self.reads = None;
+        } else {
+            self.reads = union(self.reads, Some(dst.access));
+        }
+        self.layout = dst.layout;
+
+        needs_barrier.then(|| src)
+    }
+}
+
+fn union(a: Option<MemoryAccess>, b: Option<MemoryAccess>) -> Option<MemoryAccess> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(MemoryAccess {
+            stage: a.stage | b.stage,
+            access: a.access | b.access,
+        }),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Whether earlier reads already waited for the stage and access.
+fn covers(reads: Option<MemoryAccess>, access: MemoryAccess) -> bool {
+    reads.map_or(false, |reads| {
+        reads.stage.contains(access.stage) && reads.access.contains(access.access)
+    })
+}
+
+/// An image of the graph that was created for the current target.
+struct GraphImage {
+    image: vk::Image,
+    memory: Allocation,
+    view: VkImageView,
+    format: vk::Format,
+    extent: vk::Extent2D,
+}
+
+struct CompiledPass {
+    plan: PassPlan,
+    render_pass: Option<VkRenderPass>,
+    /// One framebuffer for every target image when the pass renders into the backbuffer, one otherwise.
+    framebuffers: Option<FrameBuffers>,
+    extent: vk::Extent2D,
+    clear_values: Vec<vk::ClearValue>,
+}
+
+/// A frame described as passes that declare the images and buffers they use.
+///
+/// Passes run in the order they are added. From their declarations the graph derives the render passes,
+/// framebuffers, load and store operations and the barriers between passes, and it creates the images
+/// that are not imported. The backbuffer is the render target image of the frame, the graph leaves it in
+/// the layout the target expects.
+pub struct RenderGraph {
+    resources: Vec<Resource>,
+    passes: Vec<GraphPass>,
+
+    images: Vec<Option<GraphImage>>,
+    compiled: Vec<CompiledPass>,
+    final_barriers: Vec<Barrier>,
+
+    backbuffer_images: Vec<vk::Image>,
+    backbuffer_views: Vec<vk::ImageView>,
+    backbuffer_format: vk::Format,
+    depth_format: vk::Format,
+    extent: vk::Extent2D,
+}
+
+impl RenderGraph {
+    pub fn new() -> RenderGraph {
+        RenderGraph {
+            resources: vec![Resource::Backbuffer],
+            passes: vec![],
+            images: vec![],
+            compiled: vec![],
+            final_barriers: vec![],
+            backbuffer_images: vec![],
+            backbuffer_views: vec![],
+            backbuffer_format: vk::Format::UNDEFINED,
+            depth_format: vk::Format::UNDEFINED,
+            extent: vk::Extent2D::default(),
+        }
+    }
+
+    /// The render target image of the frame.
+    pub fn backbuffer(&self) -> ResourceId {
+        ResourceId(0)
+    }
+
+    /// Declares an image that the graph creates when it is compiled.
+    pub fn create_attachment(&mut self, name: &str, info: AttachmentInfo) -> ResourceId {
+        self.resources.push(Resource::Attachment {
+            name: name.to_string(),
+            info,
+        });
+        ResourceId(self.resources.len() - 1)
+    }
+
+    /// Declares a buffer that is owned outside of the graph, passes that use it are ordered by barriers.
+    pub fn import_buffer(&mut self, buffer: vk::Buffer) -> ResourceId {
+        self.resources.push(Resource::Buffer { buffer });
+        ResourceId(self.resources.len() - 1)
+    }
+
+    /// Adds a pass after the other passes, it is created by the next `compile`.
+    pub fn add_pass(&mut self, pass: GraphPass) {
+        self.passes.push(pass);
+    }
+
+    /// Adds a pass before the pass with the given name, it is created by the next `compile`.
+    ///
+    /// Returns `Error::UnknownPass` when the graph has no pass with that name.
+    pub fn insert_pass_before(&mut self, before: &str, pass: GraphPass) -> Result<()> {
+        let index = self
+            .passes
+            .iter()
+            .position(|pass| pass.name == before)
+            .ok_or_else(|| Error::UnknownPass(before.to_string()))?;
+
+        self.passes.insert(index, pass);
+
+        Ok(())
+    }
+
+    pub fn passes(&self) -> impl Iterator<Item = &GraphPass> {
+        self.passes.iter()
+    }
+
+    /// The render pass of a graphics pass, pipelines of that pass are created with it.
+    pub fn render_pass(&self, pass_name: &str) -> Option<&VkRenderPass> {
+        self.passes
+            .iter()
+            .zip(self.compiled.iter())
+            .find(|(pass, _)| pass.name == pass_name)
+            .and_then(|(_, compiled)| compiled.render_pass.as_ref())
+    }
+
+    /// Creates the images, render passes and framebuffers of the graph for the render target.
+    ///
+    /// Call it again after the target is recreated, the resources of the previous compile have to be
+    /// destroyed with `destroy` first.
+    pub fn compile(&mut self, render_context: &RenderContext, target: &RenderTarget) {
+        let device = render_context.logical_device();
+
+        self.extent = target.extent();
+        self.backbuffer_format = target.image_format();
+        self.backbuffer_images = target.images().clone();
+        self.backbuffer_views = target.image_views().iter().map(|view| **view).collect();
+        self.depth_format = device.find_depth_format(render_context.raw_instance());
+
+        self.images = (0..self.resources.len())
+            .map(|index| self.create_image(render_context, ResourceId(index)))
+            .collect();
+
+        let plan = self.plan(target.final_access());
+        self.final_barriers = plan.final_barriers;
+
+        self.compiled = plan
+            .passes
+            .into_iter()
+            .zip(self.passes.iter())
+            .map(|(pass_plan, pass)| self.compile_pass(device, pass, pass_plan))
+            .collect();
+    }
+
+    fn create_image(
+        &self,
+        render_context: &RenderContext,
+        resource: ResourceId,
+    ) -> Option<GraphImage> {
+        let (name, info) = match &self.resources[resource.0] {
+            Resource::Attachment { name, info } => (name, info),
+            _ => return None,
+        };
+
+        let uses: Vec<(usize, ResourceUse)> = self
+            .passes
+            .iter()
+            .enumerate()
+            .flat_map(|(index, pass)| {
+                pass.resources
+                    .iter()
+                    .filter(|(id, _)| *id == resource)
+                    .map(move |(_, resource_use)| (index, *resource_use))
+            })
+            .collect();
+
+        if uses.is_empty() {
+            return None;
+        }
+
+        let mut usage = uses
+            .iter()
+            .fold(vk::ImageUsageFlags::empty(), |usage, (_, resource_use)| {
+                usage | resource_use.image_usage()
+            });
+
+        // Attachments of a single pass never leave the render pass.
+        if uses.iter().all(|(index, _)| *index == uses[0].0)
+            && !usage.intersects(vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::STORAGE)
+        {
+            usage |= vk::ImageUsageFlags::TRANSIENT_ATTACHMENT;
+        }
+
+        let format = self.format(info.format);
+        let extent = info.size.extent(self.extent);
+
+        let (image, memory) = Texture::create_multisampled_image(
+            render_context,
+            extent.width,
+            extent.height,
+            format,
+            vk::ImageTiling::OPTIMAL,
+            usage,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            info.samples,
+        );
+        render_context.logical_device().set_debug_name(image, name);
+
+        let aspect = if info.format == AttachmentFormat::Depth {
+            vk::ImageAspectFlags::DEPTH
+        } else {
+            vk::ImageAspectFlags::COLOR
+        };
+        let view = VkImageView::create(render_context.device(), image, format, aspect);
+
+        Some(GraphImage {
+            image,
+            memory,
+            view,
+            format,
+            extent,
+        })
+    }
+
+    fn compile_pass(
+        &self,
+        device: &VkLogicalDevice,
+        pass: &GraphPass,
+        plan: PassPlan,
+    ) -> CompiledPass {
+        if pass.kind == PassKind::Compute || plan.attachments.is_empty() {
+            return CompiledPass {
+                plan,
+                render_pass: None,
+                framebuffers: None,
+                extent: self.extent,
+                clear_values: vec![],
+            };
+        }
+
+        let mut builder = VkRenderPassBuilder::builder().with_debug_name(&pass.name);
+        let mut subpass = VkSubpassDescriptor::new();
+        let mut clear_values = Vec::with_capacity(plan.attachments.len());
+
+        for (number, attachment) in plan.attachments.iter().enumerate() {
+            let (format, samples) = self.attachment_format(attachment.resource);
+            let layout = match attachment.usage {
+                AttachmentUsage::Depth => vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                AttachmentUsage::Color | AttachmentUsage::Resolve => {
+                    vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+                }
+            };
+
+            // The barriers before the pass transition the attachments, the render pass keeps their layouts.
+            let description = vk::AttachmentDescription::builder()
+                .format(format)
+                .samples(samples)
+                .load_op(attachment.load_op)
+                .store_op(attachment.store_op)
+                .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+                .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+                .initial_layout(layout)
+                .final_layout(layout)
+                .build();
+
+            builder = builder.add_attachment(number as u32, attachment.usage, description);
+            subpass = match attachment.usage {
+                AttachmentUsage::Color => subpass.with_color(number),
+                AttachmentUsage::Depth => subpass.with_depth(number),
+                AttachmentUsage::Resolve => subpass.with_resolve(number),
+            };
+
+            clear_values.push(match attachment.usage {
+                AttachmentUsage::Depth => vk::ClearValue {
+                    depth_stencil: vk::ClearDepthStencilValue {
+                        depth: 1.0,
+                        stencil: 0,
+                    },
+                },
+                AttachmentUsage::Color | AttachmentUsage::Resolve => vk::ClearValue {
+                    color: vk::ClearColorValue {
+                        float32: [0.0, 0.0, 0.0, 0.0],
+                    },
+                },
+            });
+        }
+
+        let render_pass = builder.add_subpasses(vec![subpass], &[]).build(device);
+
+        let extent = self.resource_extent(plan.attachments[0].resource);
+        let uses_backbuffer = plan
+            .attachments
+            .iter()
+            .any(|attachment| attachment.resource == self.backbuffer());
+        let framebuffer_count = if uses_backbuffer {
+            self.backbuffer_views.len()
+        } else {
+            1
+        };
+
+        let attachments: Vec<Vec<vk::ImageView>> = (0..framebuffer_count)
+            .map(|image_index| {
+                plan.attachments
+                    .iter()
+                    .map(|attachment| self.view(attachment.resource, image_index))
+                    .collect()
+            })
+            .collect();
+
+        let framebuffers = FrameBuffers::create(device, &render_pass, &attachments, &extent);
+
+        CompiledPass {
+            plan,
+            render_pass: Some(render_pass),
+            framebuffers: Some(framebuffers),
+            extent,
+            clear_values,
+        }
+    }
+
+    /// Derives the attachments of the passes and the barriers between them, without touching the device.
+    ///
+    /// An attachment is cleared by the first pass that writes it and loaded by later ones, it is stored
+    /// when a later pass uses it. Images are transitioned by barriers before a pass, so the render passes
+    /// never change layouts.
+    fn plan(&self, final_access: ImageAccess) -> GraphPlan {
+        let mut states: Vec<ResourceState> = self
+            .resources
+            .iter()
+            .enumerate()
+            .map(|(index, resource)| ResourceState {
+                layout: vk::ImageLayout::UNDEFINED,
+                last_write: match resource {
+                    // The image is acquired by a semaphore that is waited on at this stage.
+                    Resource::Backbuffer => Some(MemoryAccess {
+                        stage: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                        access: vk::AccessFlags::empty(),
+                    }),
+                    // The previous frame may still use the image.
+                    Resource::Attachment { .. } => self.last_access(ResourceId(index)),
+                    Resource::Buffer { .. } => None,
+                },
+                reads: None,
+            })
+            .collect();
+        let mut written = vec![false; self.resources.len()];
+
+        let passes = self
+            .passes
+            .iter()
+            .enumerate()
+            .map(|(pass_index, pass)| {
+                let mut barriers = vec![];
+                let mut attachments = vec![];
+
+                for &(resource, resource_use) in pass.resources.iter() {
+                    let dst = resource_use.access(pass.kind);
+
+                    if let Some(src) = states[resource.0].access(dst) {
+                        barriers.push(Barrier { resource, src, dst });
+                    }
+
+                    if let Some(usage) = resource_use.attachment_usage() {
+                        let load_op = if written[resource.0] {
+                            vk::AttachmentLoadOp::LOAD
+                        } else if usage == AttachmentUsage::Resolve {
+                            vk::AttachmentLoadOp::DONT_CARE
+                        } else {
+                            vk::AttachmentLoadOp::CLEAR
+                        };
+
+                        let store_op = if resource == self.backbuffer()
+                            || self.is_used_after(resource, pass_index)
+                        {
+                            vk::AttachmentStoreOp::STORE
+                        } else {
+                            vk::AttachmentStoreOp::DONT_CARE
+                        };
+
+                        attachments.push(AttachmentPlan {
+                            resource,
+                            usage,
+                            load_op,
+                            store_op,
+                        });
+                    }
+
+                    if dst.access.is_write() {
+                        written[resource.0] = true;
+                    }
+                }
+
+                PassPlan {
+                    barriers,
+                    attachments,
+                }
+            })
+            .collect();
+
+        let backbuffer = self.backbuffer();
+        let final_barriers = states[backbuffer.0]
+            .access(final_access)
+            .filter(|_| written[backbuffer.0])
+            .map(|src| Barrier {
+                resource: backbuffer,
+                src,
+                dst: final_access,
+            })
+            .into_iter()
+            .collect();
+
+        GraphPlan {
+            passes,
+            final_barriers,
+        }
+    }
+
+    /// The access of the last pass that uses the resource.
+    fn last_access(&self, resource: ResourceId) -> Option<MemoryAccess> {
+        self.passes.iter().rev().find_map(|pass| {
+            pass.resources
+                .iter()
+                .rev()
+                .find(|(id, _)| *id == resource)
+                .map(|(_, resource_use)| resource_use.access(pass.kind).access)
+        })
+    }
+
+    fn is_used_after(&self, resource: ResourceId, pass_index: usize) -> bool {
+        self.passes[pass_index + 1..]
+            .iter()
+            .any(|pass| pass.resources.iter().any(|(id, _)| *id == resource))
+    }
+
+    fn format(&self, format: AttachmentFormat) -> vk::Format {
+        match format {
+            AttachmentFormat::Target => self.backbuffer_format,
+            AttachmentFormat::Depth => self.depth_format,
+            AttachmentFormat::Format(format) => format,
+        }
+    }
+
+    fn attachment_format(&self, resource: ResourceId) -> (vk::Format, vk::SampleCountFlags) {
+        match &self.resources[resource.0] {
+            Resource::Attachment { info, .. } => (self.format(info.format), info.samples),
+            _ => (self.backbuffer_format, vk::SampleCountFlags::TYPE_1),
+        }
+    }
+
+    fn resource_extent(&self, resource: ResourceId) -> vk::Extent2D {
+        self.images[resource.0]
+            .as_ref()
+            .map_or(self.extent, |image| image.extent)
+    }
+
+    fn view(&self, resource: ResourceId, image_index: usize) -> vk::ImageView {
+        match &self.resources[resource.0] {
+            Resource::Backbuffer => self.backbuffer_views[image_index],
+            _ => self.images[resource.0]
+                .as_ref()
+                .map_or(vk::ImageView::null(), |image| *image.view),
+        }
+    }
+
+    fn record_barrier(
+        &self,
+        device: &VkLogicalDevice,
+        command_buffer: vk::CommandBuffer,
+        barrier: &Barrier,
+        image_index: usize,
+    ) {
+        let (image, format) = match &self.resources[barrier.resource.0] {
+            Resource::Buffer { buffer } => {
+                cmd_buffer_barrier(
+                    device,
+                    command_buffer,
+                    *buffer,
+                    barrier.src.access,
+                    barrier.dst.access,
+                );
+                return;
+            }
+            Resource::Backbuffer => (self.backbuffer_images[image_index], self.backbuffer_format),
+            Resource::Attachment { .. } => {
+                let image = self.images[barrier.resource.0]
+                    .as_ref()
+                    .expect("Failed to find the image of a graph attachment!");
+                (image.image, image.format)
+            }
+        };
+
+        let subresource_range = vk::ImageSubresourceRange {
+            aspect_mask: aspect_flags(format),
+            base_mip_level: 0,
+            level_count: 1,
+            base_array_layer: 0,
+            layer_count: 1,
+        };
+
+        cmd_image_barrier(
+            device,
+            command_buffer,
+            image,
+            subresource_range,
+            barrier.src,
+            barrier.dst,
+        );
+    }
+
+    /// Records the passes into the current command buffer.
+    ///
+    /// # Arguments
+    /// - `image_index`: The render target image of this frame.
+    /// - `timestamps`: Measures every pass on the GPU when profiling.
+    pub fn execute(
+        &mut self,
+        frame: &FrameContext,
+        image_index: usize,
+        mut timestamps: Option<&mut TimestampQueries>,
+    ) {
+        let device = frame.device;
+        let command_buffer = frame.command_buffers.current();
+        let image_views: Vec<vk::ImageView> = (0..self.resources.len())
+            .map(|index| self.view(ResourceId(index), image_index))
+            .collect();
+
+        // Taken out, so the record functions can be called while the graph records barriers.
+        let mut passes = std::mem::take(&mut self.passes);
+
+        for (pass, compiled) in passes.iter_mut().zip(self.compiled.iter()) {
+            for barrier in compiled.plan.barriers.iter() {
+                self.record_barrier(device, command_buffer, barrier, image_index);
+            }
+
+            device.begin_debug_label(command_buffer, &pass.name);
+            let scope = timestamps.as_mut().and_then(|timestamps| {
+                timestamps.begin_scope(device, command_buffer, frame.frame_data.index(), &pass.name)
+            });
+
+            if let (Some(render_pass), Some(framebuffers)) = (
+                compiled.render_pass.as_ref(),
+                compiled.framebuffers.as_ref(),
+            ) {
+                let framebuffer = if framebuffers.len() > 1 {
+                    framebuffers.get(image_index)
+                } else {
+                    framebuffers.get(0)
+                };
+
+                frame.command_buffers.begin_render_pass(
+                    device,
+                    render_pass,
+                    compiled.extent,
+                    framebuffer,
+                    &compiled.clear_values,
+                );
+            }
+
+            if let Some(record) = pass.record.as_mut() {
+                let mut context = PassContext {
+                    frame,
+                    render_pipeline: RenderPipeline::new(device, &command_buffer, frame.frame_data),
+                    extent: compiled.extent,
+                    image_views: &image_views,
+                };
+                record(&mut context);
+            }
+
+            if compiled.render_pass.is_some() {
+                frame.command_buffers.end_render_pass(device);
+            }
+
+            if let Some(timestamps) = timestamps.as_ref() {
+                timestamps.end_scope(device, command_buffer, frame.frame_data.index(), scope);
+            }
+            device.end_debug_label(command_buffer);
+        }
+
+        self.passes = passes;
+
+        for barrier in self.final_barriers.iter() {
+            self.record_barrier(device, command_buffer, barrier, image_index);
+        }
+    }
+
+    /// Destroys the images, render passes and framebuffers, the passes are kept for the next `compile`.
+    pub(crate) unsafe fn destroy(&self, device: &VkLogicalDevice) {
+        for compiled in self.compiled.iter() {
+            if let Some(framebuffers) = compiled.framebuffers.as_ref() {
+                framebuffers.destroy(device);
+            }
+            if let Some(render_pass) = compiled.render_pass.as_ref() {
+                render_pass.destroy(device);
+            }
+        }
+
+        for image in self.images.iter().flatten() {
+            image.view.destroy(device);
+            device.destroy_image(image.image, None);
+            device.free_allocation(&image.memory);
+        }
+    }
+}
+
+/// The aspects of an image with the format, stencil formats are transitioned with both aspects.
+fn aspect_flags(format: vk::Format) -> vk::ImageAspectFlags {
+    match format {
+        vk::Format::D16_UNORM | vk::Format::X8_D24_UNORM_PACK32 | vk::Format::D32_SFLOAT => {
+            vk::ImageAspectFlags::DEPTH
+        }
+        vk::Format::D16_UNORM_S8_UINT
+        | vk::Format::D24_UNORM_S8_UINT
+        | vk::Format::D32_SFLOAT_S8_UINT => {
+            vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+        }
+        _ => vk::ImageAspectFlags::COLOR,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        engine::renderer::render_graph::{
+            aspect_flags, AttachmentFormat, AttachmentInfo, GraphPass, RenderGraph,
+        },
+        vulkan::{AttachmentUsage, ImageAccess, MemoryAccess},
+    };
+    use ash::vk;
+
+    fn present() -> ImageAccess {
+        ImageAccess::new(MemoryAccess::PRESENT, vk::ImageLayout::PRESENT_SRC_KHR)
+    }
+
+    #[test]
+    fn later_passes_load_what_earlier_passes_wrote() {
+        let mut graph = RenderGraph::new();
+        let backbuffer = graph.backbuffer();
+        let depth = graph.create_attachment("Depth", AttachmentInfo::depth());
+        graph.add_pass(
+            GraphPass::graphics("Meshes")
+                .write_color(backbuffer)
+                .write_depth(depth),
+        );
+        graph.add_pass(GraphPass::graphics("UI").write_color(backbuffer));
+
+        let plan = graph.plan(present());
+        let meshes = &plan.passes[0];
+        let ui = &plan.passes[1];
+
+        assert_eq!(meshes.attachments[0].load_op, vk::AttachmentLoadOp::CLEAR);
+        assert_eq!(meshes.attachments[0].store_op, vk::AttachmentStoreOp::STORE);
+        assert_eq!(meshes.attachments[1].usage, AttachmentUsage::Depth);
+        assert_eq!(
+            meshes.attachments[1].store_op,
+            vk::AttachmentStoreOp::DONT_CARE
+        );
+        assert_eq!(meshes.barriers[0].src.layout, vk::ImageLayout::UNDEFINED);
+
+        assert_eq!(ui.attachments[0].load_op, vk::AttachmentLoadOp::LOAD);
+        assert_eq!(ui.barriers.len(), 1);
+        assert_eq!(ui.barriers[0].src.access, MemoryAccess::COLOR_ATTACHMENT);
+
+        assert_eq!(plan.final_barriers.len(), 1);
+        assert_eq!(plan.final_barriers[0].dst, present());
+    }
+
+    #[test]
+    fn multisampled_color_is_resolved_and_discarded() {
+        let mut graph = RenderGraph::new();
+        let backbuffer = graph.backbuffer();
+        let color = graph.create_attachment(
+            "Color",
+            AttachmentInfo::color(AttachmentFormat::Target)
+                .with_samples(vk::SampleCountFlags::TYPE_4),
+        );
+        graph.add_pass(
+            GraphPass::graphics("Meshes")
+                .write_color(color)
+                .resolve_into(backbuffer),
+        );
+
+        let plan = graph.plan(present());
+        let attachments = &plan.passes[0].attachments;
+
+        assert_eq!(attachments[0].store_op, vk::AttachmentStoreOp::DONT_CARE);
+        assert_eq!(attachments[1].usage, AttachmentUsage::Resolve);
+        assert_eq!(attachments[1].load_op, vk::AttachmentLoadOp::DONT_CARE);
+        assert_eq!(attachments[1].store_op, vk::AttachmentStoreOp::STORE);
+    }
+
+    #[test]
+    fn sampled_attachments_are_transitioned() {
+        let mut graph = RenderGraph::new();
+        let backbuffer = graph.backbuffer();
+        let shadow = graph.create_attachment("Shadow", AttachmentInfo::depth());
+        graph.add_pass(GraphPass::graphics("Shadow").write_depth(shadow));
+        graph.add_pass(
+            GraphPass::graphics("Meshes")
+                .read_texture(shadow)
+                .write_color(backbuffer),
+        );
+
+        let plan = graph.plan(present());
+
+        assert_eq!(
+            plan.passes[0].attachments[0].store_op,
+            vk::AttachmentStoreOp::STORE
+        );
+
+        let barrier = plan.passes[1].barriers[0];
+        assert_eq!(barrier.resource, shadow);
+        assert_eq!(
+            barrier.src.layout,
+            vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL
+        );
+        assert_eq!(
+            barrier.dst.layout,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+        );
+        // The sampled image is not an attachment of the render pass.
+        assert_eq!(plan.passes[1].attachments.len(), 1);
+    }
+
+    #[test]
+    fn buffer_reads_wait_for_writes_once() {
+        let mut graph = RenderGraph::new();
+        let backbuffer = graph.backbuffer();
+        let particles = graph.import_buffer(vk::Buffer::null());
+        graph.add_pass(
+            GraphPass::compute("Simulate").write_buffer(particles, MemoryAccess::COMPUTE_WRITE),
+        );
+        graph.add_pass(
+            GraphPass::graphics("Particles")
+                .read_buffer(particles, MemoryAccess::VERTEX_INPUT)
+                .write_color(backbuffer),
+        );
+        graph.add_pass(
+            GraphPass::graphics("Trails")
+                .read_buffer(particles, MemoryAccess::VERTEX_INPUT)
+                .write_color(backbuffer),
+        );
+
+        let plan = graph.plan(present());
+
+        assert!(plan.passes[0].barriers.is_empty());
+        assert_eq!(
+            plan.passes[1].barriers[0].src.access,
+            MemoryAccess::COMPUTE_WRITE
+        );
+        assert!(plan.passes[2]
+            .barriers
+            .iter()
+            .all(|barrier| barrier.resource != particles));
+    }
+
+    #[test]
+    fn stencil_formats_include_the_stencil_aspect() {
+        assert_eq!(
+            aspect_flags(vk::Format::D24_UNORM_S8_UINT),
+            vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+        );
+        assert_eq!(
+            aspect_flags(vk::Format::D32_SFLOAT),
+            vk::ImageAspectFlags::DEPTH
+        );
+        assert_eq!(
+            aspect_flags(vk::Format::B8G8R8A8_SRGB),
+            vk::ImageAspectFlags::COLOR
+        );
+    }
+
+    #[test]
+    fn inserting_before_an_unknown_pass_fails() {
+        let mut graph = RenderGraph::new();
+        let backbuffer = graph.backbuffer();
+        graph.add_pass(GraphPass::graphics("Meshes").write_color(backbuffer));
+
+        assert!(graph
+            .insert_pass_before("Mesh", GraphPass::graphics("Shadow"))
+            .is_err());
+        assert!(graph
+            .insert_pass_before("Meshes", GraphPass::graphics("Shadow"))
+            .is_ok());
+        assert_eq!(graph.passes().next().unwrap().name(), "Shadow");
+    }
+}
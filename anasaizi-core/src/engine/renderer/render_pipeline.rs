@@ -1,13 +1,21 @@
 use crate::{
-    engine::GpuMeshMemory,
+    engine::{FrameData, GpuMeshMemory, ObjectDescriptorSet},
     libs::imgui::DrawData,
-    vulkan::{CommandBuffers, VkCommandPool, VkInstance, VkLogicalDevice, VkPipeline, VkQueue},
+    utils::any_as_u8_slice,
+    vulkan::{
+        cmd_buffer_barrier, cmd_image_barrier, CommandBuffers, ComputePipeline, ImageAccess,
+        MemoryAccess, ShaderIo, UploadContext, VkCommandPool, VkInstance, VkLogicalDevice,
+        VkPipeline, VkQueue,
+    },
 };
 use ash::{version::DeviceV1_0, vk, vk::CommandBuffer};
 
 use crate::vulkan::VkQueueFamilyIndices;
 use serde::Serialize;
-use std::ptr;
+use std::{
+    ptr,
+    sync::{Arc, Mutex, MutexGuard},
+};
 
 pub struct RenderPipeline {
     active_command_buffer: *const CommandBuffer,
@@ -19,14 +27,14 @@ pub struct RenderPipeline {
     pub index_offset: u32,
     pub vertex_offset: u32,
 
-    active_image_index: usize,
+    frame_data: *const FrameData,
 }
 
 impl RenderPipeline {
     pub fn new(
         device: &VkLogicalDevice,
         command_buffer: &CommandBuffer,
-        active_image: usize,
+        frame_data: &FrameData,
     ) -> RenderPipeline {
         RenderPipeline {
             active_command_buffer: command_buffer,
@@ -38,13 +46,149 @@ impl RenderPipeline {
             index_count: 0,
             index_offset: 0,
             vertex_offset: 0,
-            active_image_index: active_image,
+            frame_data,
         }
     }
 
+    /// Binds the pipeline and the descriptor sets of its shader for the active frame.
     pub fn bind_pipeline(&mut self, pipeline: &VkPipeline, command_buffer: &CommandBuffers) {
         command_buffer.bind_pipeline(self.device(), pipeline);
         self.active_pipeline = pipeline;
+        self.bind_descriptor_sets();
+    }
+
+    /// Binds a descriptor set at the given set index of the active pipeline, the other sets stay bound.
+    ///
+    /// # Arguments
+    /// - `dynamic_offsets`: One offset for every dynamic buffer of the set, in binding order.
+    pub fn bind_descriptor_set(
+        &self,
+        set: u32,
+        descriptor_set: vk::DescriptorSet,
+        dynamic_offsets: &[u32],
+    ) {
+        unsafe {
+            self.device().cmd_bind_descriptor_sets(
+                *self.active_command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.active_pipeline().layout(),
+                set,
+                &[descriptor_set],
+                dynamic_offsets,
+            );
+        }
+    }
+
+    /// Binds the per object set of a mesh for the active frame.
+    ///
+    /// Without dynamic offsets the dynamic buffers of the set start at their first element.
+    pub fn bind_object_descriptor_set(
+        &self,
+        object_set: &ObjectDescriptorSet,
+        dynamic_offsets: Option<&[u32]>,
+    ) {
+        let zero_offsets = self.zero_dynamic_offsets(object_set.set);
+
+        self.bind_descriptor_set(
+            object_set.set,
+            object_set.descriptor_set(self.frame_data().index()),
+            dynamic_offsets.unwrap_or(&zero_offsets),
+        );
+    }
+
+    /// Binds the shader's own set again with the given dynamic offsets, to select other elements of its
+    /// dynamic buffers for the next draws.
+    ///
+    /// Per object sets take their offsets with `bind_object_descriptor_set`.
+    pub fn set_dynamic_offsets(&self, set: u32, dynamic_offsets: &[u32]) {
+        let descriptor_set = self
+            .frame_data()
+            .shader_resources(self.active_pipeline().pipeline_id())
+            .descriptor_set(set)
+            .expect("Shader has no set to apply the dynamic offsets to!");
+
+        self.bind_descriptor_set(set, descriptor_set, dynamic_offsets);
+    }
+
+    /// Dispatches the compute pipeline with its descriptor sets for the active frame.
+    ///
+    /// Must be recorded outside of a render pass, the graphics pipeline and its sets stay bound.
+    pub fn dispatch(&self, pipeline: &ComputePipeline, group_count: [u32; 3]) {
+        unsafe {
+            self.device().cmd_bind_pipeline(
+                *self.active_command_buffer,
+                vk::PipelineBindPoint::COMPUTE,
+                **pipeline,
+            );
+
+            for (set, descriptor_set) in
+                pipeline.io.frame_descriptor_sets(self.frame_data().index())
+            {
+                let dynamic_offsets = zero_dynamic_offsets(&pipeline.io, set);
+
+                self.device().cmd_bind_descriptor_sets(
+                    *self.active_command_buffer,
+                    vk::PipelineBindPoint::COMPUTE,
+                    pipeline.layout(),
+                    set,
+                    &[descriptor_set],
+                    &dynamic_offsets,
+                );
+            }
+
+            self.device().cmd_dispatch(
+                *self.active_command_buffer,
+                group_count[0],
+                group_count[1],
+                group_count[2],
+            );
+        }
+    }
+
+    /// Pushes constants for the next dispatch of the compute pipeline.
+    pub fn push_compute_constants<T: Sized + Copy>(&self, pipeline: &ComputePipeline, data: &T) {
+        unsafe {
+            self.device().cmd_push_constants(
+                *self.active_command_buffer,
+                pipeline.layout(),
+                vk::ShaderStageFlags::COMPUTE,
+                0,
+                any_as_u8_slice(data),
+            );
+        }
+    }
+
+    /// Records a barrier on the whole buffer, for example between a dispatch that writes it and the draws that read it.
+    pub fn buffer_barrier(&self, buffer: vk::Buffer, src: MemoryAccess, dst: MemoryAccess) {
+        cmd_buffer_barrier(self.device(), *self.active_command_buffer, buffer, src, dst);
+    }
+
+    /// Records a barrier on the image that transitions it between the layouts of `src` and `dst`.
+    pub fn image_barrier(
+        &self,
+        image: vk::Image,
+        subresource_range: vk::ImageSubresourceRange,
+        src: ImageAccess,
+        dst: ImageAccess,
+    ) {
+        cmd_image_barrier(
+            self.device(),
+            *self.active_command_buffer,
+            image,
+            subresource_range,
+            src,
+            dst,
+        );
+    }
+
+    /// Opens a labeled region for captures and validation messages, close it with `end_label`.
+    pub fn begin_label(&self, name: &str) {
+        self.device()
+            .begin_debug_label(*self.active_command_buffer, name);
+    }
+
+    pub fn end_label(&self) {
+        self.device().end_debug_label(*self.active_command_buffer);
     }
 
     pub fn set_view_port(&self, x: f32, y: f32, width: f32, height: f32) {
@@ -84,7 +228,6 @@ impl RenderPipeline {
 
     pub fn render_mesh(&self) {
         self.bind_buffers();
-        self.bind_descriptor_sets();
         self.draw_indexed();
     }
 
@@ -148,24 +291,24 @@ impl RenderPipeline {
         unsafe { &*self.device }
     }
 
+    fn frame_data(&self) -> &FrameData {
+        unsafe { &*self.frame_data }
+    }
+
     fn bind_descriptor_sets(&self) {
-        let sets = self
-            .active_pipeline()
-            .shader
-            .get_descriptor_sets(self.active_image_index, String::from(""));
+        let resources = self
+            .frame_data()
+            .shader_resources(self.active_pipeline().pipeline_id());
 
-        unsafe {
-            self.device().cmd_bind_descriptor_sets(
-                *self.active_command_buffer,
-                vk::PipelineBindPoint::GRAPHICS,
-                self.active_pipeline().layout(),
-                0,
-                &sets,
-                &[],
-            );
+        for (set, descriptor_set) in resources.descriptor_sets.iter() {
+            self.bind_descriptor_set(*set, *descriptor_set, &self.zero_dynamic_offsets(*set));
         }
     }
 
+    fn zero_dynamic_offsets(&self, set: u32) -> Vec<u32> {
+        zero_dynamic_offsets(&self.active_pipeline().shader.io, set)
+    }
+
     fn draw_indexed(&self) {
         unsafe {
             self.device().cmd_draw_indexed(
@@ -180,11 +323,21 @@ impl RenderPipeline {
     }
 }
 
+/// Offsets that select the first element of every dynamic buffer of the set.
+fn zero_dynamic_offsets(io: &ShaderIo, set: u32) -> Vec<u32> {
+    let count = io
+        .descriptor_set(set)
+        .map_or(0, |descriptor_set| descriptor_set.dynamic_offset_count());
+
+    vec![0; count]
+}
+
 pub struct RenderContext {
     graphics_queue: vk::Queue,
     command_pool: vk::CommandPool,
     instance: ash::Instance,
     device: VkLogicalDevice,
+    upload_context: Arc<Mutex<UploadContext>>,
 }
 
 impl RenderContext {
@@ -193,12 +346,14 @@ impl RenderContext {
         command_pool: &VkCommandPool,
         device: &VkLogicalDevice,
         graphics_queue: &VkQueue,
+        upload_context: &Arc<Mutex<UploadContext>>,
     ) -> RenderContext {
         RenderContext {
             graphics_queue: **graphics_queue,
             command_pool: **command_pool,
             instance: (**(instance)).clone(),
             device: device.clone(),
+            upload_context: upload_context.clone(),
         }
     }
     pub fn queue_family_indices(&self) -> &VkQueueFamilyIndices {
@@ -236,4 +391,9 @@ impl RenderContext {
     pub fn graphics_queue(&self) -> vk::Queue {
         self.graphics_queue
     }
+
+    /// Returns the upload context that batches buffer and image uploads.
+    pub fn upload_context(&self) -> MutexGuard<UploadContext> {
+        self.upload_context.lock().unwrap()
+    }
 }
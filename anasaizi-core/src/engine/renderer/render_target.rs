@@ -0,0 +1,120 @@
+use crate::{
+    engine::{RenderContext, VulkanApplication},
+    vulkan::{
+        ImageAccess, MemoryAccess, SwapchainConfig, VkImageView, VkLogicalDevice,
+        VkOffscreenTarget, VkSwapChain,
+    },
+    Result,
+};
+use ash::vk;
+
+/// The images a `RenderLayer` renders its frames into.
+///
+/// A windowed application renders to the swapchain, a headless application to an offscreen target.
+pub enum RenderTarget {
+    Swapchain(VkSwapChain),
+    Offscreen(VkOffscreenTarget),
+}
+
+impl RenderTarget {
+    /// Creates the swapchain, or an offscreen target when the application is headless.
+    ///
+    /// # Arguments
+    /// - `extent`: The inner size of the window, or the extent of the offscreen images.
+    /// - `config`: The present mode and format of the swapchain, unused by offscreen targets.
+    pub fn new(
+        application: &VulkanApplication,
+        render_context: &RenderContext,
+        extent: vk::Extent2D,
+        image_count: usize,
+        config: &SwapchainConfig,
+    ) -> Result<RenderTarget> {
+        Ok(match &application.window {
+            Some(window) => RenderTarget::Swapchain(VkSwapChain::new(
+                render_context,
+                window.surface_data(),
+                extent,
+                config,
+            )?),
+            None => {
+                RenderTarget::Offscreen(VkOffscreenTarget::new(render_context, extent, image_count))
+            }
+        })
+    }
+
+    pub fn extent(&self) -> vk::Extent2D {
+        match self {
+            RenderTarget::Swapchain(swapchain) => swapchain.extent,
+            RenderTarget::Offscreen(offscreen) => offscreen.extent,
+        }
+    }
+
+    pub fn image_format(&self) -> vk::Format {
+        match self {
+            RenderTarget::Swapchain(swapchain) => swapchain.image_format,
+            RenderTarget::Offscreen(offscreen) => offscreen.image_format,
+        }
+    }
+
+    pub fn image_count(&self) -> usize {
+        match self {
+            RenderTarget::Swapchain(swapchain) => swapchain.images.len(),
+            RenderTarget::Offscreen(offscreen) => offscreen.images.len(),
+        }
+    }
+
+    pub fn image_views(&self) -> &Vec<VkImageView> {
+        match self {
+            RenderTarget::Swapchain(swapchain) => &swapchain.image_views,
+            RenderTarget::Offscreen(offscreen) => &offscreen.image_views,
+        }
+    }
+
+    pub fn images(&self) -> &Vec<vk::Image> {
+        match self {
+            RenderTarget::Swapchain(swapchain) => &swapchain.images,
+            RenderTarget::Offscreen(offscreen) => &offscreen.images,
+        }
+    }
+
+    /// The layout the color images should be in at the end of a frame.
+    pub fn final_layout(&self) -> vk::ImageLayout {
+        match self {
+            RenderTarget::Swapchain(_) => vk::ImageLayout::PRESENT_SRC_KHR,
+            RenderTarget::Offscreen(_) => VkOffscreenTarget::final_layout(),
+        }
+    }
+
+    /// How the color images are accessed after a frame, the render graph transitions them to it.
+    pub fn final_access(&self) -> ImageAccess {
+        match self {
+            RenderTarget::Swapchain(_) => {
+                ImageAccess::new(MemoryAccess::PRESENT, self.final_layout())
+            }
+            RenderTarget::Offscreen(_) => {
+                ImageAccess::new(MemoryAccess::TRANSFER_READ, self.final_layout())
+            }
+        }
+    }
+
+    pub fn swapchain(&self) -> Option<&VkSwapChain> {
+        match self {
+            RenderTarget::Swapchain(swapchain) => Some(swapchain),
+            RenderTarget::Offscreen(_) => None,
+        }
+    }
+
+    pub fn offscreen(&self) -> Option<&VkOffscreenTarget> {
+        match self {
+            RenderTarget::Swapchain(_) => None,
+            RenderTarget::Offscreen(offscreen) => Some(offscreen),
+        }
+    }
+
+    pub(crate) unsafe fn destroy(&self, device: &VkLogicalDevice) {
+        match self {
+            RenderTarget::Swapchain(swapchain) => swapchain.destroy(device),
+            RenderTarget::Offscreen(offscreen) => offscreen.destroy(device),
+        }
+    }
+}
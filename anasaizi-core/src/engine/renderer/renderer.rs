@@ -1,4 +1,5 @@
 use crate::{
+    debug::profile_gpu_scope,
     engine::{
         camera::{Camera, CameraMovement},
         image::Texture,
@@ -6,99 +7,90 @@ use crate::{
     },
     profile_fn,
     vulkan::{
-        structures::VkSyncObjects, CommandBuffers, FrameBuffers, ShaderSet, VkCommandPool, VkQueue,
-        VkRenderPass, VkSwapChain,
+        BindlessTextureTable, ShaderSet, ShaderWatcher, SwapchainConfig, TimestampQueries,
+        UploadContext, VkCommandPool, VkQueue, VkRenderPass, BINDLESS_TEXTURE_CAPACITY,
     },
+    Error, Result, WINDOW_HEIGHT, WINDOW_WIDTH,
 };
 
 use crate::{
     engine::{
-        renderer::render_pipeline::RenderPipeline, BufferLayout, GpuMeshMemory, Layer,
-        MeshPushConstants, PBRMaps, PBRMeshPushConstants, RenderContext, Transform,
-        ViewProjectionMatrixUniformObject, World,
+        renderer::{
+            frame_data::FrameData,
+            render_graph::{
+                AttachmentFormat, AttachmentInfo, FrameContext, GraphPass, PassContext, RenderGraph,
+            },
+            render_target::RenderTarget,
+        },
+        DynamicOffsets, GpuMeshMemory, Layer, MeshPushConstants, ObjectDescriptorSet, PBRMaps,
+        PBRMeshPushConstants, RenderContext, Transform, ViewProjectionMatrixUniformObject, World,
     },
     libs::imgui::{DrawCmd, DrawCmdParams, DrawData},
     math::PosOnlyVertex,
     model::{square_indices, square_vertices},
     vulkan::{
-        GPUBuffer, ShaderBuilder, ShaderIOBuilder, VkLogicalDevice, VkPipeline,
-        VkRenderPassBuilder, VkSubpassDescriptor, Window,
+        GPUBuffer, PipelineDescription, ShaderBuilder, ShaderIOBuilder, VkLogicalDevice,
+        VkPipeline, Window,
     },
 };
 use ash::{version::DeviceV1_0, vk};
 
 use crate::engine::GLTFMaterial;
 
-use std::{mem, mem::size_of, ops::Deref, ptr};
+use std::{
+    collections::HashMap,
+    mem,
+    mem::size_of,
+    ops::Deref,
+    path::Path,
+    ptr,
+    sync::{Arc, Mutex},
+};
 use winit::event::{ElementState, MouseButton, VirtualKeyCode};
 
-pub static FRAGMENT_SHADER: &str = "assets\\shaders\\build\\fragment.frag.spv";
-pub static VERTEX_SHADER: &str = "assets\\shaders\\build\\vertex.vert.spv";
-const MAX_FRAMES_IN_FLIGHT: usize = 3;
-
-pub fn create_sync_objects(device: &ash::Device) -> VkSyncObjects {
-    let mut sync_objects = VkSyncObjects {
-        image_available_semaphores: vec![],
-        render_finished_semaphores: vec![],
-        inflight_fences: vec![],
-    };
-
-    let semaphore_create_info = vk::SemaphoreCreateInfo {
-        s_type: vk::StructureType::SEMAPHORE_CREATE_INFO,
-        p_next: ptr::null(),
-        flags: vk::SemaphoreCreateFlags::empty(),
-    };
-
-    let fence_create_info = vk::FenceCreateInfo {
-        s_type: vk::StructureType::FENCE_CREATE_INFO,
-        p_next: ptr::null(),
-        flags: vk::FenceCreateFlags::SIGNALED,
-    };
-
-    for _ in 0..MAX_FRAMES_IN_FLIGHT {
-        unsafe {
-            let image_available_semaphore = device
-                .create_semaphore(&semaphore_create_info, None)
-                .expect("Failed to create Semaphore Object!");
-            let render_finished_semaphore = device
-                .create_semaphore(&semaphore_create_info, None)
-                .expect("Failed to create Semaphore Object!");
-            let inflight_fence = device
-                .create_fence(&fence_create_info, None)
-                .expect("Failed to create Fence Object!");
-
-            sync_objects
-                .image_available_semaphores
-                .push(image_available_semaphore);
-            sync_objects
-                .render_finished_semaphores
-                .push(render_finished_semaphore);
-            sync_objects.inflight_fences.push(inflight_fence);
-        }
-    }
-
-    sync_objects
-}
+pub static FRAGMENT_SHADER: &str = "assets/shaders/fragment.frag";
+pub static VERTEX_SHADER: &str = "assets/shaders/vertex.vert";
+/// The number of frames the CPU records ahead of the GPU, unless configured otherwise.
+pub const DEFAULT_FRAMES_IN_FLIGHT: usize = 3;
+/// The number of GPU scopes that can be measured in a frame.
+const MAX_GPU_SCOPES: u32 = 8;
+/// The highest MSAA sample count used when the device supports it.
+pub const MAX_MSAA_SAMPLES: vk::SampleCountFlags = vk::SampleCountFlags::TYPE_4;
+/// The vertical field of view of the camera in radians.
+const FIELD_OF_VIEW: f32 = 1.0;
+/// The pass of the default render graph that draws the meshes of the world.
+pub const MESH_PASS: &str = "Meshes";
+/// The pass of the default render graph that draws the ui over the meshes.
+pub const UI_PASS: &str = "UI";
 
 pub struct RenderLayer {
-    pub swapchain: VkSwapChain,
-    pub render_pass: VkRenderPass,
+    pub target: RenderTarget,
+    /// The passes of a frame, other layers can add their own passes to it.
+    pub render_graph: RenderGraph,
+    samples: vk::SampleCountFlags,
     pub graphics_queue: VkQueue,
-    present_queue: VkQueue,
+    present_queue: Option<VkQueue>,
+    /// The pool of single time commands, the frames have their own pools.
     pub command_pool: VkCommandPool,
+    upload_context: Arc<Mutex<UploadContext>>,
 
-    frame_buffers: FrameBuffers,
-    command_buffers: CommandBuffers,
+    /// The resources of the frames in flight, indexed by `current_frame`.
+    frames: Vec<FrameData>,
 
     pub pipelines: Vec<VkPipeline>,
 
-    pub ui_pipeline: Option<VkPipeline>,
+    /// Set with `set_ui_pipeline`, the frames hold the handles of its resources.
+    ui_pipeline: Option<VkPipeline>,
+    /// The ui mesh of `current_frame`, it is owned by the ui layer.
     pub ui_mesh: *const GpuMeshMemory,
     pub ui_data: *const DrawData,
 
     pub texture_sampler: Option<vk::Sampler>,
+    /// The global texture table, the texture indices in push constants refer to it.
+    pub bindless_textures: BindlessTextureTable,
 
-    sync_object: VkSyncObjects,
+    /// Measures the passes on the GPU for the profiler, only created when profiling.
+    gpu_timestamps: Option<TimestampQueries>,
 
     pub camera: Camera,
     pub start_move_y: f64,
@@ -110,9 +102,17 @@ pub struct RenderLayer {
 
     pub current_frame: usize,
     delta_time: f32,
+    /// Set by resize events and config changes, the swapchain is recreated before the next frame.
+    framebuffer_resized: bool,
+    /// Used by the next swapchain recreation.
+    swapchain_config: SwapchainConfig,
 
     pub world: World,
     mouse_down: bool,
+
+    shader_watcher: Option<ShaderWatcher>,
+    /// The last reload error of each pipeline, by pipeline id.
+    shader_errors: HashMap<u32, String>,
 }
 
 impl Layer for RenderLayer {
@@ -176,6 +176,9 @@ impl Layer for RenderLayer {
             Event::MouseScroll(_xoffset, yoffset) => {
                 self.camera.process_mouse_scroll(*yoffset);
             }
+            Event::Resize(_) => {
+                self.framebuffer_resized = true;
+            }
             Event::MouseInput(state, button) => {
                 if *button == MouseButton::Left && *state == ElementState::Pressed {
                     self.mouse_down = true;
@@ -197,129 +200,141 @@ impl Layer for RenderLayer {
         render_context: &RenderContext,
         application: &VulkanApplication,
     ) {
-        let device = render_context.device();
-        let _render_pipeline = RenderPipeline::new(
-            &application.device,
-            &self.command_buffers.current(),
-            self.current_frame(),
-        );
+        // A minimized window has no extent to render to, rendering resumes when it is restored.
+        if Self::is_minimized(application) {
+            return;
+        }
 
-        let wait_fences = [self.sync_object.inflight_fences[self.current_frame()]];
+        if self.framebuffer_resized {
+            if let Err(error) = self.recreate_swapchain(application, render_context) {
+                panic!("Failed to recreate the swapchain: {}", error);
+            }
+        }
+
+        self.reload_changed_shaders(application);
 
-        let (image_index, _is_sub_optimal) = unsafe {
+        let acquired = unsafe {
             profile_fn!("Acquire Next Image...", {
-                device
-                    .reset_command_pool(*self.command_pool, vk::CommandPoolResetFlags::empty())
-                    .unwrap();
-
-                self.swapchain
-                    .loader
-                    .acquire_next_image(
-                        self.swapchain.swapchain,
+                match &self.target {
+                    RenderTarget::Swapchain(swapchain) => swapchain.loader.acquire_next_image(
+                        swapchain.swapchain,
                         u64::MAX,
-                        self.sync_object.image_available_semaphores[self.current_frame],
+                        self.frames[self.current_frame].image_available(),
                         vk::Fence::null(),
-                    )
-                    .expect("Failed to acquire next image!")
+                    ),
+                    // Offscreen images are not shared with a presentation engine.
+                    RenderTarget::Offscreen(_) => Ok((self.current_frame as u32, false)),
+                }
             })
         };
 
+        let image_index = match acquired {
+            Ok((image_index, _is_sub_optimal)) => image_index,
+            // The frame is skipped, the next one renders to the recreated swapchain.
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                if let Err(error) = self.recreate_swapchain(application, render_context) {
+                    panic!("Failed to recreate the swapchain: {}", error);
+                }
+                return;
+            }
+            Err(result) => panic!("Failed to acquire next image: {}", Error::from(result)),
+        };
+
         profile_fn!("Recording Commands...", {
-            self.command_buffers.begin_session(
-                &application.device,
-                &self.render_pass,
-                self.swapchain.extent,
-                &self.frame_buffers,
-                self.current_frame,
-            );
+            let frame = self.current_frame;
+            self.frames[frame].begin_recording(&application.device);
 
-            let mut render_pipeline = RenderPipeline::new(
-                &application.device,
-                &self.command_buffers.current(),
-                self.current_frame(),
-            );
+            let frame_data = &self.frames[frame];
+            let command_buffers = frame_data.command_buffers();
 
-            self.render_meshes(&mut render_pipeline);
+            if let Some(gpu_timestamps) = self.gpu_timestamps.as_mut() {
+                gpu_timestamps.reset(&application.device, command_buffers.current(), frame);
+            }
 
-            unsafe {
-                device
-                    .cmd_next_subpass(self.command_buffers.current(), vk::SubpassContents::INLINE);
+            let frame_context = FrameContext {
+                device: &application.device,
+                command_buffers,
+                frame_data,
+                world: &self.world,
+                pipelines: &self.pipelines,
+                ui_pipeline: self.ui_pipeline.as_ref(),
+                ui_mesh: unsafe { self.ui_mesh.as_ref() },
+                ui_data: unsafe { self.ui_data.as_ref() },
             };
 
-            self.render_ui(&mut render_pipeline);
+            self.render_graph.execute(
+                &frame_context,
+                image_index as usize,
+                self.gpu_timestamps.as_mut(),
+            );
 
-            self.command_buffers.end_session(&application.device);
+            command_buffers.end_recording(&application.device);
         });
 
         profile_fn!("Queues...", {
-            let wait_semaphores = [self.sync_object.image_available_semaphores[self.current_frame]];
-            let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
-            let signal_semaphores =
-                [self.sync_object.render_finished_semaphores[self.current_frame]];
-
-            let submit_infos = [vk::SubmitInfo {
-                s_type: vk::StructureType::SUBMIT_INFO,
-                p_next: ptr::null(),
-                wait_semaphore_count: wait_semaphores.len() as u32,
-                p_wait_semaphores: wait_semaphores.as_ptr(),
-                p_wait_dst_stage_mask: wait_stages.as_ptr(),
-                command_buffer_count: 1,
-                p_command_buffers: [self.command_buffers.current()].as_ptr(),
-                signal_semaphore_count: signal_semaphores.len() as u32,
-                p_signal_semaphores: signal_semaphores.as_ptr(),
-            }];
-
-            unsafe {
-                profile_fn!("Submitting Queues...", {
-                    device
-                        .reset_fences(&wait_fences)
-                        .expect("Failed to reset Fence!");
-
-                    device
-                        .queue_submit(
-                            *self.graphics_queue,
-                            &submit_infos,
-                            self.sync_object.inflight_fences[self.current_frame],
-                        )
-                        .expect("Failed to execute queue submit.");
-                })
-            }
-
-            let swapchains = [*self.swapchain];
-
-            let present_info = vk::PresentInfoKHR {
-                s_type: vk::StructureType::PRESENT_INFO_KHR,
-                p_next: ptr::null(),
-                wait_semaphore_count: 1,
-                p_wait_semaphores: signal_semaphores.as_ptr(),
-                swapchain_count: 1,
-                p_swapchains: swapchains.as_ptr(),
-                p_image_indices: &image_index,
-                p_results: ptr::null_mut(),
+            let frame_data = &mut self.frames[self.current_frame];
+
+            // Without a swapchain there is no image to wait for and nothing to signal presentation.
+            let (wait_semaphores, signal_semaphores) = match self.target {
+                RenderTarget::Swapchain(_) => (
+                    vec![frame_data.image_available()],
+                    vec![frame_data.render_finished()],
+                ),
+                RenderTarget::Offscreen(_) => (vec![], vec![]),
             };
 
-            unsafe {
-                profile_fn!("Present Queue...", {
-                    let result = self
-                        .swapchain
-                        .loader
-                        .queue_present(*self.present_queue, &present_info);
-
-                    match result {
-                        Err(vk::Result::ERROR_OUT_OF_DATE_KHR)
-                        | Err(vk::Result::SUBOPTIMAL_KHR) => {
-                            self.recreate_swapchain(application, render_context);
-                        }
-                        Err(_) => {
-                            panic!("a");
-                        }
-                        _ => {}
-                    }
-                });
+            // Uploads are submitted first so that the frame reads the uploaded data.
+            render_context.upload_context().flush();
+
+            profile_fn!("Submitting Queues...", {
+                frame_data.submit(
+                    &application.device,
+                    *self.graphics_queue,
+                    &wait_semaphores,
+                    &signal_semaphores,
+                );
+            });
+
+            let swapchain = self
+                .target
+                .swapchain()
+                .map(|swapchain| (swapchain.loader.clone(), swapchain.swapchain));
+
+            if let Some((swapchain_loader, swapchain)) = swapchain {
+                let swapchains = [swapchain];
+
+                let present_info = vk::PresentInfoKHR {
+                    s_type: vk::StructureType::PRESENT_INFO_KHR,
+                    p_next: ptr::null(),
+                    wait_semaphore_count: 1,
+                    p_wait_semaphores: signal_semaphores.as_ptr(),
+                    swapchain_count: 1,
+                    p_swapchains: swapchains.as_ptr(),
+                    p_image_indices: &image_index,
+                    p_results: ptr::null_mut(),
+                };
 
-                device
-                    .wait_for_fences(&wait_fences, true, u64::MAX)
-                    .expect("Failed to wait for Fence!");
+                unsafe {
+                    profile_fn!("Present Queue...", {
+                        let result = swapchain_loader
+                            .queue_present(*self.present_queue.as_ref().unwrap(), &present_info);
+
+                        match result {
+                            // `Ok(true)` is a suboptimal swapchain, it still presented.
+                            Ok(true) | Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => {
+                                if let Err(error) =
+                                    self.recreate_swapchain(application, render_context)
+                                {
+                                    panic!("Failed to recreate the swapchain: {}", error);
+                                }
+                            }
+                            Err(result) => {
+                                panic!("Failed to present frame: {}", Error::from(result));
+                            }
+                            _ => {}
+                        }
+                    });
+                }
             }
         });
 
@@ -329,14 +344,28 @@ impl Layer for RenderLayer {
         //     self.mouse_down = false;
         // }
 
-        self.current_frame = (self.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
+        // The CPU continues with the next frame while the GPU renders this one.
+        self.current_frame = (self.current_frame + 1) % self.frames.len();
+        self.begin_frame(application);
     }
 
     fn end_frame(&mut self) {}
 }
 
 impl RenderLayer {
-    pub fn new(application: &VulkanApplication) -> Self {
+    pub fn new(application: &VulkanApplication) -> Result<Self> {
+        Self::with_frames_in_flight(application, DEFAULT_FRAMES_IN_FLIGHT)
+    }
+
+    /// Creates the render layer with the number of frames the CPU may record ahead of the GPU.
+    ///
+    /// Shaders create their uniform buffers and descriptor sets for `frame_count` frames.
+    pub fn with_frames_in_flight(
+        application: &VulkanApplication,
+        frame_count: usize,
+    ) -> Result<Self> {
+        assert!(frame_count > 0, "At least one frame has to be in flight!");
+
         let device = &application.device;
         let _instance = &application.instance;
 
@@ -344,62 +373,73 @@ impl RenderLayer {
             &device,
             device.queue_family_indices().graphics_family.unwrap(),
         );
-        let present_queue = VkQueue::create(
-            &device,
-            device.queue_family_indices().present_family.unwrap(),
-        );
+        let present_queue = device
+            .queue_family_indices()
+            .present_family
+            .map(|present_family| VkQueue::create(&device, present_family));
 
         let command_pool = VkCommandPool::create(&device);
+        let upload_context = Arc::new(Mutex::new(UploadContext::new(&device, *graphics_queue)));
 
         let render_context = RenderContext::new(
             &application.instance,
             &command_pool,
             &device,
             &graphics_queue,
+            &upload_context,
         );
 
-        let swapchain = VkSwapChain::new(&render_context, application.window.surface_data());
-
-        let render_pass = Self::setup_renderpass(swapchain.image_format, &application);
+        let swapchain_config = SwapchainConfig::default();
+        let target = RenderTarget::new(
+            application,
+            &render_context,
+            Self::target_extent(application),
+            frame_count,
+            &swapchain_config,
+        )?;
 
-        let frame_buffers = FrameBuffers::create(
-            &application.device,
-            &render_pass,
-            &swapchain.image_views.iter().map(|i| **i).collect(),
-            swapchain.depth_image_view.clone(),
-            &swapchain.extent,
-        );
+        let samples = Self::sample_count(application);
+        let mut render_graph = Self::default_render_graph(samples);
+        render_graph.compile(&render_context, &target);
 
-        let sync_object = create_sync_objects(device.logical_device());
+        let frames = (0..frame_count)
+            .map(|index| FrameData::new(device, index))
+            .collect();
 
         let camera = Camera::new(
-            16.0 / 9.0,
-            (swapchain.extent.width / swapchain.extent.height) as f32,
+            Camera::aspect_ratio_of(target.extent().width, target.extent().height),
+            FIELD_OF_VIEW,
             0.1,
             100.0,
         );
 
-        let texture_sampler = Texture::create_texture_sampler(&device);
+        let texture_sampler = Texture::create_texture_sampler(&device, vk::LOD_CLAMP_NONE);
+        let bindless_textures = BindlessTextureTable::new(&device, BINDLESS_TEXTURE_CAPACITY);
 
-        let command_buffers =
-            CommandBuffers::create(&application.device, &command_pool, frame_buffers.len());
+        let gpu_timestamps = if cfg!(feature = "profile") {
+            TimestampQueries::new(device, frame_count, MAX_GPU_SCOPES)
+        } else {
+            None
+        };
 
-        RenderLayer {
-            swapchain,
-            render_pass,
+        Ok(RenderLayer {
+            target,
+            render_graph,
+            samples,
 
             command_pool,
+            upload_context,
 
             graphics_queue,
             present_queue,
 
-            frame_buffers,
-            command_buffers,
+            frames,
             pipelines: vec![],
 
             texture_sampler: Some(texture_sampler),
+            bindless_textures,
 
-            sync_object,
+            gpu_timestamps,
 
             camera,
 
@@ -414,10 +454,15 @@ impl RenderLayer {
             ui_mesh: std::ptr::null(),
             ui_data: std::ptr::null(),
             delta_time: 0.0,
+            framebuffer_resized: false,
+            swapchain_config,
 
             world: World::new(),
             mouse_down: false,
-        }
+
+            shader_watcher: None,
+            shader_errors: HashMap::new(),
+        })
     }
 
     pub fn render_context(&self, application: &VulkanApplication) -> RenderContext {
@@ -426,6 +471,7 @@ impl RenderLayer {
             &self.command_pool,
             &application.device,
             &self.graphics_queue,
+            &self.upload_context,
         )
     }
 
@@ -433,49 +479,244 @@ impl RenderLayer {
         &mut self.camera
     }
 
+    /// The frame that is rendered next, the GPU finished its previous submit.
     pub fn current_frame(&self) -> usize {
         self.current_frame
     }
 
+    /// The resources of `current_frame`, the uniform buffers of the pipelines are written through them.
+    pub fn frame_data(&self) -> &FrameData {
+        &self.frames[self.current_frame]
+    }
+
+    pub fn swapchain_config(&self) -> &SwapchainConfig {
+        &self.swapchain_config
+    }
+
+    /// Changes the present mode and format, the swapchain is recreated with it before the next frame.
+    pub fn set_swapchain_config(&mut self, swapchain_config: SwapchainConfig) {
+        if self.swapchain_config != swapchain_config {
+            self.swapchain_config = swapchain_config;
+            self.framebuffer_resized = true;
+        }
+    }
+
+    /// The number of frames in flight, shaders create their per frame resources this many times.
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Waits until the GPU finished the last frame that used the resources of the current frame.
+    ///
+    /// `on_update` calls it after advancing `current_frame`, the uniform buffers and meshes of
+    /// `current_frame` can be written until the next `on_update`.
+    fn begin_frame(&mut self, application: &VulkanApplication) {
+        let submitted_at = self.frames[self.current_frame].wait(&application.device);
+
+        // The GPU scopes are placed on the CPU clock relative to the submit.
+        if let (Some(submitted_at), Some(gpu_timestamps)) =
+            (submitted_at, self.gpu_timestamps.as_ref())
+        {
+            for scope in gpu_timestamps.results(&application.device, self.current_frame) {
+                profile_gpu_scope(&scope.name, submitted_at + scope.start, scope.duration);
+            }
+        }
+    }
+
+    /// The MSAA sample count, up to `MAX_MSAA_SAMPLES`.
+    ///
+    /// Headless rendering uses one sample, resolve filters differ between drivers
+    /// and golden images compare pixels.
+    fn sample_count(application: &VulkanApplication) -> vk::SampleCountFlags {
+        if application.window.is_some() {
+            application.device.sample_count(MAX_MSAA_SAMPLES)
+        } else {
+            vk::SampleCountFlags::TYPE_1
+        }
+    }
+
+    /// The extent of the render target, the inner size of the window or the offscreen extent.
+    fn target_extent(application: &VulkanApplication) -> vk::Extent2D {
+        match application.window.as_ref() {
+            Some(window) => {
+                let size = window.inner_size();
+                vk::Extent2D {
+                    width: size.width,
+                    height: size.height,
+                }
+            }
+            None => Self::offscreen_extent(),
+        }
+    }
+
+    fn is_minimized(application: &VulkanApplication) -> bool {
+        let extent = Self::target_extent(application);
+        extent.width == 0 || extent.height == 0
+    }
+
+    /// The extent of the offscreen target used when the application is headless.
+    fn offscreen_extent() -> vk::Extent2D {
+        vk::Extent2D {
+            width: WINDOW_WIDTH,
+            height: WINDOW_HEIGHT,
+        }
+    }
+
+    /// The meshes pass renders into the backbuffer, through a multisampled color image that is resolved
+    /// into it when MSAA is used. The ui pass draws over it.
+    fn default_render_graph(samples: vk::SampleCountFlags) -> RenderGraph {
+        let mut graph = RenderGraph::new();
+        let backbuffer = graph.backbuffer();
+        let depth =
+            graph.create_attachment("Depth", AttachmentInfo::depth().with_samples(samples));
+
+        let mesh_pass = if samples == vk::SampleCountFlags::TYPE_1 {
+            GraphPass::graphics(MESH_PASS).write_color(backbuffer)
+        } else {
+            let color = graph.create_attachment(
+                "Color (MSAA)",
+                AttachmentInfo::color(AttachmentFormat::Target).with_samples(samples),
+            );
+
+            GraphPass::graphics(MESH_PASS)
+                .write_color(color)
+                .resolve_into(backbuffer)
+        };
+
+        graph.add_pass(
+            mesh_pass
+                .write_depth(depth)
+                .with_record(Self::render_meshes),
+        );
+        graph.add_pass(
+            GraphPass::graphics(UI_PASS)
+                .write_color(backbuffer)
+                .with_record(Self::render_ui),
+        );
+
+        graph
+    }
+
+    /// The MSAA sample count of the meshes pass, pipelines drawing meshes have to use it.
+    pub fn samples(&self) -> vk::SampleCountFlags {
+        self.samples
+    }
+
+    /// The render pass of a graphics pass of the render graph, for example `MESH_PASS`.
+    pub fn render_pass(&self, pass_name: &str) -> &VkRenderPass {
+        self.render_graph
+            .render_pass(pass_name)
+            .expect("Failed to find a graphics pass with this name in the render graph!")
+    }
+
+    /// Adds a pass to the render graph before the pass with the given name, the graph is compiled again.
+    ///
+    /// # Arguments
+    /// - `before`: The name of the pass it runs before, for example `MESH_PASS` for a shadow pass.
+    ///
+    /// Returns `Error::UnknownPass` without touching the graph when there is no pass named `before`.
+    pub fn insert_render_pass(
+        &mut self,
+        application: &VulkanApplication,
+        render_context: &RenderContext,
+        before: &str,
+        pass: GraphPass,
+    ) -> Result<()> {
+        self.render_graph.insert_pass_before(before, pass)?;
+
+        unsafe {
+            application.device.device_wait_idle()?;
+            self.render_graph.destroy(&application.device);
+        }
+
+        self.render_graph.compile(render_context, &self.target);
+        self.refresh_pipelines(application);
+
+        Ok(())
+    }
+
+    /// Reads the last rendered frame back into host memory as RGBA8 pixels.
+    ///
+    /// Returns `None` when rendering to a swapchain.
+    pub fn read_back_frame(&self, render_context: &RenderContext) -> Option<Vec<u8>> {
+        let last_frame = (self.current_frame + self.frames.len() - 1) % self.frames.len();
+
+        // The frame may still be in flight, nothing waits for it at the end of `on_update`.
+        unsafe {
+            render_context
+                .device()
+                .device_wait_idle()
+                .expect("Failed to wait for the device!");
+        }
+
+        self.target
+            .offscreen()
+            .map(|offscreen| offscreen.read_back(render_context, last_frame))
+    }
+
+    /// Creates a pipeline for the meshes pass, meshes select it with `pipeline_id`.
+    ///
+    /// # Arguments
+    /// - `name`: Names the pipeline in validation messages, captures and the labels of its draws.
     pub fn create_pipeline(
         &mut self,
         application: &VulkanApplication,
         shader: ShaderSet,
+        description: PipelineDescription,
         pipeline_id: u32,
-    ) {
-        let pipeline = VkPipeline::create(
+        name: &str,
+    ) -> Result<()> {
+        let mut pipeline = VkPipeline::create(
             &application.device,
-            self.swapchain.extent,
-            &self.render_pass,
+            self.target.extent(),
+            self.render_graph
+                .render_pass(MESH_PASS)
+                .expect("Failed to find the meshes pass!"),
             shader,
+            description,
             pipeline_id,
-        );
+        )?;
+        pipeline.set_debug_name(&application.device, name);
 
+        for frame in self.frames.iter_mut() {
+            frame.register_pipeline(&pipeline);
+        }
         self.pipelines.push(pipeline);
+
+        Ok(())
+    }
+
+    /// Sets the pipeline the `UI_PASS` draws the ui data with.
+    pub fn set_ui_pipeline(&mut self, pipeline: VkPipeline) {
+        for frame in self.frames.iter_mut() {
+            frame.register_pipeline(&pipeline);
+        }
+        self.ui_pipeline = Some(pipeline);
     }
 
     pub fn pick_object_pass(&mut self, _render_context: &RenderContext) {
         // self.object_picker.pick_object::<U>(self.last_x as usize, self.last_y as usize, self.last_key, render_context, &self.world);
     }
 
-    pub fn render_meshes(&mut self, render_pipeline: &mut RenderPipeline) {
-        render_pipeline.set_view_port(
-            0.0,
-            0.0,
-            self.swapchain.extent.width as f32,
-            self.swapchain.extent.height as f32,
-        );
-        render_pipeline.set_scissors(
-            0.0,
-            0.0,
-            self.swapchain.extent.width as f32,
-            self.swapchain.extent.height as f32,
-        );
+    /// Records the meshes of the world with their pipelines, the `MESH_PASS` of the render graph.
+    pub fn render_meshes(context: &mut PassContext) {
+        let extent = context.extent;
+        let frame = context.frame;
+        let render_pipeline = &mut context.render_pipeline;
+
+        render_pipeline.set_view_port(0.0, 0.0, extent.width as f32, extent.height as f32);
+        render_pipeline.set_scissors(0.0, 0.0, extent.width as f32, extent.height as f32);
 
-        for pipeline in self.pipelines.iter_mut() {
-            render_pipeline.bind_pipeline(pipeline, &self.command_buffers);
+        for pipeline in frame.pipelines.iter() {
+            let label = match pipeline.debug_name() {
+                Some(name) => name.to_string(),
+                None => format!("Pipeline {}", pipeline.pipeline_id()),
+            };
+            render_pipeline.begin_label(&label);
+
+            render_pipeline.bind_pipeline(pipeline, frame.command_buffers);
 
-            for (id, (mesh, transform, pipeline_id)) in self
+            for (id, (mesh, transform, pipeline_id)) in frame
                 .world
                 .query::<(&GpuMeshMemory, &Transform, &u32)>()
                 .iter()
@@ -483,7 +724,26 @@ impl RenderLayer {
                 if *pipeline_id == pipeline.pipeline_id() {
                     render_pipeline.set_mesh(mesh);
 
-                    if let Ok(maps) = self.world.get::<PBRMaps>(id) {
+                    let dynamic_offsets = frame.world.get::<DynamicOffsets>(id).ok();
+                    let object_set = frame.world.get::<ObjectDescriptorSet>(id).ok();
+
+                    if let Some(object_set) = object_set.as_ref() {
+                        let object_offsets = dynamic_offsets
+                            .as_ref()
+                            .filter(|offsets| offsets.set == object_set.set)
+                            .map(|offsets| offsets.offsets.as_slice());
+
+                        render_pipeline.bind_object_descriptor_set(object_set, object_offsets);
+                    }
+
+                    if let Some(offsets) = dynamic_offsets.as_ref() {
+                        if object_set.as_ref().map(|object_set| object_set.set) != Some(offsets.set)
+                        {
+                            render_pipeline.set_dynamic_offsets(offsets.set, &offsets.offsets);
+                        }
+                    }
+
+                    if let Ok(maps) = frame.world.get::<PBRMaps>(id) {
                         // Push the model matrix using push constants.
                         let push_constants = PBRMeshPushConstants {
                             model_matrix: transform.model_transform(),
@@ -496,7 +756,7 @@ impl RenderLayer {
                         };
 
                         render_pipeline.push_mesh_constant(&push_constants);
-                    } else if let Ok(maps) = self.world.get::<GLTFMaterial>(id) {
+                    } else if let Ok(maps) = frame.world.get::<GLTFMaterial>(id) {
                         // Push the model matrix using push constants.
                         let mut push = maps.deref().clone();
                         push.model_matrix = transform.model_transform();
@@ -511,24 +771,24 @@ impl RenderLayer {
                     render_pipeline.render_mesh();
                 }
             }
-        }
-    }
 
-    pub fn render_ui(&mut self, render_pipeline: &mut RenderPipeline) {
-        if self.ui_data.is_null() || self.ui_mesh.is_null() {
-            return;
+            render_pipeline.end_label();
         }
+    }
 
-        if let Some(ui_pipeline) = &self.ui_pipeline {
-            let draw_data = unsafe { &*self.ui_data };
+    /// Records the ui draw data, the `UI_PASS` of the render graph.
+    pub fn render_ui(context: &mut PassContext) {
+        let frame = context.frame;
+        let render_pipeline = &mut context.render_pipeline;
 
+        if let (Some(ui_pipeline), Some(ui_mesh), Some(draw_data)) =
+            (frame.ui_pipeline, frame.ui_mesh, frame.ui_data)
+        {
             let framebuffer_width = draw_data.framebuffer_scale[0] * draw_data.display_size[0];
             let framebuffer_height = draw_data.framebuffer_scale[1] * draw_data.display_size[1];
 
-            render_pipeline.bind_pipeline(ui_pipeline, &self.command_buffers);
-            unsafe {
-                render_pipeline.set_mesh(&*self.ui_mesh);
-            }
+            render_pipeline.bind_pipeline(ui_pipeline, frame.command_buffers);
+            render_pipeline.set_mesh(ui_mesh);
             render_pipeline.set_view_port(0.0, 0.0, framebuffer_width, framebuffer_height);
             render_pipeline.push_ui_constants(draw_data);
 
@@ -568,106 +828,168 @@ impl RenderLayer {
         }
     }
 
-    pub fn recreate_swapchain(
-        &mut self,
-        application: &VulkanApplication,
-        render_context: &RenderContext,
-    ) {
+    /// Reloads the shaders of the pipelines when their files change on disk.
+    ///
+    /// # Arguments
+    /// - `directory`: The directory with the shader files, subdirectories are watched as well.
+    pub fn watch_shaders(&mut self, directory: &Path) -> Result<()> {
+        self.shader_watcher = Some(ShaderWatcher::new(directory)?);
+        Ok(())
+    }
+
+    /// The errors of the last shader reload that failed, the pipelines keep their old shaders.
+    pub fn shader_errors(&self) -> Vec<&str> {
+        let mut pipeline_ids: Vec<&u32> = self.shader_errors.keys().collect();
+        pipeline_ids.sort();
+
+        pipeline_ids
+            .into_iter()
+            .map(|pipeline_id| self.shader_errors[pipeline_id].as_str())
+            .collect()
+    }
+
+    /// Rebuilds the pipelines, the ui pipeline included, whose shader files changed since the last frame.
+    fn reload_changed_shaders(&mut self, application: &VulkanApplication) {
+        let changed_files = match self.shader_watcher.as_ref() {
+            Some(watcher) => watcher.changed_files(),
+            None => return,
+        };
+
+        if changed_files.is_empty() {
+            return;
+        }
+
         unsafe {
             application
                 .device
                 .device_wait_idle()
-                .expect("Failed to wait device idle!");
+                .expect("Failed to wait for the device to become idle!");
+        }
 
-            self.destroy_swapchain(&application.device);
-        };
+        let mesh_render_pass = self
+            .render_graph
+            .render_pass(MESH_PASS)
+            .expect("Failed to find the meshes pass!");
+        let ui_render_pass = self.render_graph.render_pass(UI_PASS);
+
+        let pipelines = self
+            .pipelines
+            .iter_mut()
+            .map(|pipeline| (pipeline, mesh_render_pass))
+            .chain(self.ui_pipeline.iter_mut().zip(ui_render_pass));
 
-        let surface_data = application.window.surface_data();
+        for (pipeline, render_pass) in pipelines {
+            if !changed_files
+                .iter()
+                .any(|path| pipeline.shader.is_read_from(path))
+            {
+                continue;
+            }
 
-        self.swapchain = VkSwapChain::new(render_context, surface_data);
+            let result = unsafe {
+                pipeline.reload_shaders(&application.device, self.target.extent(), render_pass)
+            };
 
-        self.render_pass = Self::setup_renderpass(self.swapchain.image_format, application);
+            match result {
+                Ok(()) => {
+                    self.shader_errors.remove(&pipeline.pipeline_id());
+                }
+                Err(error) => {
+                    let message = format!("Pipeline {}: {}", pipeline.pipeline_id(), error);
+                    log::error!("{}", message);
+                    self.shader_errors.insert(pipeline.pipeline_id(), message);
+                }
+            }
+        }
+    }
 
-        self.frame_buffers = FrameBuffers::create(
-            &application.device,
-            &self.render_pass,
-            &self.swapchain.image_views.iter().map(|i| **i).collect(),
-            self.swapchain.depth_image_view.clone(),
-            &self.swapchain.extent,
-        );
-        self.command_buffers = CommandBuffers::create(
-            &application.device,
-            &self.command_pool,
-            self.frame_buffers.len(),
-        );
+    pub fn recreate_swapchain(
+        &mut self,
+        application: &VulkanApplication,
+        render_context: &RenderContext,
+    ) -> Result<()> {
+        // A swapchain can not have a zero extent, it is recreated once the window is restored.
+        if Self::is_minimized(application) {
+            self.framebuffer_resized = true;
+            return Ok(());
+        }
 
         unsafe {
-            //self.ui_pipeline.as_mut().unwrap().refresh(&application.device, &self.swapchain, &self.render_pass);
+            application.device.device_wait_idle()?;
 
-            for pipeline in self.pipelines.iter_mut() {
-                pipeline.refresh(&application.device, &self.swapchain, &self.render_pass);
-            }
-        }
+            self.destroy_swapchain(&application.device);
+        };
 
-        self.camera.update_screen_resize(
-            16.0 / 9.0,
-            (self.swapchain.extent.width / self.swapchain.extent.height) as f32,
-        );
+        self.target = RenderTarget::new(
+            application,
+            render_context,
+            Self::target_extent(application),
+            self.frames.len(),
+            &self.swapchain_config,
+        )?;
 
-        self.current_frame = 0;
-    }
+        self.render_graph.compile(render_context, &self.target);
 
-    fn setup_renderpass(format: vk::Format, application: &VulkanApplication) -> VkRenderPass {
-        let dependecy = [vk::SubpassDependency::builder()
-            .src_stage_mask(
-                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
-                    | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
-            )
-            .dst_stage_mask(
-                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
-                    | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
-            )
-            .dst_access_mask(
-                vk::AccessFlags::COLOR_ATTACHMENT_READ
-                    | vk::AccessFlags::COLOR_ATTACHMENT_WRITE
-                    | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
-            )
-            .dependency_flags(vk::DependencyFlags::BY_REGION)
-            .src_subpass(0)
-            .dst_subpass(1)
-            .build()];
+        self.refresh_pipelines(application);
 
-        let render_pass = VkRenderPassBuilder::builder()
-            .add_color_attachment(
-                0,
-                format,
-                vk::ImageLayout::PRESENT_SRC_KHR,
-                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-            )
-            .add_depth_attachment(
-                1,
-                application.device.find_depth_format(&application.instance),
-            )
-            .add_subpasses(
-                vec![
-                    VkSubpassDescriptor::new().with_color(0).with_depth(1),
-                    VkSubpassDescriptor::new().with_color(0),
-                ],
-                &dependecy,
-            )
-            .build(&application.device);
+        self.camera
+            .update_screen_resize(self.target.extent().width, self.target.extent().height);
+
+        self.framebuffer_resized = false;
+
+        Ok(())
+    }
 
-        render_pass
+    /// Recreates the pipelines for the render passes of the last graph compile.
+    ///
+    /// A pipeline that fails to be recreated is logged and keeps its old pipeline.
+    fn refresh_pipelines(&mut self, application: &VulkanApplication) {
+        let render_pass = self
+            .render_graph
+            .render_pass(MESH_PASS)
+            .expect("Failed to find the meshes pass!");
+
+        // The ui pass can change its attachment format with the swapchain config.
+        let ui_render_pass = self.render_graph.render_pass(UI_PASS);
+
+        let pipelines = self
+            .pipelines
+            .iter_mut()
+            .map(|pipeline| (pipeline, render_pass))
+            .chain(self.ui_pipeline.iter_mut().zip(ui_render_pass));
+
+        for (pipeline, render_pass) in pipelines {
+            let result =
+                unsafe { pipeline.refresh(&application.device, self.target.extent(), render_pass) };
+
+            if let Err(error) = result {
+                log::error!(
+                    "Failed to recreate pipeline {}: {}",
+                    pipeline.pipeline_id(),
+                    error
+                );
+            }
+        }
     }
 
     unsafe fn destroy_swapchain(&self, device: &VkLogicalDevice) {
-        self.command_buffers.free(device, &self.command_pool);
-        self.frame_buffers.destroy(&device);
-        self.render_pass.destroy(&device);
-        self.swapchain.destroy(&device);
+        self.render_graph.destroy(&device);
+        self.target.destroy(&device);
     }
 
+    /// Destroys the resources of the layer once the GPU finished the frames in flight.
     pub fn destroy(&self, device: &VkLogicalDevice) {
+        unsafe {
+            device
+                .device_wait_idle()
+                .expect("Failed to wait for the device to become idle!");
+        }
+
+        // Keep the compiled pipelines around for the next run.
+        if let Err(error) = device.save_pipeline_cache() {
+            log::warn!("Failed to save the pipeline cache: {}", error);
+        }
+
         unsafe {
             self.destroy_swapchain(device);
             //self.ui_pipeline.as_ref().unwrap().destroy(&device);
@@ -677,9 +999,15 @@ impl RenderLayer {
             }
 
             self.command_pool.destroy(&device);
+            self.upload_context.lock().unwrap().destroy();
             device.destroy_sampler(self.texture_sampler.unwrap(), None);
-            self.sync_object.destroy(&device);
-            self.ui_mesh.as_ref().unwrap().destroy(&device);
+            self.bindless_textures.destroy(&device);
+            for frame in self.frames.iter() {
+                frame.destroy(&device);
+            }
+            if let Some(gpu_timestamps) = self.gpu_timestamps.as_ref() {
+                gpu_timestamps.destroy(&device);
+            }
         }
     }
 
@@ -687,7 +1015,7 @@ impl RenderLayer {
         &self,
         application: &VulkanApplication,
         render_context: &RenderContext,
-    ) -> (ShaderSet, GpuMeshMemory) {
+    ) -> Result<(ShaderSet, GpuMeshMemory)> {
         let (square_vertices, square_indices) =
             (square_vertices().to_vec(), square_indices().to_vec());
 
@@ -696,8 +1024,10 @@ impl RenderLayer {
 
         let grid_index_buffer = GPUBuffer::create(render_context, &square_indices);
 
-        let input_buffer_layout = BufferLayout::new().add_float_vec3(0);
+        grid_vertex_buffer.set_debug_name(render_context.logical_device(), "Grid/vertices");
+        grid_index_buffer.set_debug_name(render_context.logical_device(), "Grid/indices");
 
+        // The grid shader reads no push constants, but every mesh is drawn with `MeshPushConstants`.
         let push_const_ranges = [vk::PushConstantRange {
             stage_flags: vk::ShaderStageFlags::VERTEX,
             offset: 0,
@@ -707,27 +1037,26 @@ impl RenderLayer {
         let descriptors = ShaderIOBuilder::builder()
             .add_uniform_buffer(
                 0,
-                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                0,
                 render_context,
-                self.swapchain.images.len(),
+                self.frames.len(),
                 size_of::<ViewProjectionMatrixUniformObject>(),
             )
-            .add_input_buffer_layout(input_buffer_layout)
-            .add_push_constant_ranges(&push_const_ranges)
-            .build(render_context, self.swapchain.images.len());
+            .add_push_constant_ranges(&push_const_ranges);
 
-        let builder = ShaderBuilder::builder(
+        let builder = ShaderBuilder::glsl(
             application,
-            "assets\\shaders\\build\\grid_vert.vert.spv",
-            "assets\\shaders\\build\\grid_frag.frag.spv",
+            "assets/shaders/grid_vert.vert",
+            "assets/shaders/grid_frag.frag",
         )
-        .with_descriptors(descriptors);
+        .with_descriptors(descriptors, self.frames.len())
+        .with_debug_name("Grid");
 
-        let build: ShaderSet = builder.build();
+        let build: ShaderSet = builder.build()?;
 
-        (
+        Ok((
             build,
             GpuMeshMemory::new(grid_vertex_buffer, grid_index_buffer, -1),
-        )
+        ))
     }
 }
@@ -5,12 +5,14 @@ mod texture_storage;
 pub use texture_loader::TextureLoader;
 pub use texture_storage::{StoredTexture, TextureId, TextureStorage};
 
+use crate::engine::image::TextureColorSpace;
 use image::GenericImageView;
 use std::sync::{Arc, Mutex};
 
 pub async fn load_image_path(
     path: &'static str,
     id: &'static str,
+    color_space: TextureColorSpace,
     image_results: Arc<Mutex<TextureStorage>>,
     debug: bool,
 ) {
@@ -45,6 +47,7 @@ pub async fn load_image_path(
         image_data,
         image_width,
         image_height,
+        color_space,
         path.to_string(),
         texture_id.texture(),
         None,
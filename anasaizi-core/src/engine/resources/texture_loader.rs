@@ -1,4 +1,5 @@
 use crate::engine::{
+    image::TextureColorSpace,
     resources::{load_image_path, TextureStorage},
     RenderContext,
 };
@@ -22,10 +23,18 @@ impl TextureLoader {
         }
     }
 
-    pub fn load_path(&mut self, path: &'static str, id: &'static str, debug: bool) {
+    /// Loads the image in the background, it is uploaded in the given color space.
+    pub fn load_path(
+        &mut self,
+        path: &'static str,
+        id: &'static str,
+        color_space: TextureColorSpace,
+        debug: bool,
+    ) {
         let task = tokio::spawn(load_image_path(
             path,
             id,
+            color_space,
             self.storage.clone(),
             self.debug && debug,
         ));
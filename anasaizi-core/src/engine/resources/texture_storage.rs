@@ -1,5 +1,7 @@
 use crate::engine::{
-    image::Texture, resources::texture_collection::TextureCollection, RenderContext,
+    image::{Texture, TextureColorSpace},
+    resources::texture_collection::TextureCollection,
+    RenderContext,
 };
 use std::collections::HashMap;
 
@@ -8,6 +10,8 @@ pub struct StoredTexture {
     pub data: Vec<u8>,
     pub width: u32,
     pub height: u32,
+    /// The color space the texture is uploaded in.
+    pub color_space: TextureColorSpace,
     pub path: String,
     pub id: String,
     texture: Option<Texture>,
@@ -18,6 +22,7 @@ impl StoredTexture {
         data: Vec<u8>,
         width: u32,
         height: u32,
+        color_space: TextureColorSpace,
         path: String,
         id: String,
         texture: Option<Texture>,
@@ -26,6 +31,7 @@ impl StoredTexture {
             data,
             width,
             height,
+            color_space,
             path,
             id,
             texture,
@@ -55,6 +61,7 @@ impl StoredTexture {
                 &self.data,
                 self.width,
                 self.height,
+                self.color_space,
             ));
         }
     }
@@ -0,0 +1,127 @@
+use ash::vk;
+use std::{error, fmt, io};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Errors returned by the vulkan and engine modules.
+#[derive(Debug)]
+pub enum Error {
+    /// A Vulkan call returned an error code.
+    Vulkan(vk::Result),
+    /// Reading a file failed.
+    Io(io::Error),
+    /// Decoding an image failed.
+    Image(image::ImageError),
+    /// Parsing a glTF document failed.
+    Gltf(gltf::Error),
+    /// None of the physical devices meet the requirements.
+    NoSuitableDevice(String),
+    /// The input uses something the engine does not support.
+    Unsupported(String),
+    /// The shader interface does not match the layout supplied for it.
+    ShaderValidation(String),
+    /// Compiling GLSL to SPIR-V failed, holds the compiler output.
+    ShaderCompilation(String),
+    /// The render graph has no pass with this name.
+    UnknownPass(String),
+    /// The Vulkan library or one of its entry points could not be loaded.
+    Loading(String),
+    /// The validation layers that were requested but are not installed.
+    MissingLayers(Vec<String>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Vulkan(result) => write!(f, "Vulkan call failed: {:?}", result),
+            Error::Io(error) => write!(f, "IO error: {}", error),
+            Error::Image(error) => write!(f, "Failed to decode image: {}", error),
+            Error::Gltf(error) => write!(f, "Failed to load glTF: {}", error),
+            Error::NoSuitableDevice(reason) => write!(f, "No suitable GPU: {}", reason),
+            Error::Unsupported(what) => write!(f, "Unsupported: {}", what),
+            Error::ShaderValidation(mismatches) => {
+                write!(f, "Shader validation failed: {}", mismatches)
+            }
+            Error::ShaderCompilation(output) => write!(f, "Shader compilation failed: {}", output),
+            Error::UnknownPass(name) => write!(f, "The render graph has no pass named {}", name),
+            Error::Loading(what) => write!(f, "Failed to load Vulkan: {}", what),
+            Error::MissingLayers(layers) => write!(
+                f,
+                "Validation layers requested, but not available: {}",
+                layers.join(", ")
+            ),
+        }
+    }
+}
+
+impl error::Error for Error {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            Error::Vulkan(result) => Some(result),
+            Error::Io(error) => Some(error),
+            Error::Image(error) => Some(error),
+            Error::Gltf(error) => Some(error),
+            Error::NoSuitableDevice(_)
+            | Error::Unsupported(_)
+            | Error::ShaderValidation(_)
+            | Error::ShaderCompilation(_)
+            | Error::UnknownPass(_)
+            | Error::Loading(_)
+            | Error::MissingLayers(_) => None,
+        }
+    }
+}
+
+impl From<vk::Result> for Error {
+    fn from(result: vk::Result) -> Self {
+        Error::Vulkan(result)
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(error: io::Error) -> Self {
+        Error::Io(error)
+    }
+}
+
+impl From<image::ImageError> for Error {
+    fn from(error: image::ImageError) -> Self {
+        Error::Image(error)
+    }
+}
+
+impl From<gltf::Error> for Error {
+    fn from(error: gltf::Error) -> Self {
+        Error::Gltf(error)
+    }
+}
+
+impl From<tokio::task::JoinError> for Error {
+    fn from(error: tokio::task::JoinError) -> Self {
+        Error::Io(error.into())
+    }
+}
+
+impl From<notify::Error> for Error {
+    fn from(error: notify::Error) -> Self {
+        match error {
+            notify::Error::Io(error) => Error::Io(error),
+            error => Error::Io(io::Error::new(io::ErrorKind::Other, error.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::Error;
+    use ash::vk;
+    use std::error::Error as _;
+
+    #[test]
+    fn vulkan_error_message() {
+        let error = Error::from(vk::Result::ERROR_DEVICE_LOST);
+
+        assert_eq!(error.to_string(), "Vulkan call failed: ERROR_DEVICE_LOST");
+        assert!(error.source().is_some());
+    }
+}
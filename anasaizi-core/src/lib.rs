@@ -4,11 +4,14 @@
 pub mod debug;
 
 pub mod engine;
+mod error;
 pub mod math;
 pub mod model;
 pub mod utils;
 pub mod vulkan;
 
+pub use error::{Error, Result};
+
 pub const WINDOW_WIDTH: u32 = 800;
 pub const WINDOW_HEIGHT: u32 = 600;
 
@@ -44,4 +47,8 @@ pub mod libs {
     pub mod hecs {
         pub use hecs::*;
     }
+
+    pub mod log {
+        pub use log::*;
+    }
 }
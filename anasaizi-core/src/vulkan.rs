@@ -1,16 +1,23 @@
 pub use application::*;
 pub use buffers::*;
 pub use command_pool::*;
+pub use compute_pipeline::*;
+pub use debug_messenger::*;
 pub use device::*;
 pub use image_view::*;
 pub use instance::*;
 pub use layer::*;
+pub use offscreen::*;
 pub use pipeline::*;
+pub use pipeline_cache::*;
+pub use pipeline_description::*;
 pub use queue::*;
 pub use render_pass::*;
 pub use shader::*;
 pub use surface::*;
 pub use swapchain::*;
+pub use swapchain_config::*;
+pub use timestamp_queries::*;
 pub use window::*;
 
 pub use crate::engine::version::*;
@@ -18,15 +25,22 @@ pub use crate::engine::version::*;
 mod application;
 mod buffers;
 mod command_pool;
+mod compute_pipeline;
+mod debug_messenger;
 mod device;
 mod image_view;
 mod instance;
 mod layer;
+mod offscreen;
 mod pipeline;
+mod pipeline_cache;
+mod pipeline_description;
 mod queue;
 mod render_pass;
 mod shader;
 pub mod structures;
 mod surface;
 mod swapchain;
+mod swapchain_config;
+mod timestamp_queries;
 mod window;
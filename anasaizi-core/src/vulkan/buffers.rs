@@ -1,18 +1,24 @@
+mod allocator;
+mod barrier;
 mod buffer;
 mod command_buffer;
 mod framebuffer;
 mod push_constants;
 mod uniform_buffer;
+mod upload_context;
 mod vertex_buffer;
 
+pub use allocator::{Allocation, AllocationKind, HeapStatistics, MemoryAllocator};
+pub use barrier::{cmd_buffer_barrier, cmd_image_barrier, ImageAccess, MemoryAccess};
 pub use command_buffer::CommandBuffers;
 pub use framebuffer::{FrameBuffer, FrameBuffers};
 pub use uniform_buffer::UniformBuffer;
+pub use upload_context::{MipGeneration, UploadContext, UploadHandle, STAGING_RING_SIZE};
 pub use vertex_buffer::GPUBuffer;
 
 use crate::engine::RenderContext;
 use ash::{version::DeviceV1_0, vk};
-pub use buffer::{copy_image_to_buffer, create_allocate_vk_buffer};
+pub use buffer::{copy_image_to_buffer, create_allocate_vk_buffer, destroy_vk_buffer};
 use std::ptr;
 
 /// Creates a commandbuffer from the given pool which can be used to executed command on.
@@ -46,6 +52,18 @@ pub fn begin_single_time_command(render_context: &RenderContext) -> vk::CommandB
     command_buffer
 }
 
+/// Records a command buffer with `record`, submits it and waits until it is executed.
+///
+/// Use this for work that has to complete before continuing, uploads should go through the `UploadContext`.
+pub fn submit_single_time_command<F: FnOnce(vk::CommandBuffer)>(
+    render_context: &RenderContext,
+    record: F,
+) {
+    let command_buffer = begin_single_time_command(render_context);
+    record(command_buffer);
+    end_single_time_command(render_context, &command_buffer);
+}
+
 /// Finalizes the given commandbuffer and submits it to the given queue
 pub fn end_single_time_command(render_context: &RenderContext, command_buffer: &vk::CommandBuffer) {
     let device = render_context.device();
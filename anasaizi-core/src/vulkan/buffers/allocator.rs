@@ -0,0 +1,430 @@
+use ash::{version::DeviceV1_0, vk};
+use std::{fmt, ptr};
+
+/// The size of a memory block, allocations that don't fit get a dedicated block.
+pub const DEFAULT_BLOCK_SIZE: vk::DeviceSize = 64 * 1024 * 1024;
+
+/// The kind of resource that is bound to an allocation.
+///
+/// Buffers and images are placed in separate blocks so that linear and optimal resources
+/// never share a page, this way `bufferImageGranularity` does not need to be respected.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum AllocationKind {
+    Buffer,
+    Image,
+}
+
+/// A sub range of a memory block that is bound to a buffer or image.
+#[derive(Debug, Clone)]
+pub struct Allocation {
+    memory: vk::DeviceMemory,
+    offset: vk::DeviceSize,
+    size: vk::DeviceSize,
+    block_id: u64,
+    mapped_ptr: *mut u8,
+}
+
+// The mapped pointer points into persistently mapped memory owned by the allocator.
+unsafe impl Send for Allocation {}
+unsafe impl Sync for Allocation {}
+
+impl Allocation {
+    /// The device memory this allocation lives in, shared with other allocations.
+    pub fn memory(&self) -> vk::DeviceMemory {
+        self.memory
+    }
+
+    /// The offset of this allocation in its device memory.
+    pub fn offset(&self) -> vk::DeviceSize {
+        self.offset
+    }
+
+    pub fn size(&self) -> vk::DeviceSize {
+        self.size
+    }
+
+    /// Returns a pointer to the start of this allocation if its memory is host visible.
+    pub fn mapped_ptr(&self) -> Option<*mut u8> {
+        if self.mapped_ptr.is_null() {
+            None
+        } else {
+            Some(self.mapped_ptr)
+        }
+    }
+}
+
+/// Memory usage of a single memory heap.
+#[derive(Debug, Clone, Default)]
+pub struct HeapStatistics {
+    pub heap_index: u32,
+    pub heap_size: vk::DeviceSize,
+    /// The number of device memory allocations made on this heap.
+    pub block_count: usize,
+    /// The number of buffers and images placed in those blocks.
+    pub allocation_count: usize,
+    /// Bytes allocated from the driver.
+    pub reserved_bytes: vk::DeviceSize,
+    /// Bytes handed out to buffers and images.
+    pub used_bytes: vk::DeviceSize,
+}
+
+impl fmt::Display for HeapStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Heap {}: {} blocks, {} allocations, {} / {} / {} MiB used / reserved / size",
+            self.heap_index,
+            self.block_count,
+            self.allocation_count,
+            self.used_bytes / (1024 * 1024),
+            self.reserved_bytes / (1024 * 1024),
+            self.heap_size / (1024 * 1024)
+        )
+    }
+}
+
+/// Keeps track of the free ranges in a memory block.
+///
+/// Ranges are kept sorted by offset and adjacent ranges are merged when freed.
+#[derive(Debug, Clone)]
+pub(crate) struct FreeList {
+    ranges: Vec<(vk::DeviceSize, vk::DeviceSize)>,
+}
+
+impl FreeList {
+    pub(crate) fn new(size: vk::DeviceSize) -> FreeList {
+        FreeList {
+            ranges: vec![(0, size)],
+        }
+    }
+
+    /// Finds the first free range that fits `size` bytes at the given alignment.
+    ///
+    /// Returns the aligned offset of the allocated range.
+    pub(crate) fn allocate(
+        &mut self,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+    ) -> Option<vk::DeviceSize> {
+        let alignment = alignment.max(1);
+
+        for i in 0..self.ranges.len() {
+            let (range_offset, range_size) = self.ranges[i];
+            let aligned_offset = (range_offset + alignment - 1) / alignment * alignment;
+            let padding = aligned_offset - range_offset;
+
+            if range_size < padding + size {
+                continue;
+            }
+
+            let remaining = range_size - padding - size;
+
+            // Keep the padding in front and the remainder behind the allocation free.
+            self.ranges.remove(i);
+            if remaining > 0 {
+                self.ranges.insert(i, (aligned_offset + size, remaining));
+            }
+            if padding > 0 {
+                self.ranges.insert(i, (range_offset, padding));
+            }
+
+            return Some(aligned_offset);
+        }
+
+        None
+    }
+
+    /// Returns the range to the free list, merging it with its neighbours.
+    pub(crate) fn free(&mut self, offset: vk::DeviceSize, size: vk::DeviceSize) {
+        let index = self
+            .ranges
+            .iter()
+            .position(|(range_offset, _)| *range_offset > offset)
+            .unwrap_or(self.ranges.len());
+
+        self.ranges.insert(index, (offset, size));
+
+        // Merge with the next range.
+        if index + 1 < self.ranges.len() {
+            let (next_offset, next_size) = self.ranges[index + 1];
+            if offset + size == next_offset {
+                self.ranges[index].1 += next_size;
+                self.ranges.remove(index + 1);
+            }
+        }
+
+        // Merge with the previous range.
+        if index > 0 {
+            let (previous_offset, previous_size) = self.ranges[index - 1];
+            if previous_offset + previous_size == offset {
+                self.ranges[index - 1].1 += self.ranges[index].1;
+                self.ranges.remove(index);
+            }
+        }
+    }
+
+    pub(crate) fn free_bytes(&self) -> vk::DeviceSize {
+        self.ranges.iter().map(|(_, size)| size).sum()
+    }
+}
+
+struct MemoryBlock {
+    id: u64,
+    memory: vk::DeviceMemory,
+    size: vk::DeviceSize,
+    memory_type_index: u32,
+    kind: AllocationKind,
+    free_list: FreeList,
+    mapped_ptr: *mut u8,
+    allocation_count: usize,
+}
+
+/// A block based GPU memory allocator.
+///
+/// Instead of one `vkAllocateMemory` per buffer or image, memory is allocated in large blocks
+/// per memory type and buffers and images are placed into sub ranges of those blocks.
+/// Host visible blocks are persistently mapped, use `Allocation::mapped_ptr` to write to them.
+pub struct MemoryAllocator {
+    blocks: Vec<MemoryBlock>,
+    block_size: vk::DeviceSize,
+    memory_properties: vk::PhysicalDeviceMemoryProperties,
+    next_block_id: u64,
+}
+
+// Blocks only hold raw pointers to mapped device memory, access is guarded by the owning mutex.
+unsafe impl Send for MemoryAllocator {}
+
+impl MemoryAllocator {
+    pub fn new(memory_properties: vk::PhysicalDeviceMemoryProperties) -> MemoryAllocator {
+        MemoryAllocator {
+            blocks: vec![],
+            block_size: DEFAULT_BLOCK_SIZE,
+            memory_properties,
+            next_block_id: 0,
+        }
+    }
+
+    /// Finds a memory type that is allowed by `type_filter` and has the required properties.
+    pub fn find_memory_type(
+        &self,
+        type_filter: u32,
+        required_properties: vk::MemoryPropertyFlags,
+    ) -> u32 {
+        for (i, memory_type) in self.memory_properties.memory_types
+            [..self.memory_properties.memory_type_count as usize]
+            .iter()
+            .enumerate()
+        {
+            if (type_filter & (1 << i)) > 0
+                && memory_type.property_flags.contains(required_properties)
+            {
+                return i as u32;
+            }
+        }
+
+        panic!("Failed to find suitable memory type!")
+    }
+
+    /// Allocates memory for a resource with the given requirements.
+    ///
+    /// # Arguments
+    /// - `requirements`: The memory requirements of the buffer or image.
+    /// - `required_properties`: The properties the memory should have.
+    /// - `kind`: Whether a buffer or an image will be bound to the memory.
+    pub fn allocate(
+        &mut self,
+        device: &ash::Device,
+        requirements: vk::MemoryRequirements,
+        required_properties: vk::MemoryPropertyFlags,
+        kind: AllocationKind,
+    ) -> Allocation {
+        let memory_type_index =
+            self.find_memory_type(requirements.memory_type_bits, required_properties);
+
+        for block in self.blocks.iter_mut() {
+            if block.memory_type_index != memory_type_index || block.kind != kind {
+                continue;
+            }
+
+            if let Some(offset) = block
+                .free_list
+                .allocate(requirements.size, requirements.alignment)
+            {
+                block.allocation_count += 1;
+                return Self::allocation_in_block(block, offset, requirements.size);
+            }
+        }
+
+        let block_size = self.block_size.max(requirements.size);
+        let mut block = self.allocate_block(device, block_size, memory_type_index, kind);
+
+        let offset = block
+            .free_list
+            .allocate(requirements.size, requirements.alignment)
+            .unwrap();
+        block.allocation_count += 1;
+
+        let allocation = Self::allocation_in_block(&block, offset, requirements.size);
+        self.blocks.push(block);
+
+        allocation
+    }
+
+    /// Frees the sub range of the allocation, the block is released when it becomes empty.
+    pub fn free(&mut self, device: &ash::Device, allocation: &Allocation) {
+        let index = self
+            .blocks
+            .iter()
+            .position(|block| block.id == allocation.block_id)
+            .expect("Allocation does not belong to this allocator!");
+
+        let block = &mut self.blocks[index];
+        block.free_list.free(allocation.offset, allocation.size);
+        block.allocation_count -= 1;
+
+        if block.allocation_count == 0 {
+            let block = self.blocks.remove(index);
+            unsafe {
+                Self::free_block(device, &block);
+            }
+        }
+    }
+
+    /// Returns the memory usage for every memory heap.
+    pub fn statistics(&self) -> Vec<HeapStatistics> {
+        let mut statistics = self.memory_properties.memory_heaps
+            [..self.memory_properties.memory_heap_count as usize]
+            .iter()
+            .enumerate()
+            .map(|(heap_index, heap)| HeapStatistics {
+                heap_index: heap_index as u32,
+                heap_size: heap.size,
+                ..Default::default()
+            })
+            .collect::<Vec<HeapStatistics>>();
+
+        for block in self.blocks.iter() {
+            let heap_index =
+                self.memory_properties.memory_types[block.memory_type_index as usize].heap_index;
+            let heap = &mut statistics[heap_index as usize];
+
+            heap.block_count += 1;
+            heap.allocation_count += block.allocation_count;
+            heap.reserved_bytes += block.size;
+            heap.used_bytes += block.size - block.free_list.free_bytes();
+        }
+
+        statistics
+    }
+
+    /// Frees all memory blocks.
+    pub unsafe fn destroy(&mut self, device: &ash::Device) {
+        for block in self.blocks.drain(..) {
+            Self::free_block(device, &block);
+        }
+    }
+
+    fn allocate_block(
+        &mut self,
+        device: &ash::Device,
+        size: vk::DeviceSize,
+        memory_type_index: u32,
+        kind: AllocationKind,
+    ) -> MemoryBlock {
+        let allocate_info = vk::MemoryAllocateInfo::builder()
+            .allocation_size(size)
+            .memory_type_index(memory_type_index)
+            .build();
+
+        let memory = unsafe {
+            device
+                .allocate_memory(&allocate_info, None)
+                .expect("Failed to allocate memory block!")
+        };
+
+        let is_host_visible = self.memory_properties.memory_types[memory_type_index as usize]
+            .property_flags
+            .contains(vk::MemoryPropertyFlags::HOST_VISIBLE);
+
+        let mapped_ptr = if is_host_visible {
+            unsafe {
+                device
+                    .map_memory(memory, 0, vk::WHOLE_SIZE, vk::MemoryMapFlags::empty())
+                    .expect("Failed to Map Memory") as *mut u8
+            }
+        } else {
+            ptr::null_mut()
+        };
+
+        let id = self.next_block_id;
+        self.next_block_id += 1;
+
+        MemoryBlock {
+            id,
+            memory,
+            size,
+            memory_type_index,
+            kind,
+            free_list: FreeList::new(size),
+            mapped_ptr,
+            allocation_count: 0,
+        }
+    }
+
+    unsafe fn free_block(device: &ash::Device, block: &MemoryBlock) {
+        if !block.mapped_ptr.is_null() {
+            device.unmap_memory(block.memory);
+        }
+        device.free_memory(block.memory, None);
+    }
+
+    fn allocation_in_block(
+        block: &MemoryBlock,
+        offset: vk::DeviceSize,
+        size: vk::DeviceSize,
+    ) -> Allocation {
+        let mapped_ptr = if block.mapped_ptr.is_null() {
+            ptr::null_mut()
+        } else {
+            unsafe { block.mapped_ptr.add(offset as usize) }
+        };
+
+        Allocation {
+            memory: block.memory,
+            offset,
+            size,
+            block_id: block.id,
+            mapped_ptr,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::buffers::allocator::FreeList;
+
+    #[test]
+    fn allocate_aligned() {
+        let mut free_list = FreeList::new(1024);
+
+        assert_eq!(free_list.allocate(10, 1), Some(0));
+        assert_eq!(free_list.allocate(100, 256), Some(256));
+        assert_eq!(free_list.allocate(1024, 1), None);
+        assert_eq!(free_list.free_bytes(), 1024 - 110);
+    }
+
+    #[test]
+    fn free_merges_ranges() {
+        let mut free_list = FreeList::new(300);
+
+        let a = free_list.allocate(100, 1).unwrap();
+        let b = free_list.allocate(100, 1).unwrap();
+        let c = free_list.allocate(100, 1).unwrap();
+
+        free_list.free(a, 100);
+        free_list.free(c, 100);
+        free_list.free(b, 100);
+
+        assert_eq!(free_list.allocate(300, 1), Some(0));
+    }
+}
@@ -0,0 +1,177 @@
+use ash::{version::DeviceV1_0, vk};
+
+/// A pipeline stage together with the memory accesses it makes, one side of a barrier.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryAccess {
+    pub stage: vk::PipelineStageFlags,
+    pub access: vk::AccessFlags,
+}
+
+impl MemoryAccess {
+    pub const COMPUTE_READ: MemoryAccess = MemoryAccess {
+        stage: vk::PipelineStageFlags::COMPUTE_SHADER,
+        access: vk::AccessFlags::SHADER_READ,
+    };
+    pub const COMPUTE_WRITE: MemoryAccess = MemoryAccess {
+        stage: vk::PipelineStageFlags::COMPUTE_SHADER,
+        access: vk::AccessFlags::SHADER_WRITE,
+    };
+    /// Vertex and index buffers read by draws.
+    pub const VERTEX_INPUT: MemoryAccess = MemoryAccess {
+        stage: vk::PipelineStageFlags::VERTEX_INPUT,
+        access: vk::AccessFlags::from_raw(
+            vk::AccessFlags::VERTEX_ATTRIBUTE_READ.as_raw() | vk::AccessFlags::INDEX_READ.as_raw(),
+        ),
+    };
+    /// Arguments of indirect draws and dispatches.
+    pub const INDIRECT: MemoryAccess = MemoryAccess {
+        stage: vk::PipelineStageFlags::DRAW_INDIRECT,
+        access: vk::AccessFlags::INDIRECT_COMMAND_READ,
+    };
+    pub const VERTEX_SHADER_READ: MemoryAccess = MemoryAccess {
+        stage: vk::PipelineStageFlags::VERTEX_SHADER,
+        access: vk::AccessFlags::SHADER_READ,
+    };
+    pub const FRAGMENT_SHADER_READ: MemoryAccess = MemoryAccess {
+        stage: vk::PipelineStageFlags::FRAGMENT_SHADER,
+        access: vk::AccessFlags::SHADER_READ,
+    };
+    pub const TRANSFER_READ: MemoryAccess = MemoryAccess {
+        stage: vk::PipelineStageFlags::TRANSFER,
+        access: vk::AccessFlags::TRANSFER_READ,
+    };
+    pub const TRANSFER_WRITE: MemoryAccess = MemoryAccess {
+        stage: vk::PipelineStageFlags::TRANSFER,
+        access: vk::AccessFlags::TRANSFER_WRITE,
+    };
+    /// Color attachments written, and read for blending, by a render pass.
+    pub const COLOR_ATTACHMENT: MemoryAccess = MemoryAccess {
+        stage: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+        access: vk::AccessFlags::from_raw(
+            vk::AccessFlags::COLOR_ATTACHMENT_READ.as_raw()
+                | vk::AccessFlags::COLOR_ATTACHMENT_WRITE.as_raw(),
+        ),
+    };
+    /// Depth attachments tested and written by a render pass.
+    pub const DEPTH_ATTACHMENT: MemoryAccess = MemoryAccess {
+        stage: vk::PipelineStageFlags::from_raw(
+            vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS.as_raw()
+                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS.as_raw(),
+        ),
+        access: vk::AccessFlags::from_raw(
+            vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ.as_raw()
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE.as_raw(),
+        ),
+    };
+    /// Storage images and buffers written by compute shaders that are also read by them.
+    pub const COMPUTE_READ_WRITE: MemoryAccess = MemoryAccess {
+        stage: vk::PipelineStageFlags::COMPUTE_SHADER,
+        access: vk::AccessFlags::from_raw(
+            vk::AccessFlags::SHADER_READ.as_raw() | vk::AccessFlags::SHADER_WRITE.as_raw(),
+        ),
+    };
+    /// Swapchain images handed to the presentation engine, the present waits on a semaphore.
+    pub const PRESENT: MemoryAccess = MemoryAccess {
+        stage: vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+        access: vk::AccessFlags::empty(),
+    };
+    pub const HOST_READ: MemoryAccess = MemoryAccess {
+        stage: vk::PipelineStageFlags::HOST,
+        access: vk::AccessFlags::HOST_READ,
+    };
+    /// Nothing has to be waited for, for example an image whose old contents are discarded.
+    pub const NONE: MemoryAccess = MemoryAccess {
+        stage: vk::PipelineStageFlags::TOP_OF_PIPE,
+        access: vk::AccessFlags::empty(),
+    };
+
+    /// Whether the access writes memory, later accesses then have to wait for it.
+    pub fn is_write(&self) -> bool {
+        self.access.intersects(
+            vk::AccessFlags::SHADER_WRITE
+                | vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE
+                | vk::AccessFlags::TRANSFER_WRITE
+                | vk::AccessFlags::HOST_WRITE
+                | vk::AccessFlags::MEMORY_WRITE,
+        )
+    }
+}
+
+/// One side of an image barrier, the image is in `layout` while it is accessed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImageAccess {
+    pub access: MemoryAccess,
+    pub layout: vk::ImageLayout,
+}
+
+impl ImageAccess {
+    pub fn new(access: MemoryAccess, layout: vk::ImageLayout) -> ImageAccess {
+        ImageAccess { access, layout }
+    }
+}
+
+/// Records a barrier that makes the accesses `src` made to the whole buffer available to `dst`.
+pub fn cmd_buffer_barrier(
+    device: &ash::Device,
+    command_buffer: vk::CommandBuffer,
+    buffer: vk::Buffer,
+    src: MemoryAccess,
+    dst: MemoryAccess,
+) {
+    let buffer_barriers = [vk::BufferMemoryBarrier::builder()
+        .src_access_mask(src.access)
+        .dst_access_mask(dst.access)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .buffer(buffer)
+        .offset(0)
+        .size(vk::WHOLE_SIZE)
+        .build()];
+
+    unsafe {
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            src.stage,
+            dst.stage,
+            vk::DependencyFlags::empty(),
+            &[],
+            &buffer_barriers,
+            &[],
+        );
+    }
+}
+
+/// Records a barrier between the accesses to an image, it transitions the image from the layout of `src`
+/// to the layout of `dst`.
+pub fn cmd_image_barrier(
+    device: &ash::Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    subresource_range: vk::ImageSubresourceRange,
+    src: ImageAccess,
+    dst: ImageAccess,
+) {
+    let image_barriers = [vk::ImageMemoryBarrier::builder()
+        .src_access_mask(src.access.access)
+        .dst_access_mask(dst.access.access)
+        .old_layout(src.layout)
+        .new_layout(dst.layout)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(subresource_range)
+        .build()];
+
+    unsafe {
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            src.access.stage,
+            dst.access.stage,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &image_barriers,
+        );
+    }
+}
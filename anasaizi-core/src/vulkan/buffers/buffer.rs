@@ -1,14 +1,14 @@
 use crate::{
     engine::RenderContext,
-    vulkan::{begin_single_time_command, end_single_time_command},
+    vulkan::{Allocation, AllocationKind, VkLogicalDevice},
 };
 use ash::{
     version::DeviceV1_0,
     vk,
-    vk::{Buffer, DeviceMemory, Extent2D, Extent3D, Offset3D},
+    vk::{Buffer, Extent2D, Extent3D, Offset3D},
 };
 
-/// Creates and allocates a vulkan buffer.
+/// Creates a vulkan buffer and binds it to memory from the device allocator.
 ///
 /// # Arguments
 /// - `size`: The size in bytes of the buffer that is to be created.
@@ -19,9 +19,26 @@ pub fn create_allocate_vk_buffer(
     size: u64,
     usage: vk::BufferUsageFlags,
     flags: vk::MemoryPropertyFlags,
-) -> (Buffer, DeviceMemory) {
-    let device = render_context.device();
+) -> (Buffer, Allocation) {
+    allocate_vk_buffer(render_context.logical_device(), size, usage, flags)
+}
+
+/// Destroys a buffer created with `create_allocate_vk_buffer` and frees its allocation.
+pub fn destroy_vk_buffer(
+    render_context: &RenderContext,
+    buffer: vk::Buffer,
+    allocation: &Allocation,
+) {
+    free_vk_buffer(render_context.logical_device(), buffer, allocation);
+}
 
+/// Same as `create_allocate_vk_buffer` for when there is no render context yet.
+pub(crate) fn allocate_vk_buffer(
+    device: &VkLogicalDevice,
+    size: u64,
+    usage: vk::BufferUsageFlags,
+    flags: vk::MemoryPropertyFlags,
+) -> (Buffer, Allocation) {
     // Create buffer.
     let buffer_create_info = vk::BufferCreateInfo::builder()
         .size(size)
@@ -35,63 +52,29 @@ pub fn create_allocate_vk_buffer(
             .expect("Could not create vertex buffer.")
     };
 
-    // Allocate buffer.
+    // Place the buffer in a shared memory block.
     let mem_requirements = unsafe { device.get_buffer_memory_requirements(buffer) };
 
-    let memory_type = render_context.find_memory_type(mem_requirements.memory_type_bits, flags);
-
-    let allocate_info = vk::MemoryAllocateInfo::builder()
-        .allocation_size(mem_requirements.size)
-        .memory_type_index(memory_type)
-        .build();
-
-    let buffer_memory = unsafe {
-        device
-            .allocate_memory(&allocate_info, None)
-            .expect("Could not allocate memory for buffer image.")
-    };
+    let allocation = device.allocate(mem_requirements, flags, AllocationKind::Buffer);
 
     unsafe {
         device
-            .bind_buffer_memory(buffer, buffer_memory, 0)
+            .bind_buffer_memory(buffer, allocation.memory(), allocation.offset())
             .expect("Failed to bind Buffer");
     }
 
-    (buffer, buffer_memory)
+    (buffer, allocation)
 }
 
-/// Copy one buffer over to an other buffer.
-///
-/// # Arguments
-/// - `submit_queue`: The queue that will be used to execute this copy command on.
-/// - `command_pool`: The pool that will be used to allocate the command buffer, used for copy operation, from.
-/// - `src_buffer`: The source buffer that will be copied to the `dst_buffer`.
-/// - `dst_buffer`: The destination buffer into which the data from `src_buffer` will be copied.
-/// - `size`: The size of data that will be copied. Offset is 0.
-pub fn copy_buffer(
-    render_context: &RenderContext,
-    src_buffer: vk::Buffer,
-    dst_buffer: vk::Buffer,
-    size: vk::DeviceSize,
+pub(crate) fn free_vk_buffer(
+    device: &VkLogicalDevice,
+    buffer: vk::Buffer,
+    allocation: &Allocation,
 ) {
-    let command_buffer = begin_single_time_command(render_context);
-
     unsafe {
-        let copy_regions = [vk::BufferCopy {
-            src_offset: 0,
-            dst_offset: 0,
-            size,
-        }];
-
-        render_context.device().cmd_copy_buffer(
-            command_buffer,
-            src_buffer,
-            dst_buffer,
-            &copy_regions,
-        );
+        device.destroy_buffer(buffer, None);
     }
-
-    end_single_time_command(render_context, &command_buffer);
+    device.free_allocation(allocation);
 }
 
 /// Copy one buffer over to an other buffer.
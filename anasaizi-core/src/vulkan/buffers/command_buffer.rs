@@ -1,4 +1,7 @@
-use crate::vulkan::{FrameBuffers, VkCommandPool, VkLogicalDevice, VkPipeline, VkRenderPass};
+use crate::vulkan::{
+    cmd_buffer_barrier, cmd_image_barrier, ComputePipeline, ImageAccess, MemoryAccess,
+    VkCommandPool, VkLogicalDevice, VkPipeline, VkRenderPass,
+};
 use ash::{version::DeviceV1_0, vk, vk::CommandBuffer};
 
 /// A Vulkan command buffer.
@@ -13,63 +16,56 @@ pub struct CommandBuffers {
 }
 
 impl CommandBuffers {
-    /// Begins the render session.
-    ///
-    /// 1. Begins the renderpass.
-    /// 2. Begins the commandbuffer recording.
-    pub fn begin_session(
-        &mut self,
-        device: &VkLogicalDevice,
-        render_pass: &VkRenderPass,
-        surface_extent: vk::Extent2D,
-        framebuffers: &FrameBuffers,
-        index: usize,
-    ) {
+    /// Begins recording the command buffer for the given index.
+    pub fn begin_recording(&mut self, device: &VkLogicalDevice, index: usize) {
         let command_buffer_begin_info = vk::CommandBufferBeginInfo::builder()
             .flags(vk::CommandBufferUsageFlags::SIMULTANEOUS_USE);
         self.active_buffer = index;
 
-        let command_buffer = self.current();
-
         unsafe {
             device
-                .begin_command_buffer(command_buffer, &command_buffer_begin_info)
+                .begin_command_buffer(self.current(), &command_buffer_begin_info)
                 .expect("Failed to begin recording Command Buffer at beginning!");
         }
+    }
 
-        let clear_values = [
-            vk::ClearValue {
-                color: vk::ClearColorValue {
-                    float32: [0.0, 0.0, 0.0, 0.0],
-                },
-            },
-            vk::ClearValue {
-                depth_stencil: vk::ClearDepthStencilValue {
-                    depth: 1.0,
-                    stencil: 0,
-                },
-            },
-        ];
-
+    /// Begins the renderpass on the framebuffer.
+    ///
+    /// # Arguments
+    /// - `clear_values`: One clear value for every attachment of the render pass.
+    pub fn begin_render_pass(
+        &self,
+        device: &VkLogicalDevice,
+        render_pass: &VkRenderPass,
+        extent: vk::Extent2D,
+        framebuffer: vk::Framebuffer,
+        clear_values: &[vk::ClearValue],
+    ) {
         let render_pass_begin_info = vk::RenderPassBeginInfo::builder()
             .render_area(vk::Rect2D {
                 offset: vk::Offset2D { x: 0, y: 0 },
-                extent: surface_extent,
+                extent,
             })
-            .framebuffer(framebuffers.get(index))
-            .clear_values(&clear_values)
+            .framebuffer(framebuffer)
+            .clear_values(clear_values)
             .render_pass(**render_pass)
             .build();
 
         unsafe {
             device.cmd_begin_render_pass(
-                command_buffer,
+                self.current(),
                 &render_pass_begin_info,
                 vk::SubpassContents::INLINE,
             );
         }
     }
 
+    pub fn end_render_pass(&self, device: &VkLogicalDevice) {
+        unsafe {
+            device.cmd_end_render_pass(self.current());
+        }
+    }
+
     /// Binds a pipeline to the current render session.
     pub fn bind_pipeline(&self, device: &VkLogicalDevice, pipeline: &VkPipeline) {
         let command_buffer = self.current();
@@ -79,17 +75,53 @@ impl CommandBuffers {
         };
     }
 
-    /// Ends the render session.
-    ///
-    /// 1. Ends the renderpass.
-    /// 2. Ends the commandbuffer recording.
-    pub fn end_session(&self, device: &VkLogicalDevice) {
-        let command_buffer = self.current();
+    /// Binds a compute pipeline, it does not disturb the bound graphics pipeline.
+    pub fn bind_compute_pipeline(&self, device: &VkLogicalDevice, pipeline: &ComputePipeline) {
+        unsafe {
+            device.cmd_bind_pipeline(self.current(), vk::PipelineBindPoint::COMPUTE, **pipeline)
+        };
+    }
+
+    /// Dispatches the bound compute pipeline with the given number of workgroups, outside of a render pass.
+    pub fn dispatch(&self, device: &VkLogicalDevice, group_count: [u32; 3]) {
+        unsafe {
+            device.cmd_dispatch(
+                self.current(),
+                group_count[0],
+                group_count[1],
+                group_count[2],
+            )
+        };
+    }
+
+    /// Records a barrier on the whole buffer, see `cmd_buffer_barrier`.
+    pub fn buffer_barrier(
+        &self,
+        device: &VkLogicalDevice,
+        buffer: vk::Buffer,
+        src: MemoryAccess,
+        dst: MemoryAccess,
+    ) {
+        cmd_buffer_barrier(device, self.current(), buffer, src, dst);
+    }
+
+    /// Records a barrier on the image, see `cmd_image_barrier`.
+    pub fn image_barrier(
+        &self,
+        device: &VkLogicalDevice,
+        image: vk::Image,
+        subresource_range: vk::ImageSubresourceRange,
+        src: ImageAccess,
+        dst: ImageAccess,
+    ) {
+        cmd_image_barrier(device, self.current(), image, subresource_range, src, dst);
+    }
 
+    /// Ends recording the command buffer, it can be submitted after this.
+    pub fn end_recording(&self, device: &VkLogicalDevice) {
         unsafe {
-            device.cmd_end_render_pass(command_buffer);
             device
-                .end_command_buffer(command_buffer)
+                .end_command_buffer(self.current())
                 .expect("Failed to record Command Buffer at Ending!");
         }
     }
@@ -1,4 +1,4 @@
-use crate::vulkan::{VkImageView, VkLogicalDevice, VkRenderPass};
+use crate::vulkan::{VkLogicalDevice, VkRenderPass};
 use ash::{version::DeviceV1_0, vk};
 use std::ops::Deref;
 
@@ -11,20 +11,20 @@ pub struct FrameBuffer {
 }
 
 impl FrameBuffer {
+    /// Creates a framebuffer.
+    ///
+    /// The attachments are ordered as the attachments of the render pass.
     pub fn create(
         device: &VkLogicalDevice,
         render_pass: &VkRenderPass,
-        image_view: vk::ImageView,
-        depth_image_view: VkImageView,
-        swapchain_extent: &vk::Extent2D,
+        attachments: &[vk::ImageView],
+        extent: &vk::Extent2D,
     ) -> FrameBuffer {
-        let attachments = [image_view, *depth_image_view];
-
         let framebuffer_create_info = vk::FramebufferCreateInfo::builder()
             .render_pass(**render_pass)
-            .attachments(&attachments)
-            .width(swapchain_extent.width)
-            .height(swapchain_extent.height)
+            .attachments(attachments)
+            .width(extent.width)
+            .height(extent.height)
             .layers(1)
             .build();
 
@@ -54,29 +54,21 @@ pub struct FrameBuffers {
 }
 
 impl FrameBuffers {
-    /// Creates a new collection of framebuffer for the given images.
+    /// Creates a new collection of framebuffers.
     ///
     /// `render_pass`: The render pass that will render into the framebuffers.
-    /// `image_views`: The image views defining the contents of the framebuffers.
-    /// `swapchain_extent`: The dimensions of the framebufers.
+    /// `attachments`: The image views of every framebuffer, for example one set for each swapchain image.
+    /// `extent`: The dimensions of the framebuffers.
     pub fn create(
         device: &VkLogicalDevice,
         render_pass: &VkRenderPass,
-        image_views: &Vec<vk::ImageView>,
-        depth_image_view: VkImageView,
-        swapchain_extent: &vk::Extent2D,
+        attachments: &[Vec<vk::ImageView>],
+        extent: &vk::Extent2D,
     ) -> FrameBuffers {
-        let mut frame_buffers = vec![];
-
-        for &image_view in image_views.iter() {
-            frame_buffers.push(FrameBuffer::create(
-                device,
-                render_pass,
-                image_view,
-                depth_image_view.clone(),
-                swapchain_extent,
-            ));
-        }
+        let frame_buffers = attachments
+            .iter()
+            .map(|attachments| FrameBuffer::create(device, render_pass, attachments, extent))
+            .collect();
 
         FrameBuffers { frame_buffers }
     }
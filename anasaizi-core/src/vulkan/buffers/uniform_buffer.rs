@@ -1,6 +1,6 @@
 use crate::{
     engine::RenderContext,
-    vulkan::{buffers::buffer::create_allocate_vk_buffer, VkLogicalDevice},
+    vulkan::{buffers::buffer::create_allocate_vk_buffer, Allocation, VkLogicalDevice},
 };
 use ash::{version::DeviceV1_0, vk};
 
@@ -8,7 +8,7 @@ use ash::{version::DeviceV1_0, vk};
 pub struct UniformBuffer {
     // There is a uniform buffer for each frame.
     buffer: Vec<vk::Buffer>,
-    buffers_memory: Vec<vk::DeviceMemory>,
+    buffers_memory: Vec<Allocation>,
     frames_count: usize,
     pub uniform_object_size: usize,
 }
@@ -46,13 +46,13 @@ impl UniformBuffer {
         for i in 0..self.frames_count {
             unsafe {
                 device.destroy_buffer(self.buffer[i], None);
-                device.free_memory(self.buffers_memory[i], None);
             }
+            device.free_allocation(&self.buffers_memory[i]);
         }
     }
 
-    pub fn buffers_memory(&self, image_index: usize) -> vk::DeviceMemory {
-        self.buffers_memory[image_index]
+    pub fn buffers_memory(&self, image_index: usize) -> &Allocation {
+        &self.buffers_memory[image_index]
     }
 
     pub fn buffers(&self, image_index: usize) -> vk::Buffer {
@@ -0,0 +1,614 @@
+use crate::vulkan::{
+    buffers::buffer::{allocate_vk_buffer, free_vk_buffer},
+    cmd_image_barrier, Allocation, ImageAccess, MemoryAccess, VkLogicalDevice,
+};
+use ash::{version::DeviceV1_0, vk};
+use std::{collections::VecDeque, mem, ptr};
+
+/// The size of the staging ring buffer, larger uploads get a dedicated staging buffer.
+pub const STAGING_RING_SIZE: vk::DeviceSize = 32 * 1024 * 1024;
+
+/// Staging offsets are aligned to this so that every copy source is texel and word aligned.
+const STAGING_ALIGNMENT: vk::DeviceSize = 16;
+
+/// Identifies a submitted batch of uploads.
+///
+/// Use `UploadContext::wait` or `UploadContext::is_complete` to check if its uploads finished.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct UploadHandle {
+    batch: u64,
+}
+
+/// How the mip levels of an uploaded image are filled from the first level.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MipGeneration {
+    /// Blits every level from the one before it, the format needs linear blit support.
+    Blit,
+    /// Downsamples the rgba data on the CPU and copies every level.
+    Cpu,
+}
+
+struct InFlightBatch {
+    id: u64,
+    fence: vk::Fence,
+    command_buffer: vk::CommandBuffer,
+    /// Staging buffers for uploads that did not fit into the ring.
+    dedicated_buffers: Vec<(vk::Buffer, Allocation)>,
+}
+
+/// Batches buffer and image uploads into a single submission.
+///
+/// Data is copied into a persistently mapped staging ring buffer and the copy commands
+/// are recorded into one command buffer. `flush` submits all recorded uploads together and
+/// returns a handle that is backed by a fence. The ring is reused once its batches completed.
+///
+/// Uploads are visible to any work that is submitted to the same queue after the flush.
+pub struct UploadContext {
+    device: VkLogicalDevice,
+    queue: vk::Queue,
+    command_pool: vk::CommandPool,
+
+    staging_buffer: vk::Buffer,
+    staging_memory: Allocation,
+    head: vk::DeviceSize,
+
+    recording: Option<vk::CommandBuffer>,
+    pending_dedicated_buffers: Vec<(vk::Buffer, Allocation)>,
+    in_flight: VecDeque<InFlightBatch>,
+    next_batch: u64,
+}
+
+impl UploadContext {
+    /// Creates a new upload context that submits to the given queue.
+    ///
+    /// The queue has to belong to the graphics queue family.
+    pub fn new(device: &VkLogicalDevice, queue: vk::Queue) -> UploadContext {
+        let command_pool_create_info = vk::CommandPoolCreateInfo::builder()
+            .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+            .queue_family_index(device.queue_family_indices().graphics_family.unwrap());
+
+        let command_pool = unsafe {
+            device
+                .create_command_pool(&command_pool_create_info, None)
+                .expect("Failed to create upload Command Pool!")
+        };
+
+        let (staging_buffer, staging_memory) = allocate_vk_buffer(
+            device,
+            STAGING_RING_SIZE,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        );
+
+        UploadContext {
+            device: device.clone(),
+            queue,
+            command_pool,
+            staging_buffer,
+            staging_memory,
+            head: 0,
+            recording: None,
+            pending_dedicated_buffers: vec![],
+            in_flight: VecDeque::new(),
+            next_batch: 1,
+        }
+    }
+
+    /// Records a copy of `data` into the start of `dst_buffer`.
+    ///
+    /// The destination buffer needs the `TRANSFER_DST` usage.
+    pub fn upload_buffer<T>(&mut self, data: &[T], dst_buffer: vk::Buffer) {
+        let size = (data.len() * mem::size_of::<T>()) as vk::DeviceSize;
+
+        if size == 0 {
+            return;
+        }
+
+        let (src_buffer, src_offset) = self.stage(data.as_ptr() as *const u8, size);
+        let command_buffer = self.command_buffer();
+
+        let copy_regions = [vk::BufferCopy {
+            src_offset,
+            dst_offset: 0,
+            size,
+        }];
+
+        unsafe {
+            self.device
+                .cmd_copy_buffer(command_buffer, src_buffer, dst_buffer, &copy_regions);
+        }
+    }
+
+    /// Records a copy of rgba `data` into the first mip level of `image` and fills the other levels.
+    ///
+    /// The image is expected in `UNDEFINED` layout and is left in `SHADER_READ_ONLY_OPTIMAL`.
+    ///
+    /// # Arguments
+    /// - `mip_levels`: The number of mip levels of the image, each level is half the size of the last.
+    /// - `mip_generation`: How the levels after the first are filled.
+    pub fn upload_image(
+        &mut self,
+        data: &[u8],
+        image: vk::Image,
+        width: u32,
+        height: u32,
+        mip_levels: u32,
+        mip_generation: MipGeneration,
+    ) {
+        let command_buffer = self.command_buffer();
+
+        cmd_image_barrier(
+            &self.device,
+            command_buffer,
+            image,
+            Self::mip_range(0, mip_levels),
+            ImageAccess::new(MemoryAccess::NONE, vk::ImageLayout::UNDEFINED),
+            ImageAccess::new(
+                MemoryAccess::TRANSFER_WRITE,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            ),
+        );
+
+        self.copy_to_image(data, image, 0, width, height);
+
+        match mip_generation {
+            MipGeneration::Blit => self.blit_mip_levels(image, width, height, mip_levels),
+            MipGeneration::Cpu => {
+                let mut level = data.to_vec();
+                let (mut level_width, mut level_height) = (width, height);
+
+                for mip_level in 1..mip_levels {
+                    level = downsample_rgba(&level, level_width, level_height);
+                    level_width = (level_width / 2).max(1);
+                    level_height = (level_height / 2).max(1);
+
+                    self.copy_to_image(&level, image, mip_level, level_width, level_height);
+                }
+
+                cmd_image_barrier(
+                    &self.device,
+                    self.command_buffer(),
+                    image,
+                    Self::mip_range(0, mip_levels),
+                    ImageAccess::new(
+                        MemoryAccess::TRANSFER_WRITE,
+                        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    ),
+                    ImageAccess::new(
+                        MemoryAccess::FRAGMENT_SHADER_READ,
+                        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                    ),
+                );
+            }
+        }
+    }
+
+    /// Returns true when there are recorded uploads that are not submitted yet.
+    pub fn has_pending_uploads(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    /// Submits all recorded uploads in a single batch.
+    ///
+    /// Returns the handle of the submitted batch, or of the last batch when nothing was recorded.
+    pub fn flush(&mut self) -> UploadHandle {
+        let command_buffer = match self.recording.take() {
+            Some(command_buffer) => command_buffer,
+            None => {
+                return UploadHandle {
+                    batch: self.next_batch - 1,
+                }
+            }
+        };
+
+        // Make the transfers visible to everything that is submitted after this batch.
+        let memory_barriers = [vk::MemoryBarrier::builder()
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(
+                vk::AccessFlags::VERTEX_ATTRIBUTE_READ
+                    | vk::AccessFlags::INDEX_READ
+                    | vk::AccessFlags::UNIFORM_READ
+                    | vk::AccessFlags::SHADER_READ,
+            )
+            .build()];
+
+        unsafe {
+            self.device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::ALL_COMMANDS,
+                vk::DependencyFlags::empty(),
+                &memory_barriers,
+                &[],
+                &[],
+            );
+
+            self.device
+                .end_command_buffer(command_buffer)
+                .expect("Failed to record upload Command Buffer!");
+        }
+
+        let fence_create_info = vk::FenceCreateInfo::builder().build();
+        let fence = unsafe {
+            self.device
+                .create_fence(&fence_create_info, None)
+                .expect("Failed to create upload Fence!")
+        };
+
+        let command_buffers = [command_buffer];
+        let submit_infos = [vk::SubmitInfo::builder()
+            .command_buffers(&command_buffers)
+            .build()];
+
+        unsafe {
+            self.device
+                .queue_submit(self.queue, &submit_infos, fence)
+                .expect("Failed to submit uploads!");
+        }
+
+        let id = self.next_batch;
+        self.next_batch += 1;
+
+        self.in_flight.push_back(InFlightBatch {
+            id,
+            fence,
+            command_buffer,
+            dedicated_buffers: mem::take(&mut self.pending_dedicated_buffers),
+        });
+
+        UploadHandle { batch: id }
+    }
+
+    /// Blocks until the uploads of the given batch completed.
+    pub fn wait(&mut self, handle: UploadHandle) {
+        if let Some(batch) = self.in_flight.iter().find(|batch| batch.id == handle.batch) {
+            unsafe {
+                self.device
+                    .wait_for_fences(&[batch.fence], true, u64::MAX)
+                    .expect("Failed to wait for upload Fence!");
+            }
+        }
+
+        self.retire_completed();
+    }
+
+    /// Returns true when the uploads of the given batch completed.
+    pub fn is_complete(&mut self, handle: UploadHandle) -> bool {
+        self.retire_completed();
+        !self.in_flight.iter().any(|batch| batch.id == handle.batch)
+    }
+
+    /// Submits the recorded uploads and blocks until all submitted uploads completed.
+    pub fn flush_and_wait(&mut self) {
+        self.flush();
+
+        let fences = self
+            .in_flight
+            .iter()
+            .map(|batch| batch.fence)
+            .collect::<Vec<vk::Fence>>();
+
+        if !fences.is_empty() {
+            unsafe {
+                self.device
+                    .wait_for_fences(&fences, true, u64::MAX)
+                    .expect("Failed to wait for upload Fence!");
+            }
+        }
+
+        self.retire_completed();
+    }
+
+    /// Waits for all uploads and destroys the staging ring and command pool.
+    pub unsafe fn destroy(&mut self) {
+        self.flush_and_wait();
+
+        free_vk_buffer(&self.device, self.staging_buffer, &self.staging_memory);
+        self.device.destroy_command_pool(self.command_pool, None);
+    }
+
+    /// Returns the command buffer that is being recorded, begins a new one when needed.
+    fn command_buffer(&mut self) -> vk::CommandBuffer {
+        if let Some(command_buffer) = self.recording {
+            return command_buffer;
+        }
+
+        let allocate_info = vk::CommandBufferAllocateInfo::builder()
+            .command_buffer_count(1)
+            .command_pool(self.command_pool)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .build();
+
+        let command_buffer = unsafe {
+            self.device
+                .allocate_command_buffers(&allocate_info)
+                .expect("Failed to allocate upload Command Buffer!")[0]
+        };
+
+        let begin_info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT)
+            .build();
+
+        unsafe {
+            self.device
+                .begin_command_buffer(command_buffer, &begin_info)
+                .expect("Failed to begin recording upload Command Buffer!");
+        }
+
+        self.recording = Some(command_buffer);
+        command_buffer
+    }
+
+    /// Copies `size` bytes into staging memory and returns the source buffer and offset to copy from.
+    fn stage(&mut self, data: *const u8, size: vk::DeviceSize) -> (vk::Buffer, vk::DeviceSize) {
+        if size > STAGING_RING_SIZE {
+            let (buffer, memory) = allocate_vk_buffer(
+                &self.device,
+                size,
+                vk::BufferUsageFlags::TRANSFER_SRC,
+                vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+            );
+
+            unsafe {
+                ptr::copy_nonoverlapping(data, memory.mapped_ptr().unwrap(), size as usize);
+            }
+
+            self.pending_dedicated_buffers.push((buffer, memory));
+            return (buffer, 0);
+        }
+
+        self.retire_completed();
+        if self.recording.is_none() && self.in_flight.is_empty() {
+            self.head = 0;
+        }
+
+        let mut offset =
+            (self.head + STAGING_ALIGNMENT - 1) / STAGING_ALIGNMENT * STAGING_ALIGNMENT;
+
+        // The ring is full, wait until every batch that reads from it completed.
+        if offset + size > STAGING_RING_SIZE {
+            self.flush_and_wait();
+            offset = 0;
+        }
+
+        unsafe {
+            let ring_ptr = self.staging_memory.mapped_ptr().unwrap();
+            ptr::copy_nonoverlapping(data, ring_ptr.add(offset as usize), size as usize);
+        }
+
+        self.head = offset + size;
+
+        (self.staging_buffer, offset)
+    }
+
+    /// Records a copy of rgba `data` into a mip level of `image`, which is in `TRANSFER_DST_OPTIMAL`.
+    fn copy_to_image(
+        &mut self,
+        data: &[u8],
+        image: vk::Image,
+        mip_level: u32,
+        width: u32,
+        height: u32,
+    ) {
+        let (src_buffer, src_offset) = self.stage(data.as_ptr(), data.len() as vk::DeviceSize);
+        let command_buffer = self.command_buffer();
+
+        let buffer_image_regions = [vk::BufferImageCopy {
+            image_subresource: vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level,
+                base_array_layer: 0,
+                layer_count: 1,
+            },
+            image_extent: vk::Extent3D {
+                width,
+                height,
+                depth: 1,
+            },
+            buffer_offset: src_offset,
+            buffer_image_height: 0,
+            buffer_row_length: 0,
+            image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+        }];
+
+        unsafe {
+            self.device.cmd_copy_buffer_to_image(
+                command_buffer,
+                src_buffer,
+                image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &buffer_image_regions,
+            );
+        }
+    }
+
+    /// Records blits that fill every mip level from the level before it.
+    ///
+    /// The first level has to be written, every level is left in `SHADER_READ_ONLY_OPTIMAL`.
+    fn blit_mip_levels(&mut self, image: vk::Image, width: u32, height: u32, mip_levels: u32) {
+        let command_buffer = self.command_buffer();
+        let (mut level_width, mut level_height) = (width as i32, height as i32);
+
+        for mip_level in 1..mip_levels {
+            let (next_width, next_height) = ((level_width / 2).max(1), (level_height / 2).max(1));
+
+            cmd_image_barrier(
+                &self.device,
+                command_buffer,
+                image,
+                Self::mip_range(mip_level - 1, 1),
+                ImageAccess::new(
+                    MemoryAccess::TRANSFER_WRITE,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                ),
+                ImageAccess::new(
+                    MemoryAccess::TRANSFER_READ,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                ),
+            );
+
+            let subresource = |mip_level| vk::ImageSubresourceLayers {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                mip_level,
+                base_array_layer: 0,
+                layer_count: 1,
+            };
+
+            let regions = [vk::ImageBlit {
+                src_subresource: subresource(mip_level - 1),
+                src_offsets: [
+                    vk::Offset3D { x: 0, y: 0, z: 0 },
+                    vk::Offset3D {
+                        x: level_width,
+                        y: level_height,
+                        z: 1,
+                    },
+                ],
+                dst_subresource: subresource(mip_level),
+                dst_offsets: [
+                    vk::Offset3D { x: 0, y: 0, z: 0 },
+                    vk::Offset3D {
+                        x: next_width,
+                        y: next_height,
+                        z: 1,
+                    },
+                ],
+            }];
+
+            unsafe {
+                self.device.cmd_blit_image(
+                    command_buffer,
+                    image,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                    image,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    &regions,
+                    vk::Filter::LINEAR,
+                );
+            }
+
+            level_width = next_width;
+            level_height = next_height;
+        }
+
+        // Every level but the last was read by a blit.
+        let read_access = ImageAccess::new(
+            MemoryAccess::FRAGMENT_SHADER_READ,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        );
+
+        if mip_levels > 1 {
+            cmd_image_barrier(
+                &self.device,
+                command_buffer,
+                image,
+                Self::mip_range(0, mip_levels - 1),
+                ImageAccess::new(
+                    MemoryAccess::TRANSFER_READ,
+                    vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                ),
+                read_access,
+            );
+        }
+
+        cmd_image_barrier(
+            &self.device,
+            command_buffer,
+            image,
+            Self::mip_range(mip_levels - 1, 1),
+            ImageAccess::new(
+                MemoryAccess::TRANSFER_WRITE,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            ),
+            read_access,
+        );
+    }
+
+    fn mip_range(base_mip_level: u32, level_count: u32) -> vk::ImageSubresourceRange {
+        vk::ImageSubresourceRange {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            base_mip_level,
+            level_count,
+            base_array_layer: 0,
+            layer_count: 1,
+        }
+    }
+
+    /// Frees the resources of the batches whose fence is signaled.
+    fn retire_completed(&mut self) {
+        while let Some(batch) = self.in_flight.front() {
+            let is_signaled = unsafe {
+                self.device
+                    .get_fence_status(batch.fence)
+                    .expect("Failed to get upload Fence status!")
+            };
+
+            if !is_signaled {
+                break;
+            }
+
+            let batch = self.in_flight.pop_front().unwrap();
+
+            unsafe {
+                self.device.destroy_fence(batch.fence, None);
+                self.device
+                    .free_command_buffers(self.command_pool, &[batch.command_buffer]);
+            }
+
+            for (buffer, memory) in batch.dedicated_buffers.iter() {
+                free_vk_buffer(&self.device, *buffer, memory);
+            }
+        }
+    }
+}
+
+/// Halves rgba8 `data` in both dimensions with a box filter, odd edges repeat the last texel.
+fn downsample_rgba(data: &[u8], width: u32, height: u32) -> Vec<u8> {
+    let (width, height) = (width as usize, height as usize);
+    let (dst_width, dst_height) = ((width / 2).max(1), (height / 2).max(1));
+    let mut downsampled = Vec::with_capacity(dst_width * dst_height * 4);
+
+    for y in 0..dst_height {
+        for x in 0..dst_width {
+            let texels = [
+                (2 * x, 2 * y),
+                ((2 * x + 1).min(width - 1), 2 * y),
+                (2 * x, (2 * y + 1).min(height - 1)),
+                ((2 * x + 1).min(width - 1), (2 * y + 1).min(height - 1)),
+            ];
+
+            for channel in 0..4 {
+                let sum: u32 = texels
+                    .iter()
+                    .map(|(x, y)| data[(y * width + x) * 4 + channel] as u32)
+                    .sum();
+
+                downsampled.push(((sum + 2) / 4) as u8);
+            }
+        }
+    }
+
+    downsampled
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::buffers::upload_context::downsample_rgba;
+
+    #[test]
+    fn downsample_averages_texels() {
+        let data = [
+            0, 0, 0, 255, 255, 0, 0, 255, //
+            0, 255, 0, 255, 0, 0, 255, 255,
+        ];
+
+        assert_eq!(downsample_rgba(&data, 2, 2), vec![64, 64, 64, 255]);
+    }
+
+    #[test]
+    fn downsample_keeps_one_texel_wide_images() {
+        let data = [10, 20, 30, 40, 30, 40, 50, 60, 50, 60, 70, 80];
+
+        assert_eq!(downsample_rgba(&data, 1, 3), vec![20, 30, 40, 50]);
+    }
+}
@@ -1,49 +1,28 @@
 use crate::{
     engine::RenderContext,
-    vulkan::buffers::buffer::{copy_buffer, create_allocate_vk_buffer},
+    vulkan::{buffers::buffer::create_allocate_vk_buffer, Allocation, VkLogicalDevice},
 };
 use ash::{version::DeviceV1_0, vk};
 use core::ops::Deref;
 use std::{any::TypeId, mem, mem::size_of};
 
-/// An allocated vulkan buffer containing vertices.
+/// An allocated vulkan buffer containing vertices, indices or shader data.
 #[derive(Clone)]
 pub struct GPUBuffer {
     buffer: vk::Buffer,
-    buffer_memory: vk::DeviceMemory,
+    buffer_memory: Allocation,
     count: usize,
+    // The distance in bytes between two elements.
+    stride: u64,
 }
 
 impl GPUBuffer {
     /// Creates a new GPU buffer from the given data.
+    ///
+    /// The upload is batched, it completes with the next `UploadContext::flush`.
     pub fn create<U: 'static>(render_context: &RenderContext, data: &Vec<U>) -> GPUBuffer {
-        let device = render_context.device();
-        // Allocate the staging buffer.
         let buffer_size = (size_of::<U>() * data.len()) as u64;
 
-        let (staging_buffer, staging_buffer_memory) = create_allocate_vk_buffer(
-            render_context,
-            buffer_size,
-            vk::BufferUsageFlags::TRANSFER_SRC,
-            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
-        );
-
-        // Copy the indices into the allocated buffer.
-        unsafe {
-            let data_ptr = device
-                .map_memory(
-                    staging_buffer_memory,
-                    0,
-                    buffer_size,
-                    vk::MemoryMapFlags::empty(),
-                )
-                .expect("Failed to Map Memory") as *mut U;
-
-            data_ptr.copy_from_nonoverlapping(data.as_ptr(), data.len());
-
-            device.unmap_memory(staging_buffer_memory);
-        }
-
         let buffer_flags = if TypeId::of::<U>() == TypeId::of::<u16>()
             || TypeId::of::<U>() == TypeId::of::<u32>()
         {
@@ -60,28 +39,85 @@ impl GPUBuffer {
             vk::MemoryPropertyFlags::DEVICE_LOCAL | vk::MemoryPropertyFlags::HOST_VISIBLE,
         );
 
-        // Copy data from CPU staging buffer to GPU
-        copy_buffer(render_context, staging_buffer, buffer, buffer_size);
+        // Copy the data through the staging ring, it is submitted with the next flush.
+        render_context
+            .upload_context()
+            .upload_buffer(data.as_slice(), buffer);
 
-        // Clean up the staging buffer.
-        unsafe {
-            device.destroy_buffer(staging_buffer, None);
-            device.free_memory(staging_buffer_memory, None)
+        GPUBuffer {
+            buffer,
+            buffer_memory,
+            count: data.len() as usize,
+            stride: size_of::<U>() as u64,
         }
+    }
+
+    /// Creates a storage buffer with the given elements, shaders access it as an array in a `buffer` block.
+    ///
+    /// The memory is host visible, update it with `update_buffer_content` or `write_element`.
+    pub fn storage<U: Copy>(render_context: &RenderContext, data: &[U]) -> GPUBuffer {
+        let stride = size_of::<U>() as u64;
+
+        let gpu_buffer = Self::host_visible(
+            render_context,
+            vk::BufferUsageFlags::STORAGE_BUFFER,
+            data.len(),
+            stride,
+        );
+        gpu_buffer.update_buffer_content(data);
+
+        gpu_buffer
+    }
+
+    /// Creates a uniform buffer with room for `count` elements of `U`, for binding as a dynamic uniform buffer.
+    ///
+    /// Every element starts at a multiple of `minUniformBufferOffsetAlignment`, select one per draw with
+    /// `dynamic_offset` and write it with `write_element`.
+    pub fn dynamic_uniform<U: Copy>(render_context: &RenderContext, count: usize) -> GPUBuffer {
+        let alignment = render_context
+            .logical_device()
+            .device_properties()
+            .limits
+            .min_uniform_buffer_offset_alignment;
+
+        Self::host_visible(
+            render_context,
+            vk::BufferUsageFlags::UNIFORM_BUFFER,
+            count,
+            aligned_stride(size_of::<U>() as u64, alignment),
+        )
+    }
+
+    fn host_visible(
+        render_context: &RenderContext,
+        usage: vk::BufferUsageFlags,
+        count: usize,
+        stride: u64,
+    ) -> GPUBuffer {
+        // Vulkan does not allow empty buffers.
+        let buffer_size = stride * count.max(1) as u64;
+
+        let (buffer, buffer_memory) = create_allocate_vk_buffer(
+            render_context,
+            buffer_size,
+            usage,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        );
 
         GPUBuffer {
             buffer,
             buffer_memory,
-            count: data.len() as usize,
+            count,
+            stride,
         }
     }
 
     /// Destroys the buffer and its memory.
-    pub fn destroy(&self, device: &ash::Device) {
+    pub fn destroy(&self, device: &VkLogicalDevice) {
         unsafe {
             device.destroy_buffer(self.buffer, None);
-            device.free_memory(self.buffer_memory, None)
         }
+        device.free_allocation(&self.buffer_memory);
     }
 
     /// Returns the number of indices.
@@ -89,23 +125,71 @@ impl GPUBuffer {
         self.count
     }
 
+    /// Names the buffer in validation messages and captures.
+    pub fn set_debug_name(&self, device: &VkLogicalDevice, name: &str) {
+        device.set_debug_name(self.buffer, name);
+    }
+
+    /// The distance in bytes between two elements, including the padding of dynamic uniform buffers.
+    pub fn stride(&self) -> u64 {
+        self.stride
+    }
+
+    /// The size in bytes of all elements.
+    pub fn size(&self) -> u64 {
+        self.stride * self.count as u64
+    }
+
+    /// The offset of the element at `index`, passed as dynamic offset when binding the descriptor set.
+    pub fn dynamic_offset(&self, index: usize) -> u32 {
+        (self.stride * index as u64) as u32
+    }
+
+    /// Writes the element at `index`.
+    pub fn write_element<T: Copy>(&self, index: usize, element: &T) {
+        assert!(
+            index < self.count && size_of::<T>() as u64 <= self.stride,
+            "Element does not fit in the buffer!"
+        );
+
+        unsafe {
+            let data_ptr = self
+                .buffer_memory
+                .mapped_ptr()
+                .expect("Buffer memory is not host visible!")
+                .add(self.stride as usize * index);
+
+            std::ptr::copy_nonoverlapping(
+                element as *const T as *const u8,
+                data_ptr,
+                size_of::<T>(),
+            );
+        }
+    }
+
     /// Updates the buffer contents with the given data.
     ///
     /// Make sure that the given data is the same as what is stored in the buffer.
-    pub fn update_buffer_content<T: Copy>(&self, device: &ash::Device, data: &[T]) {
+    pub fn update_buffer_content<T: Copy>(&self, data: &[T]) {
         unsafe {
             let size = (data.len() * mem::size_of::<T>()) as _;
 
-            let data_ptr = device
-                .map_memory(self.buffer_memory, 0, size, vk::MemoryMapFlags::empty())
-                .unwrap();
-            let mut align = ash::util::Align::new(data_ptr, mem::align_of::<T>() as _, size);
+            let data_ptr = self
+                .buffer_memory
+                .mapped_ptr()
+                .expect("Buffer memory is not host visible!");
+            let mut align =
+                ash::util::Align::new(data_ptr as *mut _, mem::align_of::<T>() as _, size);
             align.copy_from_slice(&data);
-            device.unmap_memory(self.buffer_memory);
         };
     }
 }
 
+/// Rounds the element size up to the alignment, the alignment is a power of two.
+fn aligned_stride(size: u64, alignment: u64) -> u64 {
+    size.next_multiple_of(alignment.max(1))
+}
+
 impl Deref for GPUBuffer {
     type Target = vk::Buffer;
 
@@ -113,3 +197,16 @@ impl Deref for GPUBuffer {
         &self.buffer
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::buffers::vertex_buffer::aligned_stride;
+
+    #[test]
+    fn stride_is_aligned() {
+        assert_eq!(aligned_stride(64, 256), 256);
+        assert_eq!(aligned_stride(300, 256), 512);
+        assert_eq!(aligned_stride(256, 256), 256);
+        assert_eq!(aligned_stride(12, 0), 12);
+    }
+}
@@ -0,0 +1,205 @@
+use crate::{
+    vulkan::{
+        ShaderBuilder, ShaderIOBuilder, ShaderIo, ShaderReflection, ShaderSet, ShaderSource,
+        VkLogicalDevice,
+    },
+    Error, Result,
+};
+use ash::{version::DeviceV1_0, vk};
+use std::{ffi::CString, ops::Deref, path::Path};
+
+/// A Vulkan compute pipeline.
+///
+/// A compute pipeline runs a single compute shader, it is dispatched outside of render passes with
+/// `RenderPipeline::dispatch` or `CommandBuffers::dispatch`.
+pub struct ComputePipeline {
+    pipeline: vk::Pipeline,
+    layout: vk::PipelineLayout,
+    module: vk::ShaderModule,
+    source: ShaderSource,
+    local_size: [u32; 3],
+    pub io: ShaderIo,
+}
+
+impl ComputePipeline {
+    /// Creates a new `ComputePipeline`.
+    ///
+    /// The shader is reflected, the stages of the descriptor set layout bindings and the push constant ranges
+    /// and specialization constant entries that were not supplied are derived from it.
+    /// The shader io needs no input buffer layout.
+    /// Fails when the shader can not be read or compiled, is not a compute shader or does not match the io.
+    pub fn create(
+        device: &VkLogicalDevice,
+        source: ShaderSource,
+        descriptors: ShaderIOBuilder,
+        frames: usize,
+    ) -> Result<ComputePipeline> {
+        let code = source.load()?;
+        let reflection = Self::reflect(&code)?;
+
+        let mut io = descriptors.build(device, &reflection, frames);
+        ShaderBuilder::derive_missing_constants(&reflection, &mut io);
+        reflection.validate(&io)?;
+
+        let module = ShaderSet::create_shader_module(device, code)?;
+        let layout = io.create_pipeline_layout(device);
+        let pipeline = match Self::create_pipeline(device, module, layout, &io) {
+            Ok(pipeline) => pipeline,
+            Err(error) => {
+                unsafe {
+                    device.destroy_pipeline_layout(layout, None);
+                    device.destroy_shader_module(module, None);
+                }
+                return Err(error);
+            }
+        };
+
+        Ok(ComputePipeline {
+            pipeline,
+            layout,
+            module,
+            source,
+            local_size: reflection.local_size.unwrap_or([1, 1, 1]),
+            io,
+        })
+    }
+
+    pub fn layout(&self) -> vk::PipelineLayout {
+        self.layout
+    }
+
+    /// The workgroup size of the shader.
+    pub fn local_size(&self) -> [u32; 3] {
+        self.local_size
+    }
+
+    /// The number of workgroups that covers the given number of invocations in every dimension.
+    pub fn group_count(&self, invocations: [u32; 3]) -> [u32; 3] {
+        group_count(invocations, self.local_size)
+    }
+
+    /// Returns whether the shader is read from the given file.
+    pub fn is_read_from(&self, path: &Path) -> bool {
+        self.source.is_read_from(path)
+    }
+
+    /// Reads, or compiles, the shader again and rebuilds the pipeline with it.
+    ///
+    /// The shader io is kept, the new code must match it. On failure the old shader stays in use.
+    /// The pipeline must not be in use by the device.
+    pub unsafe fn reload(&mut self, device: &VkLogicalDevice) -> Result<()> {
+        let code = self.source.load()?;
+        let reflection = Self::reflect(&code)?;
+        reflection.validate(&self.io)?;
+
+        let module = ShaderSet::create_shader_module(device, code)?;
+        let pipeline = match Self::create_pipeline(device, module, self.layout, &self.io) {
+            Ok(pipeline) => pipeline,
+            Err(error) => {
+                device.destroy_shader_module(module, None);
+                return Err(error);
+            }
+        };
+
+        device.destroy_pipeline(self.pipeline, None);
+        device.destroy_shader_module(self.module, None);
+
+        self.module = module;
+        self.local_size = reflection.local_size.unwrap_or([1, 1, 1]);
+        self.pipeline = pipeline;
+
+        Ok(())
+    }
+
+    /// Destroys the pipeline and its contents:
+    /// - Pipeline
+    /// - Pipeline layout
+    /// - Shader module
+    /// - Shader io
+    pub unsafe fn destroy(&self, device: &VkLogicalDevice) {
+        device.destroy_pipeline(self.pipeline, None);
+        device.destroy_pipeline_layout(self.layout, None);
+        device.destroy_shader_module(self.module, None);
+
+        self.io.destroy(device);
+    }
+
+    fn reflect(code: &[u8]) -> Result<ShaderReflection> {
+        let reflection = ShaderReflection::from_spirv(code)?;
+
+        if reflection.stages != vk::ShaderStageFlags::COMPUTE {
+            return Err(Error::ShaderValidation(format!(
+                "a compute pipeline needs a compute shader, but the shader is a {:?} shader",
+                reflection.stages
+            )));
+        }
+
+        Ok(reflection)
+    }
+
+    fn create_pipeline(
+        device: &VkLogicalDevice,
+        module: vk::ShaderModule,
+        layout: vk::PipelineLayout,
+        io: &ShaderIo,
+    ) -> Result<vk::Pipeline> {
+        let main_function_name = CString::new("main").unwrap();
+
+        let data = io.specialization_data_ref();
+        let specialisation_info = vk::SpecializationInfo::builder()
+            .map_entries(&io.specialization_constants)
+            .data(&data)
+            .build();
+
+        let stage = vk::PipelineShaderStageCreateInfo::builder()
+            .module(module)
+            .name(&main_function_name)
+            .stage(vk::ShaderStageFlags::COMPUTE)
+            .specialization_info(&specialisation_info)
+            .build();
+
+        let pipeline_info = [vk::ComputePipelineCreateInfo::builder()
+            .stage(stage)
+            .layout(layout)
+            .base_pipeline_index(-1)
+            .build()];
+
+        let pipelines = unsafe {
+            device
+                .logical_device()
+                .create_compute_pipelines(**device.pipeline_cache(), &pipeline_info, None)
+                .map_err(|(_, result)| result)?
+        };
+
+        Ok(pipelines[0])
+    }
+}
+
+/// Rounds the invocations up to whole workgroups.
+fn group_count(invocations: [u32; 3], local_size: [u32; 3]) -> [u32; 3] {
+    [
+        invocations[0].div_ceil(local_size[0].max(1)),
+        invocations[1].div_ceil(local_size[1].max(1)),
+        invocations[2].div_ceil(local_size[2].max(1)),
+    ]
+}
+
+impl Deref for ComputePipeline {
+    type Target = vk::Pipeline;
+
+    fn deref(&self) -> &Self::Target {
+        &self.pipeline
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::compute_pipeline::group_count;
+
+    #[test]
+    fn group_count_covers_invocations() {
+        assert_eq!(group_count([100, 1, 1], [64, 1, 1]), [2, 1, 1]);
+        assert_eq!(group_count([128, 16, 1], [64, 8, 1]), [2, 2, 1]);
+        assert_eq!(group_count([0, 1, 1], [64, 1, 1]), [0, 1, 1]);
+    }
+}
@@ -0,0 +1,361 @@
+use crate::Result;
+use ash::{extensions::ext::DebugUtils, vk};
+use std::{
+    ffi::{c_void, CStr},
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+/// The severity of a debug message, ordered from least to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MessageSeverity {
+    Verbose,
+    Info,
+    Warning,
+    Error,
+}
+
+impl MessageSeverity {
+    /// The most severe of the given severity flags.
+    pub fn from_flags(flags: vk::DebugUtilsMessageSeverityFlagsEXT) -> MessageSeverity {
+        if flags.contains(vk::DebugUtilsMessageSeverityFlagsEXT::ERROR) {
+            MessageSeverity::Error
+        } else if flags.contains(vk::DebugUtilsMessageSeverityFlagsEXT::WARNING) {
+            MessageSeverity::Warning
+        } else if flags.contains(vk::DebugUtilsMessageSeverityFlagsEXT::INFO) {
+            MessageSeverity::Info
+        } else {
+            MessageSeverity::Verbose
+        }
+    }
+
+    /// The flags of this severity and all more severe ones.
+    fn and_above(self) -> vk::DebugUtilsMessageSeverityFlagsEXT {
+        [
+            (
+                MessageSeverity::Verbose,
+                vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE,
+            ),
+            (
+                MessageSeverity::Info,
+                vk::DebugUtilsMessageSeverityFlagsEXT::INFO,
+            ),
+            (
+                MessageSeverity::Warning,
+                vk::DebugUtilsMessageSeverityFlagsEXT::WARNING,
+            ),
+            (
+                MessageSeverity::Error,
+                vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
+            ),
+        ]
+        .iter()
+        .filter(|(severity, _)| *severity >= self)
+        .fold(
+            vk::DebugUtilsMessageSeverityFlagsEXT::empty(),
+            |flags, (_, flag)| flags | *flag,
+        )
+    }
+}
+
+/// What a debug message is about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageType {
+    /// A violation of the specification or a possible mistake, reported by the validation layers.
+    Validation,
+    /// A potentially non-optimal use of Vulkan.
+    Performance,
+    /// Anything else, for example loader messages.
+    General,
+}
+
+impl MessageType {
+    /// Classifies the message, validation takes precedence over performance.
+    pub fn from_flags(flags: vk::DebugUtilsMessageTypeFlagsEXT) -> MessageType {
+        if flags.contains(vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION) {
+            MessageType::Validation
+        } else if flags.contains(vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE) {
+            MessageType::Performance
+        } else {
+            MessageType::General
+        }
+    }
+}
+
+/// A message of the Vulkan implementation or the validation layers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugMessage {
+    pub severity: MessageSeverity,
+    pub message_type: MessageType,
+    /// The name of the validation rule, for example `VUID-vkCmdDraw-None-02699`.
+    pub id_name: Option<String>,
+    pub message: String,
+    /// The debug names of the objects the message is about, see `VkLogicalDevice::set_debug_name`.
+    pub object_names: Vec<String>,
+}
+
+impl DebugMessage {
+    /// Whether this is a validation error, these fail tests in test mode.
+    pub fn is_validation_error(&self) -> bool {
+        self.severity == MessageSeverity::Error && self.message_type == MessageType::Validation
+    }
+}
+
+impl fmt::Display for DebugMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{:?}][{:?}]", self.severity, self.message_type)?;
+
+        if !self.object_names.is_empty() {
+            write!(f, "[{}]", self.object_names.join(", "))?;
+        }
+
+        write!(f, " {}", self.message)
+    }
+}
+
+/// Receives the debug messages, it is called from the thread that made the Vulkan call.
+pub trait DebugMessageSink: Send + Sync {
+    fn receive(&self, message: &DebugMessage);
+}
+
+/// Forwards the messages to the `log` crate with the `vulkan` target, the level follows the severity.
+pub struct LogSink;
+
+impl DebugMessageSink for LogSink {
+    fn receive(&self, message: &DebugMessage) {
+        let level = match message.severity {
+            MessageSeverity::Error => log::Level::Error,
+            MessageSeverity::Warning => log::Level::Warn,
+            MessageSeverity::Info => log::Level::Info,
+            MessageSeverity::Verbose => log::Level::Debug,
+        };
+
+        log::log!(target: "vulkan", level, "{}", message);
+    }
+}
+
+/// Collects the messages in memory, for example to show them in the editor or inspect them in tests.
+#[derive(Default)]
+pub struct MemorySink {
+    messages: Mutex<Vec<DebugMessage>>,
+}
+
+impl MemorySink {
+    pub fn new() -> MemorySink {
+        MemorySink::default()
+    }
+
+    /// The messages that were received so far.
+    pub fn messages(&self) -> Vec<DebugMessage> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    /// Takes the messages that were received so far.
+    pub fn take(&self) -> Vec<DebugMessage> {
+        std::mem::take(&mut *self.messages.lock().unwrap())
+    }
+}
+
+impl DebugMessageSink for MemorySink {
+    fn receive(&self, message: &DebugMessage) {
+        self.messages.lock().unwrap().push(message.clone());
+    }
+}
+
+/// The state the callback reaches through its user data pointer.
+struct MessengerState {
+    sink: Arc<dyn DebugMessageSink>,
+    min_severity: MessageSeverity,
+    fail_on_error: bool,
+    validation_errors: Mutex<Vec<DebugMessage>>,
+}
+
+impl MessengerState {
+    fn dispatch(&self, message: DebugMessage) {
+        if message.severity < self.min_severity {
+            return;
+        }
+
+        self.sink.receive(&message);
+
+        if self.fail_on_error && message.is_validation_error() {
+            self.validation_errors.lock().unwrap().push(message);
+        }
+    }
+}
+
+/// Routes the messages of the Vulkan implementation and validation layers to a `DebugMessageSink`.
+///
+/// In test mode the validation errors are also recorded, dropping the messenger then panics when
+/// there were any, which fails the test that created it.
+pub struct DebugMessenger {
+    debug_utils: DebugUtils,
+    messenger: vk::DebugUtilsMessengerEXT,
+    state: Box<MessengerState>,
+}
+
+impl DebugMessenger {
+    /// Creates a new `DebugMessenger`.
+    ///
+    /// # Arguments
+    /// - `sink`: Receives the messages.
+    /// - `min_severity`: Less severe messages are dropped.
+    /// - `fail_on_error`: Records validation errors and panics on drop when there were any.
+    pub fn new(
+        entry: &ash::Entry,
+        instance: &ash::Instance,
+        sink: Arc<dyn DebugMessageSink>,
+        min_severity: MessageSeverity,
+        fail_on_error: bool,
+    ) -> Result<DebugMessenger> {
+        let debug_utils = DebugUtils::new(entry, instance);
+
+        // Boxed, so its address stays valid for the callback when the messenger moves.
+        let state = Box::new(MessengerState {
+            sink,
+            min_severity,
+            fail_on_error,
+            validation_errors: Mutex::new(vec![]),
+        });
+
+        let create_info = vk::DebugUtilsMessengerCreateInfoEXT::builder()
+            .message_severity(min_severity.and_above())
+            .message_type(
+                vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                    | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE
+                    | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
+            )
+            .pfn_user_callback(Some(debug_utils_callback))
+            .user_data(&*state as *const MessengerState as *mut c_void);
+
+        let messenger = unsafe { debug_utils.create_debug_utils_messenger(&create_info, None)? };
+
+        Ok(DebugMessenger {
+            debug_utils,
+            messenger,
+            state,
+        })
+    }
+
+    /// The validation errors that were recorded in test mode.
+    pub fn validation_errors(&self) -> Vec<DebugMessage> {
+        self.state.validation_errors.lock().unwrap().clone()
+    }
+}
+
+impl Drop for DebugMessenger {
+    fn drop(&mut self) {
+        unsafe {
+            self.debug_utils
+                .destroy_debug_utils_messenger(self.messenger, None);
+        }
+
+        let errors = self.validation_errors();
+
+        if !errors.is_empty() && !std::thread::panicking() {
+            let errors = errors
+                .iter()
+                .map(|error| format!("\t - {}", error))
+                .collect::<Vec<String>>()
+                .join("\n");
+            panic!("Vulkan validation errors were emitted:\n{}", errors);
+        }
+    }
+}
+
+unsafe extern "system" fn debug_utils_callback(
+    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
+    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
+    p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
+    p_user_data: *mut c_void,
+) -> vk::Bool32 {
+    if p_callback_data.is_null() || p_user_data.is_null() {
+        return vk::FALSE;
+    }
+
+    let state = &*(p_user_data as *const MessengerState);
+    let callback_data = &*p_callback_data;
+
+    let to_string = |ptr: *const std::os::raw::c_char| {
+        (!ptr.is_null()).then(|| CStr::from_ptr(ptr).to_string_lossy().into_owned())
+    };
+
+    let object_names = if callback_data.p_objects.is_null() {
+        vec![]
+    } else {
+        std::slice::from_raw_parts(callback_data.p_objects, callback_data.object_count as usize)
+            .iter()
+            .filter_map(|object| to_string(object.p_object_name))
+            .collect()
+    };
+
+    state.dispatch(DebugMessage {
+        severity: MessageSeverity::from_flags(message_severity),
+        message_type: MessageType::from_flags(message_type),
+        id_name: to_string(callback_data.p_message_id_name),
+        message: to_string(callback_data.p_message).unwrap_or_default(),
+        object_names,
+    });
+
+    vk::FALSE
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::debug_messenger::{
+        DebugMessage, MemorySink, MessageSeverity, MessageType, MessengerState,
+    };
+    use ash::vk;
+    use std::sync::{Arc, Mutex};
+
+    fn message(severity: MessageSeverity, message_type: MessageType) -> DebugMessage {
+        DebugMessage {
+            severity,
+            message_type,
+            id_name: None,
+            message: String::from("message"),
+            object_names: vec![],
+        }
+    }
+
+    #[test]
+    fn classify_flags() {
+        assert_eq!(
+            MessageSeverity::from_flags(
+                vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
+                    | vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+            ),
+            MessageSeverity::Error
+        );
+        assert_eq!(
+            MessageType::from_flags(
+                vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
+                    | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION
+            ),
+            MessageType::Validation
+        );
+        assert_eq!(
+            MessageSeverity::Warning.and_above(),
+            vk::DebugUtilsMessageSeverityFlagsEXT::WARNING
+                | vk::DebugUtilsMessageSeverityFlagsEXT::ERROR
+        );
+    }
+
+    #[test]
+    fn dispatch_filters_and_records_validation_errors() {
+        let sink = Arc::new(MemorySink::new());
+        let state = MessengerState {
+            sink: sink.clone(),
+            min_severity: MessageSeverity::Warning,
+            fail_on_error: true,
+            validation_errors: Mutex::new(vec![]),
+        };
+
+        state.dispatch(message(MessageSeverity::Info, MessageType::General));
+        state.dispatch(message(MessageSeverity::Warning, MessageType::Performance));
+        state.dispatch(message(MessageSeverity::Error, MessageType::Validation));
+
+        assert_eq!(sink.take().len(), 2);
+        assert_eq!(state.validation_errors.lock().unwrap().len(), 1);
+    }
+}
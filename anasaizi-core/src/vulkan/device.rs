@@ -1,14 +1,69 @@
-use ash::{version::InstanceV1_0, vk};
+use ash::{
+    extensions::ext::DebugUtils,
+    version::{InstanceV1_0, InstanceV1_1},
+    vk,
+    vk::Handle,
+};
 
-use std::fmt::Formatter;
+use std::{ffi::CString, fmt::Formatter};
 
 use crate::{
     utils::vk_to_string,
-    vulkan::{Version, VkInstance, VkQueueFamilyProperties, VkSurfaceData},
+    vulkan::{
+        Allocation, AllocationKind, HeapStatistics, MemoryAllocator, Version, VkInstance,
+        VkPipelineCache, VkQueueFamilyProperties, VkSurfaceData,
+    },
+    Error, Result,
 };
 
 use crate::{engine::Extensions, vulkan::VkQueueFamilyIndices};
-use std::{fmt, ops::Deref};
+use std::{
+    fmt,
+    ops::Deref,
+    sync::{Arc, Mutex},
+};
+
+/// Environment variable that forces a physical device, either by index or by a name substring.
+pub const DEVICE_SELECTION_ENV: &str = "ANASAIZI_GPU";
+
+/// Decides which physical device is used.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeviceSelection {
+    /// Use the suitable device with the highest score.
+    Automatic,
+    /// Use the device at this index in the enumerated device list.
+    Index(usize),
+    /// Use the first device whose name contains this substring, case insensitive.
+    Name(String),
+}
+
+impl DeviceSelection {
+    /// Reads the selection from `ANASAIZI_GPU`, a number selects by index, any other value by name.
+    pub fn from_env() -> DeviceSelection {
+        match std::env::var(DEVICE_SELECTION_ENV) {
+            Ok(value) if !value.trim().is_empty() => Self::parse(value.trim()),
+            _ => DeviceSelection::Automatic,
+        }
+    }
+
+    pub fn parse(value: &str) -> DeviceSelection {
+        match value.parse::<usize>() {
+            Ok(index) => DeviceSelection::Index(index),
+            Err(_) => DeviceSelection::Name(value.to_string()),
+        }
+    }
+
+    fn matches(&self, index: usize, properties: &DeviceProperties) -> bool {
+        match self {
+            DeviceSelection::Automatic => true,
+            DeviceSelection::Index(selected) => *selected == index,
+            DeviceSelection::Name(name) => properties
+                .device_name
+                .to_lowercase()
+                .contains(&name.to_lowercase()),
+        }
+    }
+}
 
 /// A Vulkan logical device.
 ///
@@ -21,58 +76,141 @@ pub struct VkLogicalDevice {
     queue_family_indices: VkQueueFamilyIndices,
     logical_device: ash::Device,
     device_mem_properties: vk::PhysicalDeviceMemoryProperties,
+    allocator: Arc<Mutex<MemoryAllocator>>,
+    pipeline_cache: VkPipelineCache,
+    debug_utils: DebugUtils,
 }
 
 impl VkLogicalDevice {
+    /// Creates a new logical device.
+    ///
+    /// # Arguments
+    /// - `surface_data`: The surface to present to, `None` when rendering headless.
+    ///    Present support is only required from the queue families when a surface is given.
+    /// - `selection`: Which physical device to use, see `DeviceSelection`.
     pub fn new(
         instance: &VkInstance,
         required_extensions: Extensions,
-        surface_data: &VkSurfaceData,
-    ) -> VkLogicalDevice {
+        surface_data: Option<&VkSurfaceData>,
+        selection: DeviceSelection,
+    ) -> Result<VkLogicalDevice> {
         let (queue_family_indices, physical_device) =
-            Self::pick_physical_device(instance, surface_data);
+            Self::pick_physical_device(instance, surface_data, &required_extensions, &selection)?;
 
-        if !Self::check_device_extension_support(instance, physical_device, &required_extensions) {
-            panic!(
-                "Device does not support the extensions: {:?}",
-                required_extensions
-            );
-        }
-
-        let device_properties = unsafe { instance.get_physical_device_properties(physical_device) };
+        let raw_properties = unsafe { instance.get_physical_device_properties(physical_device) };
+        let device_properties = DeviceProperties::from(raw_properties);
         let device_features = unsafe { instance.get_physical_device_features(physical_device) };
 
-        let device_properties = DeviceProperties {
-            device_name: vk_to_string(&device_properties.device_name).unwrap(),
-            device_type: DeviceType::from(device_properties.device_type),
-            api_version: Version::decode(device_properties.api_version),
-            driver_version: Version::decode(device_properties.driver_version),
-            vendor_id: device_properties.vendor_id,
-            device_id: device_properties.device_id,
-            limits: device_properties.limits,
-        };
-
         let logical_device = Self::create_logical_device(
             &instance,
             physical_device,
-            &surface_data,
+            surface_data,
             &required_extensions,
             device_features,
-        );
+        )?;
 
         let device_mem_properties =
             unsafe { instance.get_physical_device_memory_properties(physical_device) };
 
-        VkLogicalDevice {
+        let pipeline_cache = VkPipelineCache::new(&logical_device, &raw_properties)?;
+
+        Ok(VkLogicalDevice {
             physical_device,
             device_properties,
             device_features,
             queue_family_indices,
             logical_device,
             device_mem_properties,
+            allocator: Arc::new(Mutex::new(MemoryAllocator::new(device_mem_properties))),
+            pipeline_cache,
+            debug_utils: DebugUtils::new(instance.entry(), &**instance),
+        })
+    }
+
+    /// Names a Vulkan object, validation messages and captures show the name instead of the handle.
+    pub fn set_debug_name<H: Handle>(&self, handle: H, name: &str) {
+        let name = debug_label(name);
+        let name_info = vk::DebugUtilsObjectNameInfoEXT::builder()
+            .object_type(H::TYPE)
+            .object_handle(handle.as_raw())
+            .object_name(&name);
+
+        // Names are a debugging aid, failing to set one does not affect rendering.
+        unsafe {
+            let _ = self
+                .debug_utils
+                .debug_utils_set_object_name(self.logical_device.handle(), &name_info);
+        }
+    }
+
+    /// Opens a labeled region in the command buffer, it is closed with `end_debug_label`.
+    pub fn begin_debug_label(&self, command_buffer: vk::CommandBuffer, name: &str) {
+        let name = debug_label(name);
+        let label = vk::DebugUtilsLabelEXT::builder().label_name(&name);
+
+        unsafe {
+            self.debug_utils
+                .cmd_begin_debug_utils_label(command_buffer, &label);
+        }
+    }
+
+    pub fn end_debug_label(&self, command_buffer: vk::CommandBuffer) {
+        unsafe {
+            self.debug_utils.cmd_end_debug_utils_label(command_buffer);
         }
     }
 
+    /// Sub allocates memory for a buffer or image from the shared memory blocks.
+    pub fn allocate(
+        &self,
+        requirements: vk::MemoryRequirements,
+        properties: vk::MemoryPropertyFlags,
+        kind: AllocationKind,
+    ) -> Allocation {
+        self.allocator.lock().unwrap().allocate(
+            &self.logical_device,
+            requirements,
+            properties,
+            kind,
+        )
+    }
+
+    /// Returns the allocation to its memory block.
+    pub fn free_allocation(&self, allocation: &Allocation) {
+        self.allocator
+            .lock()
+            .unwrap()
+            .free(&self.logical_device, allocation);
+    }
+
+    /// Returns the memory usage per memory heap.
+    pub fn memory_statistics(&self) -> Vec<HeapStatistics> {
+        self.allocator.lock().unwrap().statistics()
+    }
+
+    /// The pipeline cache all pipelines are created with.
+    pub fn pipeline_cache(&self) -> &VkPipelineCache {
+        &self.pipeline_cache
+    }
+
+    /// Saves the pipeline cache to disk, call this on shutdown.
+    pub fn save_pipeline_cache(&self) -> Result<()> {
+        self.pipeline_cache.save(&self.logical_device)
+    }
+
+    /// Destroys the pipeline cache, must happen before the logical device is destroyed.
+    pub unsafe fn destroy_pipeline_cache(&self) {
+        self.pipeline_cache.destroy(&self.logical_device);
+    }
+
+    /// Frees the memory blocks of the allocator, every buffer and image must be destroyed before.
+    pub unsafe fn destroy_allocator(&self) {
+        self.allocator
+            .lock()
+            .unwrap()
+            .destroy(&self.logical_device);
+    }
+
     pub fn find_memory_type(
         &self,
         type_filter: u32,
@@ -89,6 +227,35 @@ impl VkLogicalDevice {
         panic!("Failed to find suitable memory type!")
     }
 
+    /// The highest MSAA sample count the device supports for both color and depth attachments,
+    /// limited to `max_samples`.
+    pub fn sample_count(&self, max_samples: vk::SampleCountFlags) -> vk::SampleCountFlags {
+        let limits = &self.device_properties.limits;
+
+        Self::pick_sample_count(
+            limits.framebuffer_color_sample_counts & limits.framebuffer_depth_sample_counts,
+            max_samples,
+        )
+    }
+
+    pub(crate) fn pick_sample_count(
+        supported: vk::SampleCountFlags,
+        max_samples: vk::SampleCountFlags,
+    ) -> vk::SampleCountFlags {
+        [
+            vk::SampleCountFlags::TYPE_64,
+            vk::SampleCountFlags::TYPE_32,
+            vk::SampleCountFlags::TYPE_16,
+            vk::SampleCountFlags::TYPE_8,
+            vk::SampleCountFlags::TYPE_4,
+            vk::SampleCountFlags::TYPE_2,
+        ]
+        .iter()
+        .copied()
+        .find(|&samples| samples.as_raw() <= max_samples.as_raw() && supported.contains(samples))
+        .unwrap_or(vk::SampleCountFlags::TYPE_1)
+    }
+
     pub fn find_depth_format(&self, instance: &ash::Instance) -> vk::Format {
         self.find_supported_format(
             instance,
@@ -102,6 +269,23 @@ impl VkLogicalDevice {
         )
     }
 
+    /// Whether images of the format support the features with the given tiling.
+    pub fn supports_format_features(
+        &self,
+        instance: &ash::Instance,
+        format: vk::Format,
+        tiling: vk::ImageTiling,
+        features: vk::FormatFeatureFlags,
+    ) -> bool {
+        let format_properties =
+            unsafe { instance.get_physical_device_format_properties(self.physical_device, format) };
+
+        match tiling {
+            vk::ImageTiling::LINEAR => format_properties.linear_tiling_features.contains(features),
+            _ => format_properties.optimal_tiling_features.contains(features),
+        }
+    }
+
     fn find_supported_format(
         &self,
         instance: &ash::Instance,
@@ -150,12 +334,12 @@ impl VkLogicalDevice {
     fn create_logical_device(
         instance: &VkInstance,
         physical_device: vk::PhysicalDevice,
-        surface_data: &VkSurfaceData,
+        surface_data: Option<&VkSurfaceData>,
         extensions: &Extensions,
         features: vk::PhysicalDeviceFeatures,
-    ) -> ash::Device {
+    ) -> Result<ash::Device> {
         // Setup the queues to use
-        let indices = Self::find_queue_family(instance, physical_device, surface_data);
+        let indices = Self::find_queue_family(instance, physical_device, surface_data)?;
 
         let queue_priorities = [1.0_f32];
 
@@ -171,20 +355,20 @@ impl VkLogicalDevice {
             .map(|x| x.as_ptr())
             .collect::<Vec<*const i8>>();
 
+        let mut descriptor_indexing_features = Self::required_descriptor_indexing_features();
+
         // Create device
         let device_create_info = vk::DeviceCreateInfo::builder()
             .queue_create_infos(&[queue_create_info])
             .enabled_extension_names(&extensions_ptr)
             .enabled_features(&features)
+            .push_next(&mut descriptor_indexing_features)
             .build();
 
-        let device: ash::Device = unsafe {
-            instance
-                .create_device(physical_device, &device_create_info, None)
-                .expect("Failed to create logical Device!")
-        };
+        let device: ash::Device =
+            unsafe { instance.create_device(physical_device, &device_create_info, None)? };
 
-        device
+        Ok(device)
     }
 
     /// Validates if the physical device supports the required extensions.
@@ -192,72 +376,180 @@ impl VkLogicalDevice {
         instance: &VkInstance,
         physical_device: vk::PhysicalDevice,
         required_extensions: &Extensions,
-    ) -> bool {
-        let available_extensions = unsafe {
-            instance
-                .enumerate_device_extension_properties(physical_device)
-                .expect("Failed to get device extension properties.")
-        }
-        .iter()
-        .map(|e| vk_to_string(&e.extension_name))
-        .filter(|result| result.is_ok())
-        .map(|string| string.unwrap())
-        .collect::<Vec<String>>();
+    ) -> Result<bool> {
+        let available_extensions =
+            unsafe { instance.enumerate_device_extension_properties(physical_device)? }
+                .iter()
+                .map(|e| vk_to_string(&e.extension_name))
+                .filter(|result| result.is_ok())
+                .map(|string| string.unwrap())
+                .collect::<Vec<String>>();
 
         let available_extensions = Extensions::new(available_extensions);
 
-        let result = available_extensions.has(&required_extensions);
-
-        result
+        Ok(available_extensions.has(&required_extensions))
     }
 
-    /// Pick a physical device that is capable of using the graphics queue.
+    /// Pick the physical device with the highest score, or the one forced by `selection`.
+    ///
+    /// Every candidate is logged together with its score.
     fn pick_physical_device(
         instance: &VkInstance,
-        surface_data: &VkSurfaceData,
-    ) -> (VkQueueFamilyIndices, vk::PhysicalDevice) {
-        let physical_devices = unsafe {
-            instance
-                .enumerate_physical_devices()
-                .expect("Failed to enumerate Physical Devices!")
-        };
+        surface_data: Option<&VkSurfaceData>,
+        required_extensions: &Extensions,
+        selection: &DeviceSelection,
+    ) -> Result<(VkQueueFamilyIndices, vk::PhysicalDevice)> {
+        let physical_devices = unsafe { instance.enumerate_physical_devices()? };
+
+        let mut result: Option<(u32, VkQueueFamilyIndices, vk::PhysicalDevice)> = None;
+
+        for (index, &physical_device) in physical_devices.iter().enumerate() {
+            let properties = DeviceProperties::from(unsafe {
+                instance.get_physical_device_properties(physical_device)
+            });
+
+            let (queue_family, suitable) = Self::is_physical_device_suitable(
+                instance,
+                physical_device,
+                surface_data,
+                required_extensions,
+            )?;
+
+            let score = if suitable {
+                Some(Self::score_physical_device(
+                    instance,
+                    physical_device,
+                    &properties,
+                ))
+            } else {
+                None
+            };
+
+            log::info!(
+                "GPU candidate {}:{:?}\t- Score: {}",
+                index,
+                properties,
+                score.map_or(String::from("not suitable"), |score| score.to_string())
+            );
 
-        let mut result = None;
-        for &physical_device in physical_devices.iter() {
-            let (queue_family, suitable) =
-                Self::is_physical_device_suitable(instance, physical_device, surface_data);
+            if !selection.matches(index, &properties) {
+                continue;
+            }
 
-            if suitable {
-                if result.is_none() {
-                    result = Some((queue_family, physical_device))
+            if let Some(score) = score {
+                if result.as_ref().map_or(true, |(best, _, _)| score > *best) {
+                    result = Some((score, queue_family, physical_device));
                 }
             }
         }
 
         match result {
-            None => panic!("Failed to find a suitable GPU!"),
-            Some(physical_device) => physical_device,
+            None if *selection != DeviceSelection::Automatic => Err(Error::NoSuitableDevice(
+                format!("no suitable GPU matches {:?}", selection),
+            )),
+            None => Err(Error::NoSuitableDevice(String::from(
+                "no GPU supports the required queues, extensions and features",
+            ))),
+            Some((_, queue_family, physical_device)) => Ok((queue_family, physical_device)),
         }
     }
 
-    /// Validate if the physical device is able to use the graphics queue.
+    /// Validate if the physical device is able to use the graphics queue,
+    /// supports the required extensions and the required features.
     fn is_physical_device_suitable(
         instance: &VkInstance,
         physical_device: vk::PhysicalDevice,
-        surface_data: &VkSurfaceData,
-    ) -> (VkQueueFamilyIndices, bool) {
-        let indices = Self::find_queue_family(instance, physical_device, surface_data);
+        surface_data: Option<&VkSurfaceData>,
+        required_extensions: &Extensions,
+    ) -> Result<(VkQueueFamilyIndices, bool)> {
+        let indices = Self::find_queue_family(instance, physical_device, surface_data)?;
         let is_complete = indices.is_complete();
 
-        return (indices, is_complete);
+        let supports_extensions =
+            Self::check_device_extension_support(instance, physical_device, required_extensions)?;
+
+        let features = unsafe { instance.get_physical_device_features(physical_device) };
+        // Texture samplers are created with anisotropic filtering enabled.
+        let supports_features = features.sampler_anisotropy == vk::TRUE
+            && Self::supports_descriptor_indexing(instance, physical_device);
+
+        Ok((
+            indices,
+            is_complete && supports_extensions && supports_features,
+        ))
+    }
+
+    /// The descriptor indexing features used by the bindless texture table, shaders declare it as a
+    /// runtime array that is indexed with `nonuniformEXT`.
+    fn required_descriptor_indexing_features() -> vk::PhysicalDeviceDescriptorIndexingFeatures {
+        vk::PhysicalDeviceDescriptorIndexingFeatures::builder()
+            .descriptor_binding_partially_bound(true)
+            .descriptor_binding_sampled_image_update_after_bind(true)
+            .runtime_descriptor_array(true)
+            .shader_sampled_image_array_non_uniform_indexing(true)
+            .build()
+    }
+
+    /// Validate if the physical device supports the required descriptor indexing features.
+    fn supports_descriptor_indexing(
+        instance: &VkInstance,
+        physical_device: vk::PhysicalDevice,
+    ) -> bool {
+        let mut descriptor_indexing_features =
+            vk::PhysicalDeviceDescriptorIndexingFeatures::default();
+        let mut features = vk::PhysicalDeviceFeatures2::builder()
+            .push_next(&mut descriptor_indexing_features)
+            .build();
+
+        unsafe { instance.get_physical_device_features2(physical_device, &mut features) };
+
+        descriptor_indexing_features.descriptor_binding_partially_bound == vk::TRUE
+            && descriptor_indexing_features.descriptor_binding_sampled_image_update_after_bind
+                == vk::TRUE
+            && descriptor_indexing_features.runtime_descriptor_array == vk::TRUE
+            && descriptor_indexing_features.shader_sampled_image_array_non_uniform_indexing
+                == vk::TRUE
+    }
+
+    /// Scores a suitable physical device, a higher score is preferred.
+    ///
+    /// Device type weights the most (discrete > integrated > virtual > CPU),
+    /// after that the amount of device local memory.
+    fn score_physical_device(
+        instance: &VkInstance,
+        physical_device: vk::PhysicalDevice,
+        properties: &DeviceProperties,
+    ) -> u32 {
+        let type_score = match properties.device_type {
+            DeviceType::DiscreteGPU => 100_000,
+            DeviceType::IntegratedGPU => 50_000,
+            DeviceType::VirtualGPU => 20_000,
+            DeviceType::CPU => 10_000,
+            DeviceType::Other => 0,
+        };
+
+        let memory_properties =
+            unsafe { instance.get_physical_device_memory_properties(physical_device) };
+
+        let device_local_memory: u64 = memory_properties.memory_heaps
+            [..memory_properties.memory_heap_count as usize]
+            .iter()
+            .filter(|heap| heap.flags.contains(vk::MemoryHeapFlags::DEVICE_LOCAL))
+            .map(|heap| heap.size)
+            .sum();
+
+        // One point per 64 MiB, capped so memory never outweighs the device type.
+        let memory_score = ((device_local_memory / (64 * 1024 * 1024)) as u32).min(9_999);
+
+        type_score + memory_score
     }
 
     /// Find supported queues for the given device.
     fn find_queue_family(
         instance: &VkInstance,
         physical_device: vk::PhysicalDevice,
-        surface_data: &VkSurfaceData,
-    ) -> VkQueueFamilyIndices {
+        surface_data: Option<&VkSurfaceData>,
+    ) -> Result<VkQueueFamilyIndices> {
         let queue_families =
             unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
 
@@ -275,19 +567,20 @@ impl VkLogicalDevice {
                 queue_family_indices.graphics_family = Some(index);
             }
 
-            let is_present_support = unsafe {
-                surface_data
-                    .surface_loader
-                    .get_physical_device_surface_support(
-                        physical_device,
-                        index as u32,
-                        surface_data.surface,
-                    )
-            }
-            .expect("Error when trying to check present support");
-
-            if queue_family.queue_count > 0 && is_present_support {
-                queue_family_indices.present_family = Some(index);
+            if let Some(surface_data) = surface_data {
+                let is_present_support = unsafe {
+                    surface_data
+                        .surface_loader
+                        .get_physical_device_surface_support(
+                            physical_device,
+                            index as u32,
+                            surface_data.surface,
+                        )?
+                };
+
+                if queue_family.queue_count > 0 && is_present_support {
+                    queue_family_indices.present_family = Some(index);
+                }
             }
 
             if queue_family_indices.is_complete() {
@@ -297,7 +590,7 @@ impl VkLogicalDevice {
             index += 1;
         }
 
-        queue_family_indices
+        Ok(queue_family_indices)
     }
 }
 
@@ -324,12 +617,17 @@ impl From<vk::PhysicalDeviceType> for DeviceType {
             vk::PhysicalDeviceType::INTEGRATED_GPU => DeviceType::IntegratedGPU,
             vk::PhysicalDeviceType::DISCRETE_GPU => DeviceType::DiscreteGPU,
             vk::PhysicalDeviceType::VIRTUAL_GPU => DeviceType::VirtualGPU,
-            vk::PhysicalDeviceType::OTHER => DeviceType::DiscreteGPU,
+            vk::PhysicalDeviceType::OTHER => DeviceType::Other,
             _ => panic!(),
         }
     }
 }
 
+/// Converts a debug name to a C string, interior nul characters are dropped.
+fn debug_label(name: &str) -> CString {
+    CString::new(name.replace('\0', "")).unwrap()
+}
+
 #[derive(Clone)]
 pub struct DeviceProperties {
     pub api_version: Version,
@@ -341,6 +639,20 @@ pub struct DeviceProperties {
     pub limits: vk::PhysicalDeviceLimits,
 }
 
+impl From<vk::PhysicalDeviceProperties> for DeviceProperties {
+    fn from(properties: vk::PhysicalDeviceProperties) -> Self {
+        DeviceProperties {
+            device_name: vk_to_string(&properties.device_name).unwrap(),
+            device_type: DeviceType::from(properties.device_type),
+            api_version: Version::decode(properties.api_version),
+            driver_version: Version::decode(properties.driver_version),
+            vendor_id: properties.vendor_id,
+            device_id: properties.device_id,
+            limits: properties.limits,
+        }
+    }
+}
+
 impl fmt::Debug for DeviceProperties {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
         write!(f, "\nDevice Properties:\n")?;
@@ -363,3 +675,50 @@ impl Deref for VkLogicalDevice {
         &self.logical_device
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::{device::debug_label, DeviceSelection, VkLogicalDevice};
+    use ash::vk;
+
+    #[test]
+    fn debug_label_drops_nul() {
+        assert_eq!(
+            debug_label("Sponza/arch\0stone").to_str().unwrap(),
+            "Sponza/archstone"
+        );
+    }
+
+    #[test]
+    fn parse_device_selection() {
+        assert_eq!(DeviceSelection::parse("1"), DeviceSelection::Index(1));
+        assert_eq!(
+            DeviceSelection::parse("GeForce"),
+            DeviceSelection::Name(String::from("GeForce"))
+        );
+    }
+
+    #[test]
+    fn pick_sample_count() {
+        let supported = vk::SampleCountFlags::TYPE_1
+            | vk::SampleCountFlags::TYPE_2
+            | vk::SampleCountFlags::TYPE_4
+            | vk::SampleCountFlags::TYPE_8;
+
+        assert_eq!(
+            VkLogicalDevice::pick_sample_count(supported, vk::SampleCountFlags::TYPE_4),
+            vk::SampleCountFlags::TYPE_4
+        );
+        assert_eq!(
+            VkLogicalDevice::pick_sample_count(supported, vk::SampleCountFlags::TYPE_64),
+            vk::SampleCountFlags::TYPE_8
+        );
+        assert_eq!(
+            VkLogicalDevice::pick_sample_count(
+                vk::SampleCountFlags::TYPE_1,
+                vk::SampleCountFlags::TYPE_4
+            ),
+            vk::SampleCountFlags::TYPE_1
+        );
+    }
+}
@@ -17,6 +17,17 @@ impl VkImageView {
         image: vk::Image,
         format: vk::Format,
         aspect: vk::ImageAspectFlags,
+    ) -> VkImageView {
+        Self::create_with_mip_levels(device, image, format, aspect, 1)
+    }
+
+    /// Creates a view of the first `mip_levels` mip levels of the image.
+    pub fn create_with_mip_levels(
+        device: &ash::Device,
+        image: vk::Image,
+        format: vk::Format,
+        aspect: vk::ImageAspectFlags,
+        mip_levels: u32,
     ) -> VkImageView {
         let imageview_create_info = vk::ImageViewCreateInfo::builder()
             .view_type(vk::ImageViewType::TYPE_2D)
@@ -30,7 +41,7 @@ impl VkImageView {
             .subresource_range(vk::ImageSubresourceRange {
                 aspect_mask: aspect,
                 base_mip_level: 0,
-                level_count: 1,
+                level_count: mip_levels,
                 base_array_layer: 0,
                 layer_count: 1,
             })
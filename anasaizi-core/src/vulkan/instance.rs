@@ -1,18 +1,25 @@
 use crate::{
     engine::Extensions,
-    vulkan::{structures::VkValidationInfo, VkApplication, VkValidationLayers},
+    utils::vk_to_string,
+    vulkan::{structures::VkValidationInfo, DebugMessenger, VkApplication, VkValidationLayers},
+    Error, Result,
+};
+use ash::{
+    version::{EntryV1_0, InstanceV1_0},
+    vk,
 };
-use ash::{version::EntryV1_0, vk};
 use std::{ffi::CString, fmt, ops::Deref};
 
 /// Vulkan Instance.
 ///
 /// The instance is the connection between the application and the Vulkan library.
+/// With validation enabled it owns the messenger that routes the validation messages.
 pub struct VkInstance {
     entry: ash::Entry,
     instance: ash::Instance,
 
     validation_layers: Option<VkValidationLayers>,
+    debug_messenger: Option<DebugMessenger>,
 }
 
 impl VkInstance {
@@ -21,18 +28,44 @@ impl VkInstance {
         validation: VkValidationInfo,
         extensions: Extensions,
         application: &VkApplication,
-    ) -> VkInstance {
+    ) -> Result<VkInstance> {
+        Self::with_optional_extensions(validation, extensions, Extensions::new(vec![]), application)
+    }
+
+    /// Creates a new vulkan instance with the given extensions.
+    ///
+    /// # Arguments
+    /// - `optional_extensions`: Only enabled when the driver supports them.
+    ///
+    /// Fails when the Vulkan library can not be loaded or the validation layers are missing.
+    pub fn with_optional_extensions(
+        validation: VkValidationInfo,
+        extensions: Extensions,
+        optional_extensions: Extensions,
+        application: &VkApplication,
+    ) -> Result<VkInstance> {
         // Create entry
-        let entry = unsafe { ash::Entry::new().unwrap() };
+        let entry =
+            unsafe { ash::Entry::new() }.map_err(|error| Error::Loading(error.to_string()))?;
+
+        let available_extensions = Self::available_extensions(&entry)?;
+        let mut extensions = extensions.extensions();
+        extensions.extend(
+            optional_extensions
+                .extensions()
+                .into_iter()
+                .filter(|extension| available_extensions.contains(extension)),
+        );
+        let extensions = Extensions::new(extensions);
 
         // Check if validation layers are enabled
         let mut validation_layers = None;
 
         if validation.is_enable {
-            let layers = VkValidationLayers::new(&entry, validation.to_vec_owned());
+            let layers = VkValidationLayers::new(&entry, validation.to_vec_owned())?;
 
             if !layers.has_required_layers() {
-                panic!("Validation layers requested, but not available!");
+                return Err(Error::MissingLayers(layers.missing_layers()));
             }
 
             validation_layers = Some(layers)
@@ -74,19 +107,56 @@ impl VkInstance {
         let instance: ash::Instance = unsafe {
             entry
                 .create_instance(&create_info, None)
-                .expect("Failed to create instance!")
+                .map_err(|error| match error {
+                    ash::InstanceError::LoadError(symbols) => Error::Loading(symbols.join(", ")),
+                    ash::InstanceError::VkError(result) => Error::Vulkan(result),
+                })?
+        };
+
+        let debug_messenger = if validation.is_enable {
+            let messenger = DebugMessenger::new(
+                &entry,
+                &instance,
+                validation.sink.clone(),
+                validation.min_severity,
+                validation.fail_on_error,
+            );
+
+            match messenger {
+                Ok(messenger) => Some(messenger),
+                Err(error) => {
+                    unsafe { instance.destroy_instance(None) };
+                    return Err(error);
+                }
+            }
+        } else {
+            None
         };
 
-        VkInstance {
+        Ok(VkInstance {
             entry,
             validation_layers,
             instance,
-        }
+            debug_messenger,
+        })
+    }
+
+    fn available_extensions(entry: &ash::Entry) -> Result<Vec<String>> {
+        Ok(entry
+            .enumerate_instance_extension_properties()?
+            .iter()
+            .filter_map(|extension| vk_to_string(&extension.extension_name).ok())
+            .collect())
     }
 
     pub fn entry(&self) -> &ash::Entry {
         &self.entry
     }
+
+    /// The messenger of the validation layers, `None` when validation is disabled.
+    pub fn debug_messenger(&self) -> Option<&DebugMessenger> {
+        self.debug_messenger.as_ref()
+    }
 }
 
 impl Deref for VkInstance {
@@ -98,7 +168,7 @@ impl Deref for VkInstance {
 }
 
 impl fmt::Debug for VkInstance {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         if let Some(validation_layer) = &self.validation_layers {
             write!(f, "{:?}", validation_layer)?;
         }
@@ -1,4 +1,4 @@
-use crate::utils::vk_to_string;
+use crate::{utils::vk_to_string, Result};
 use ash::version::EntryV1_0;
 use std::fmt;
 
@@ -17,32 +17,35 @@ pub struct VkValidationLayers {
 
 impl VkValidationLayers {
     /// Creates a new validation layer.
-    pub fn new(entry: &ash::Entry, required_layers: Vec<String>) -> VkValidationLayers {
-        VkValidationLayers {
-            supported_layers: Self::initialize_validation_layers(entry),
+    pub fn new(entry: &ash::Entry, required_layers: Vec<String>) -> Result<VkValidationLayers> {
+        Ok(VkValidationLayers {
+            supported_layers: Self::initialize_validation_layers(entry)?,
             required_layers,
-        }
+        })
     }
 
     pub fn has_required_layers(&self) -> bool {
-        for required_layer in self.required_layers.iter() {
-            let contains_layer = self
-                .supported_layers
-                .iter()
-                .any(|l| l.name == *required_layer);
-
-            if !contains_layer {
-                return false;
-            }
-        }
+        self.missing_layers().is_empty()
+    }
 
-        true
+    /// The required layers the Vulkan installation does not provide.
+    pub fn missing_layers(&self) -> Vec<String> {
+        self.required_layers
+            .iter()
+            .filter(|required_layer| {
+                !self
+                    .supported_layers
+                    .iter()
+                    .any(|l| l.name == **required_layer)
+            })
+            .cloned()
+            .collect()
     }
 
-    fn initialize_validation_layers(entry: &ash::Entry) -> Vec<VkValidationLayerProperties> {
-        let layer_properties = entry
-            .enumerate_instance_layer_properties()
-            .expect("Failed to enumerate Instance Layers Properties!");
+    fn initialize_validation_layers(
+        entry: &ash::Entry,
+    ) -> Result<Vec<VkValidationLayerProperties>> {
+        let layer_properties = entry.enumerate_instance_layer_properties()?;
 
         let mut supported_layers = vec![];
 
@@ -60,12 +63,12 @@ impl VkValidationLayers {
             });
         }
 
-        supported_layers
+        Ok(supported_layers)
     }
 }
 
 impl fmt::Debug for VkValidationLayers {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "Required Layers:\n")?;
 
         for required_layer in &self.required_layers {
@@ -0,0 +1,132 @@
+use ash::{version::DeviceV1_0, vk};
+
+use crate::{
+    engine::{image::Texture, RenderContext},
+    vulkan::{
+        copy_image_to_buffer, create_allocate_vk_buffer, destroy_vk_buffer,
+        submit_single_time_command, Allocation, VkImageView, VkLogicalDevice,
+    },
+};
+use std::ptr;
+
+/// An offscreen render target.
+///
+/// Used instead of a swapchain when rendering headless.
+/// Frames are rendered into color images that can be read back to host memory.
+pub struct VkOffscreenTarget {
+    pub images: Vec<vk::Image>,
+    images_memory: Vec<Allocation>,
+    pub image_format: vk::Format,
+    pub extent: vk::Extent2D,
+    pub image_views: Vec<VkImageView>,
+}
+
+impl VkOffscreenTarget {
+    /// The format of the color images, sRGB so the read back pixels match what a swapchain would present.
+    pub const COLOR_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+
+    /// Creates a new offscreen target.
+    ///
+    /// # Arguments
+    /// - `extent`: The dimensions of the color images.
+    /// - `image_count`: The number of color images, one for each frame in flight.
+    pub fn new(
+        render_context: &RenderContext,
+        extent: vk::Extent2D,
+        image_count: usize,
+    ) -> VkOffscreenTarget {
+        let mut images = Vec::with_capacity(image_count);
+        let mut images_memory = Vec::with_capacity(image_count);
+        let mut image_views = Vec::with_capacity(image_count);
+
+        for _ in 0..image_count {
+            let (image, image_memory) = Texture::create_image(
+                render_context,
+                extent.width,
+                extent.height,
+                Self::COLOR_FORMAT,
+                vk::ImageTiling::OPTIMAL,
+                vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC,
+                vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            );
+
+            let image_view = VkImageView::create(
+                render_context.device(),
+                image,
+                Self::COLOR_FORMAT,
+                vk::ImageAspectFlags::COLOR,
+            );
+
+            images.push(image);
+            images_memory.push(image_memory);
+            image_views.push(image_view);
+        }
+
+        VkOffscreenTarget {
+            images,
+            images_memory,
+            image_format: Self::COLOR_FORMAT,
+            extent,
+            image_views,
+        }
+    }
+
+    /// The layout the render graph leaves the color images in at the end of a frame.
+    pub fn final_layout() -> vk::ImageLayout {
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL
+    }
+
+    /// Reads the color image at `index` back into host memory.
+    ///
+    /// The image has to be in `TRANSFER_SRC_OPTIMAL` layout, which is the case after a rendered frame.
+    /// The returned pixels are tightly packed RGBA8.
+    pub fn read_back(&self, render_context: &RenderContext, index: usize) -> Vec<u8> {
+        let size = (self.extent.width * self.extent.height * 4) as u64;
+
+        let (buffer, buffer_memory) = create_allocate_vk_buffer(
+            render_context,
+            size,
+            vk::BufferUsageFlags::TRANSFER_DST,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        );
+
+        submit_single_time_command(render_context, |command_buffer| {
+            copy_image_to_buffer(
+                render_context,
+                self.images[index],
+                Self::final_layout(),
+                buffer,
+                command_buffer,
+                size,
+                self.extent,
+            )
+        });
+
+        let mut pixels = vec![0u8; size as usize];
+
+        unsafe {
+            let data_ptr = buffer_memory
+                .mapped_ptr()
+                .expect("Read back buffer is not host visible!");
+
+            ptr::copy_nonoverlapping(data_ptr, pixels.as_mut_ptr(), size as usize);
+        }
+
+        destroy_vk_buffer(render_context, buffer, &buffer_memory);
+
+        pixels
+    }
+
+    pub(crate) unsafe fn destroy(&self, device: &VkLogicalDevice) {
+        for ((image_view, image), memory) in self
+            .image_views
+            .iter()
+            .zip(self.images.iter())
+            .zip(self.images_memory.iter())
+        {
+            image_view.destroy(device);
+            device.destroy_image(*image, None);
+            device.free_allocation(memory);
+        }
+    }
+}
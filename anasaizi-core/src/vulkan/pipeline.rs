@@ -1,7 +1,8 @@
 use crate::{
     utils::any_as_u8_slice,
     vulkan,
-    vulkan::{ShaderSet, VkLogicalDevice, VkRenderPass, VkSwapChain},
+    vulkan::{DepthStencilState, PipelineDescription, ShaderSet, VkLogicalDevice, VkRenderPass},
+    Result,
 };
 use ash::{
     version::DeviceV1_0,
@@ -77,58 +78,25 @@ impl VkPipelineBuilder {
         self
     }
 
-    fn with_rasterization_info(mut self) -> VkPipelineBuilder {
-        self.p_rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
-            .depth_clamp_enable(false)
-            .rasterizer_discard_enable(false)
-            .polygon_mode(vk::PolygonMode::FILL)
-            .line_width(1.0)
-            .cull_mode(vk::CullModeFlags::NONE)
-            .front_face(vk::FrontFace::CLOCKWISE)
-            .depth_bias_enable(false)
-            .depth_bias_constant_factor(0.0)
-            .depth_bias_clamp(0.0)
-            .depth_bias_slope_factor(0.0)
-            .build();
-
-        self
-    }
-
-    fn with_depth_stage(mut self) -> VkPipelineBuilder {
-        let stencil_state = vk::StencilOpState::builder()
-            .fail_op(vk::StencilOp::KEEP)
-            .pass_op(vk::StencilOp::KEEP)
-            .depth_fail_op(vk::StencilOp::KEEP)
-            .compare_op(vk::CompareOp::ALWAYS)
-            .build();
-
-        self.p_depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
-            .depth_compare_op(vk::CompareOp::LESS)
-            .stencil_test_enable(true) // can be enabled
-            .front(stencil_state)
-            .back(stencil_state)
-            .depth_bounds_test_enable(false) // can be enabled
-            .max_depth_bounds(1.0)
-            .min_depth_bounds(0.0)
-            .depth_test_enable(true)
-            .depth_write_enable(true)
-            .build();
+    /// Applies the rasterization, topology, depth/stencil and blend state of the description.
+    fn with_description(mut self, description: &PipelineDescription) -> VkPipelineBuilder {
+        self.p_rasterization_state = description.rasterization_info();
+        self.p_input_assembly_state = description.input_assembly_info();
+        self.p_depth_stencil_state = description.depth_stencil_info();
 
-        self
-    }
-
-    fn with_input_assembly_state(mut self) -> VkPipelineBuilder {
-        self.p_input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
-            .primitive_restart_enable(false)
-            .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        self.color_blend_attachment_states = description.blend_attachment_states();
+        self.p_color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+            .logic_op(vk::LogicOp::COPY)
+            .attachments(&self.color_blend_attachment_states)
+            .blend_constants([0.0, 0.0, 0.0, 0.0])
             .build();
 
         self
     }
 
-    fn with_multilsample(mut self) -> VkPipelineBuilder {
+    fn with_multilsample(mut self, samples: vk::SampleCountFlags) -> VkPipelineBuilder {
         self.p_multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
-            .rasterization_samples(vk::SampleCountFlags::TYPE_1)
+            .rasterization_samples(samples)
             .sample_shading_enable(false)
             .alpha_to_one_enable(false)
             .alpha_to_coverage_enable(false)
@@ -146,27 +114,6 @@ impl VkPipelineBuilder {
         self
     }
 
-    fn color_blend(mut self) -> VkPipelineBuilder {
-        self.color_blend_attachment_states = vec![vk::PipelineColorBlendAttachmentState::builder()
-            .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
-            .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
-            .color_blend_op(vk::BlendOp::ADD)
-            .src_alpha_blend_factor(vk::BlendFactor::SRC_ALPHA)
-            .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
-            .blend_enable(true)
-            .alpha_blend_op(vk::BlendOp::SUBTRACT)
-            .color_write_mask(vk::ColorComponentFlags::all())
-            .build()];
-
-        self.p_color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
-            .logic_op(vk::LogicOp::COPY)
-            .attachments(&self.color_blend_attachment_states)
-            .blend_constants([0.0, 0.0, 0.0, 0.0])
-            .build();
-
-        self
-    }
-
     fn layout(mut self, pipeline_layout: vk::PipelineLayout) -> VkPipelineBuilder {
         self.layout = pipeline_layout;
         self
@@ -190,7 +137,7 @@ impl VkPipelineBuilder {
         self
     }
 
-    fn build(self, device: &VkLogicalDevice, shader_set: &mut ShaderSet) -> vk::Pipeline {
+    fn build(self, device: &VkLogicalDevice, shader_set: &mut ShaderSet) -> Result<vk::Pipeline> {
         let main_function_name = CString::new("main").unwrap(); // the beginning function name in shader code.
 
         let data = shader_set.io.specialization_data_ref();
@@ -236,15 +183,14 @@ impl VkPipelineBuilder {
             .dynamic_state(&self.p_dynamic_state)
             .build()];
 
-        let pipeline = unsafe {
+        let pipelines = unsafe {
             device
                 .logical_device()
-                .create_graphics_pipelines(vk::PipelineCache::null(), &pipeline_info, None)
-                .map_err(|e| e.1)
-                .unwrap()[0]
+                .create_graphics_pipelines(**device.pipeline_cache(), &pipeline_info, None)
+                .map_err(|(_, result)| result)?
         };
 
-        pipeline
+        Ok(pipelines[0])
     }
 }
 
@@ -255,29 +201,66 @@ pub struct VkPipeline {
     pipeline: vk::Pipeline,
     layout: vk::PipelineLayout,
     pub shader: ShaderSet,
+    description: PipelineDescription,
     pipeline_id: u32,
+    debug_name: Option<String>,
 }
 
 impl VkPipeline {
     /// Creates a new `Pipeline`.
+    ///
+    /// # Arguments
+    /// - `description`: The fixed function state of the pipeline, it is kept for `refresh`.
     pub fn create(
         device: &VkLogicalDevice,
         swapchain_extent: vk::Extent2D,
         render_pass: &VkRenderPass,
         mut shader_set: ShaderSet,
+        description: PipelineDescription,
         pipeline_id: u32,
-    ) -> VkPipeline {
-        let builder =
-            Self::pipeline_builder(device, swapchain_extent, render_pass, &mut shader_set);
+    ) -> Result<VkPipeline> {
+        let (pipeline, layout) = Self::create_pipeline(
+            device,
+            swapchain_extent,
+            render_pass,
+            &mut shader_set,
+            &description,
+        )?;
+
+        Ok(VkPipeline {
+            layout,
+            pipeline,
+            shader: shader_set,
+            description,
+            pipeline_id,
+            debug_name: None,
+        })
+    }
+
+    /// Creates the pipeline layout and the pipeline, the layout is destroyed again when the pipeline fails.
+    fn create_pipeline(
+        device: &VkLogicalDevice,
+        swapchain_extent: vk::Extent2D,
+        render_pass: &VkRenderPass,
+        shader_set: &mut ShaderSet,
+        description: &PipelineDescription,
+    ) -> Result<(vk::Pipeline, vk::PipelineLayout)> {
+        let builder = Self::pipeline_builder(
+            device,
+            swapchain_extent,
+            render_pass,
+            shader_set,
+            description,
+        );
 
         let layout = builder.layout;
-        let pipeline = builder.build(device, &mut shader_set);
 
-        VkPipeline {
-            layout: layout,
-            pipeline: pipeline,
-            shader: shader_set,
-            pipeline_id,
+        match builder.build(device, shader_set) {
+            Ok(pipeline) => Ok((pipeline, layout)),
+            Err(error) => {
+                unsafe { device.destroy_pipeline_layout(layout, None) };
+                Err(error)
+            }
         }
     }
 
@@ -286,17 +269,15 @@ impl VkPipeline {
         swapchain_extent: vk::Extent2D,
         render_pass: &VkRenderPass,
         shader_set: &mut ShaderSet,
+        description: &PipelineDescription,
     ) -> VkPipelineBuilder {
         let pipeline_layout = shader_set.io.create_pipeline_layout(device);
 
         VkPipelineBuilder::new()
             .vertex_input_state(shader_set.io.vertex_input_info())
-            .with_input_assembly_state()
             .with_viewport(swapchain_extent)
-            .with_rasterization_info()
-            .with_multilsample()
-            .with_depth_stage()
-            .color_blend()
+            .with_description(description)
+            .with_multilsample(render_pass.samples())
             .layout(pipeline_layout)
             .subpass(0)
             .with_renderpass(render_pass.clone())
@@ -308,51 +289,111 @@ impl VkPipeline {
         render_pass: &VkRenderPass,
         mut shader_set: ShaderSet,
         pipeline_id: u32,
-    ) -> VkPipeline {
+    ) -> Result<VkPipeline> {
         let pipeline_layout = shader_set.io.create_pipeline_layout(device);
 
+        // The ui is drawn on top of the scene, without depth testing.
+        let description =
+            PipelineDescription::default().with_depth_stencil(DepthStencilState::disabled());
+
         let pipeline = VkPipelineBuilder::new()
-            .with_rasterization_info()
-            .with_multilsample()
-            .with_input_assembly_state()
-            .color_blend()
+            .with_description(&description)
+            .with_multilsample(render_pass.samples())
             .with_viewport(Default::default())
             .with_dynamic_state()
-            .with_input_assembly_state()
             .layout(pipeline_layout)
             .vertex_input_state(shader_set.io.vertex_input_info())
             .with_renderpass(render_pass.clone())
-            .subpass(1)
+            .subpass(0)
             .build(device, &mut shader_set);
 
-        VkPipeline {
+        let pipeline = match pipeline {
+            Ok(pipeline) => pipeline,
+            Err(error) => {
+                unsafe { device.destroy_pipeline_layout(pipeline_layout, None) };
+                return Err(error);
+            }
+        };
+
+        Ok(VkPipeline {
             layout: pipeline_layout,
             pipeline,
             shader: shader_set,
+            description,
             pipeline_id,
-        }
+            debug_name: None,
+        })
     }
 
     pub fn layout(&self) -> vk::PipelineLayout {
         self.layout
     }
 
+    /// Names the pipeline and its layout in validation messages and captures, the name is kept on refresh.
+    pub fn set_debug_name(&mut self, device: &VkLogicalDevice, name: &str) {
+        self.debug_name = Some(name.to_string());
+        self.apply_debug_name(device);
+    }
+
+    pub fn debug_name(&self) -> Option<&str> {
+        self.debug_name.as_deref()
+    }
+
+    fn apply_debug_name(&self, device: &VkLogicalDevice) {
+        if let Some(name) = self.debug_name.as_ref() {
+            device.set_debug_name(self.pipeline, name);
+            device.set_debug_name(self.layout, name);
+        }
+    }
+
+    /// The fixed function state this pipeline was created with.
+    pub fn description(&self) -> &PipelineDescription {
+        &self.description
+    }
+
     /// Refreshes the pipeline.
-    /// This will destroy the pipeline and layout but will keep the shaders and meshes.
+    /// This will recreate the pipeline and layout but will keep the shaders and meshes.
+    ///
+    /// The old pipeline and layout are only destroyed once the new ones were created,
+    /// on failure the pipeline keeps using them.
     pub unsafe fn refresh(
         &mut self,
         device: &VkLogicalDevice,
-        swapchain: &VkSwapChain,
+        extent: vk::Extent2D,
         render_pass: &VkRenderPass,
-    ) {
+    ) -> Result<()> {
+        let (pipeline, layout) = Self::create_pipeline(
+            device,
+            extent,
+            render_pass,
+            &mut self.shader,
+            &self.description,
+        )?;
+
         device.destroy_pipeline(self.pipeline, None);
         device.destroy_pipeline_layout(self.layout, None);
 
-        let builder =
-            Self::pipeline_builder(device, swapchain.extent, render_pass, &mut self.shader);
+        self.pipeline = pipeline;
+        self.layout = layout;
+
+        self.apply_debug_name(device);
+
+        Ok(())
+    }
 
-        self.layout = builder.layout;
-        self.pipeline = builder.build(device, &mut self.shader);
+    /// Reloads the shaders from their files and rebuilds the pipeline with them.
+    ///
+    /// When the shaders fail to load, or the pipeline fails to be created with them,
+    /// the pipeline keeps the old one.
+    /// The pipeline must not be in use by the device.
+    pub unsafe fn reload_shaders(
+        &mut self,
+        device: &VkLogicalDevice,
+        extent: vk::Extent2D,
+        render_pass: &VkRenderPass,
+    ) -> Result<()> {
+        self.shader.reload(device)?;
+        self.refresh(device, extent, render_pass)
     }
 
     /// Destroys the pipeline and its contents:
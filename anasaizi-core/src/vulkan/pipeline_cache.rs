@@ -0,0 +1,159 @@
+use ash::{version::DeviceV1_0, vk};
+use std::{
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+use crate::Result;
+
+/// Environment variable that overrides the directory the pipeline cache is stored in.
+pub const PIPELINE_CACHE_DIR_ENV: &str = "ANASAIZI_PIPELINE_CACHE_DIR";
+
+/// Directory the pipeline cache is stored in when `ANASAIZI_PIPELINE_CACHE_DIR` is not set.
+pub const DEFAULT_PIPELINE_CACHE_DIR: &str = "cache";
+
+/// The size of the header vulkan puts in front of the cache data, version one.
+const CACHE_HEADER_SIZE: usize = 32;
+
+/// A vulkan pipeline cache that is persisted on disk.
+///
+/// The cache file is keyed by the device and driver version, data of another driver is never loaded.
+#[derive(Clone)]
+pub struct VkPipelineCache {
+    cache: vk::PipelineCache,
+    path: PathBuf,
+}
+
+impl VkPipelineCache {
+    /// Creates the pipeline cache, seeded with the data saved by a previous run when there is any.
+    ///
+    /// # Arguments
+    /// - `properties`: The properties of the physical device the cache is created for.
+    pub fn new(
+        device: &ash::Device,
+        properties: &vk::PhysicalDeviceProperties,
+    ) -> Result<VkPipelineCache> {
+        let path = Self::cache_directory().join(Self::file_name(properties));
+
+        let initial_data = fs::read(&path)
+            .ok()
+            .filter(|data| Self::is_compatible(data, properties))
+            .unwrap_or_default();
+
+        let create_info = vk::PipelineCacheCreateInfo::builder()
+            .initial_data(&initial_data)
+            .build();
+
+        let cache = unsafe { device.create_pipeline_cache(&create_info, None)? };
+
+        Ok(VkPipelineCache { cache, path })
+    }
+
+    /// The file the cache is saved to.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes the cache data to disk so the next run can reuse the compiled pipelines.
+    pub fn save(&self, device: &ash::Device) -> Result<()> {
+        let data = unsafe { device.get_pipeline_cache_data(self.cache)? };
+
+        if let Some(directory) = self.path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+
+        // Write to a temporary file first so a crash never leaves a truncated cache behind.
+        let temporary_path = self.path.with_extension("tmp");
+        fs::write(&temporary_path, &data)?;
+        fs::rename(&temporary_path, &self.path)?;
+
+        Ok(())
+    }
+
+    /// Destroys the cache, call `save` first to keep its data.
+    pub unsafe fn destroy(&self, device: &ash::Device) {
+        device.destroy_pipeline_cache(self.cache, None);
+    }
+
+    fn cache_directory() -> PathBuf {
+        match std::env::var(PIPELINE_CACHE_DIR_ENV) {
+            Ok(directory) if !directory.trim().is_empty() => PathBuf::from(directory.trim()),
+            _ => PathBuf::from(DEFAULT_PIPELINE_CACHE_DIR),
+        }
+    }
+
+    /// The cache file name, made from the vendor, device, pipeline cache UUID and driver version.
+    fn file_name(properties: &vk::PhysicalDeviceProperties) -> String {
+        let uuid: String = properties
+            .pipeline_cache_uuid
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        format!(
+            "pipeline_{:04x}_{:04x}_{}_{:08x}.bin",
+            properties.vendor_id, properties.device_id, uuid, properties.driver_version
+        )
+    }
+
+    /// Validates the cache header against the device, some drivers do not reject foreign data themselves.
+    pub(crate) fn is_compatible(data: &[u8], properties: &vk::PhysicalDeviceProperties) -> bool {
+        if data.len() < CACHE_HEADER_SIZE {
+            return false;
+        }
+
+        let read_u32 = |offset: usize| {
+            let mut bytes = [0u8; 4];
+            bytes.copy_from_slice(&data[offset..offset + 4]);
+            u32::from_ne_bytes(bytes)
+        };
+
+        read_u32(0) as usize >= CACHE_HEADER_SIZE
+            && read_u32(4) == vk::PipelineCacheHeaderVersion::ONE.as_raw() as u32
+            && read_u32(8) == properties.vendor_id
+            && read_u32(12) == properties.device_id
+            && data[16..32] == properties.pipeline_cache_uuid
+    }
+}
+
+impl Deref for VkPipelineCache {
+    type Target = vk::PipelineCache;
+
+    fn deref(&self) -> &Self::Target {
+        &self.cache
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::VkPipelineCache;
+    use ash::vk;
+
+    fn cache_data(properties: &vk::PhysicalDeviceProperties) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&32u32.to_ne_bytes());
+        data.extend_from_slice(&1u32.to_ne_bytes());
+        data.extend_from_slice(&properties.vendor_id.to_ne_bytes());
+        data.extend_from_slice(&properties.device_id.to_ne_bytes());
+        data.extend_from_slice(&properties.pipeline_cache_uuid);
+        data.extend_from_slice(&[7u8; 64]);
+        data
+    }
+
+    #[test]
+    fn header_must_match_device() {
+        let mut properties = vk::PhysicalDeviceProperties::default();
+        properties.vendor_id = 0x10de;
+        properties.device_id = 0x2204;
+        properties.pipeline_cache_uuid = [3u8; 16];
+
+        let data = cache_data(&properties);
+        assert!(VkPipelineCache::is_compatible(&data, &properties));
+        assert!(!VkPipelineCache::is_compatible(&data[..16], &properties));
+
+        let mut other_driver = properties;
+        other_driver.pipeline_cache_uuid = [4u8; 16];
+        assert!(!VkPipelineCache::is_compatible(&data, &other_driver));
+    }
+}
@@ -0,0 +1,352 @@
+use ash::vk;
+use std::hash::{Hash, Hasher};
+
+/// Describes the fixed function state of a graphics pipeline.
+///
+/// The description is hashable so pipelines with the same state can be shared.
+/// `Default` gives the state the engine has always used: no culling, clockwise front faces,
+/// filled triangle lists, depth test LESS with writes and one alpha blended color attachment.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct PipelineDescription {
+    pub rasterization: RasterizationState,
+    pub topology: vk::PrimitiveTopology,
+    pub depth_stencil: DepthStencilState,
+    /// The blend state of each color attachment in the subpass.
+    pub blend_attachments: Vec<BlendState>,
+}
+
+impl PipelineDescription {
+    pub fn with_cull_mode(mut self, cull_mode: vk::CullModeFlags) -> Self {
+        self.rasterization.cull_mode = cull_mode;
+        self
+    }
+
+    pub fn with_front_face(mut self, front_face: vk::FrontFace) -> Self {
+        self.rasterization.front_face = front_face;
+        self
+    }
+
+    pub fn with_polygon_mode(mut self, polygon_mode: vk::PolygonMode) -> Self {
+        self.rasterization.polygon_mode = polygon_mode;
+        self
+    }
+
+    pub fn with_line_width(mut self, line_width: f32) -> Self {
+        self.rasterization.line_width = line_width;
+        self
+    }
+
+    pub fn with_depth_bias(mut self, depth_bias: DepthBias) -> Self {
+        self.rasterization.depth_bias = Some(depth_bias);
+        self
+    }
+
+    pub fn with_topology(mut self, topology: vk::PrimitiveTopology) -> Self {
+        self.topology = topology;
+        self
+    }
+
+    pub fn with_depth_stencil(mut self, depth_stencil: DepthStencilState) -> Self {
+        self.depth_stencil = depth_stencil;
+        self
+    }
+
+    /// Sets the blend state, one for each color attachment.
+    pub fn with_blend_attachments(mut self, blend_attachments: Vec<BlendState>) -> Self {
+        self.blend_attachments = blend_attachments;
+        self
+    }
+
+    pub(crate) fn rasterization_info(&self) -> vk::PipelineRasterizationStateCreateInfo {
+        let rasterization = &self.rasterization;
+        let depth_bias = rasterization.depth_bias.unwrap_or_default();
+
+        vk::PipelineRasterizationStateCreateInfo::builder()
+            .depth_clamp_enable(false)
+            .rasterizer_discard_enable(false)
+            .polygon_mode(rasterization.polygon_mode)
+            .line_width(rasterization.line_width)
+            .cull_mode(rasterization.cull_mode)
+            .front_face(rasterization.front_face)
+            .depth_bias_enable(rasterization.depth_bias.is_some())
+            .depth_bias_constant_factor(depth_bias.constant_factor)
+            .depth_bias_clamp(depth_bias.clamp)
+            .depth_bias_slope_factor(depth_bias.slope_factor)
+            .build()
+    }
+
+    pub(crate) fn input_assembly_info(&self) -> vk::PipelineInputAssemblyStateCreateInfo {
+        vk::PipelineInputAssemblyStateCreateInfo::builder()
+            .primitive_restart_enable(false)
+            .topology(self.topology)
+            .build()
+    }
+
+    pub(crate) fn depth_stencil_info(&self) -> vk::PipelineDepthStencilStateCreateInfo {
+        let depth_stencil = &self.depth_stencil;
+
+        vk::PipelineDepthStencilStateCreateInfo::builder()
+            .depth_test_enable(depth_stencil.depth_test)
+            .depth_write_enable(depth_stencil.depth_write)
+            .depth_compare_op(depth_stencil.depth_compare_op)
+            .stencil_test_enable(depth_stencil.stencil_test)
+            .front(depth_stencil.front.into())
+            .back(depth_stencil.back.into())
+            .depth_bounds_test_enable(false)
+            .max_depth_bounds(1.0)
+            .min_depth_bounds(0.0)
+            .build()
+    }
+
+    pub(crate) fn blend_attachment_states(&self) -> Vec<vk::PipelineColorBlendAttachmentState> {
+        self.blend_attachments
+            .iter()
+            .map(|blend| blend.attachment_state())
+            .collect()
+    }
+}
+
+impl Default for PipelineDescription {
+    fn default() -> Self {
+        PipelineDescription {
+            rasterization: RasterizationState::default(),
+            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+            depth_stencil: DepthStencilState::default(),
+            blend_attachments: vec![BlendState::alpha()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RasterizationState {
+    pub cull_mode: vk::CullModeFlags,
+    pub front_face: vk::FrontFace,
+    pub polygon_mode: vk::PolygonMode,
+    pub line_width: f32,
+    /// Depth bias is disabled when `None`.
+    pub depth_bias: Option<DepthBias>,
+}
+
+impl Default for RasterizationState {
+    fn default() -> Self {
+        RasterizationState {
+            cull_mode: vk::CullModeFlags::NONE,
+            front_face: vk::FrontFace::CLOCKWISE,
+            polygon_mode: vk::PolygonMode::FILL,
+            line_width: 1.0,
+            depth_bias: None,
+        }
+    }
+}
+
+impl PartialEq for RasterizationState {
+    fn eq(&self, other: &Self) -> bool {
+        self.cull_mode == other.cull_mode
+            && self.front_face == other.front_face
+            && self.polygon_mode == other.polygon_mode
+            && self.line_width.to_bits() == other.line_width.to_bits()
+            && self.depth_bias == other.depth_bias
+    }
+}
+
+impl Eq for RasterizationState {}
+
+impl Hash for RasterizationState {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.cull_mode.hash(state);
+        self.front_face.hash(state);
+        self.polygon_mode.hash(state);
+        self.line_width.to_bits().hash(state);
+        self.depth_bias.hash(state);
+    }
+}
+
+/// Depth bias, used to avoid z-fighting for example for shadow maps and decals.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DepthBias {
+    pub constant_factor: f32,
+    pub clamp: f32,
+    pub slope_factor: f32,
+}
+
+impl PartialEq for DepthBias {
+    fn eq(&self, other: &Self) -> bool {
+        self.constant_factor.to_bits() == other.constant_factor.to_bits()
+            && self.clamp.to_bits() == other.clamp.to_bits()
+            && self.slope_factor.to_bits() == other.slope_factor.to_bits()
+    }
+}
+
+impl Eq for DepthBias {}
+
+impl Hash for DepthBias {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.constant_factor.to_bits().hash(state);
+        self.clamp.to_bits().hash(state);
+        self.slope_factor.to_bits().hash(state);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DepthStencilState {
+    pub depth_test: bool,
+    pub depth_write: bool,
+    pub depth_compare_op: vk::CompareOp,
+    pub stencil_test: bool,
+    pub front: StencilFaceState,
+    pub back: StencilFaceState,
+}
+
+impl DepthStencilState {
+    /// Depth and stencil testing disabled.
+    pub fn disabled() -> Self {
+        DepthStencilState {
+            depth_test: false,
+            depth_write: false,
+            depth_compare_op: vk::CompareOp::NEVER,
+            stencil_test: false,
+            front: StencilFaceState::default(),
+            back: StencilFaceState::default(),
+        }
+    }
+}
+
+impl Default for DepthStencilState {
+    fn default() -> Self {
+        DepthStencilState {
+            depth_test: true,
+            depth_write: true,
+            depth_compare_op: vk::CompareOp::LESS,
+            stencil_test: true,
+            front: StencilFaceState::default(),
+            back: StencilFaceState::default(),
+        }
+    }
+}
+
+/// The stencil operations of one face, see `vk::StencilOpState`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct StencilFaceState {
+    pub fail_op: vk::StencilOp,
+    pub pass_op: vk::StencilOp,
+    pub depth_fail_op: vk::StencilOp,
+    pub compare_op: vk::CompareOp,
+    pub compare_mask: u32,
+    pub write_mask: u32,
+    pub reference: u32,
+}
+
+impl Default for StencilFaceState {
+    fn default() -> Self {
+        StencilFaceState {
+            fail_op: vk::StencilOp::KEEP,
+            pass_op: vk::StencilOp::KEEP,
+            depth_fail_op: vk::StencilOp::KEEP,
+            compare_op: vk::CompareOp::ALWAYS,
+            compare_mask: 0,
+            write_mask: 0,
+            reference: 0,
+        }
+    }
+}
+
+impl From<StencilFaceState> for vk::StencilOpState {
+    fn from(face: StencilFaceState) -> Self {
+        vk::StencilOpState::builder()
+            .fail_op(face.fail_op)
+            .pass_op(face.pass_op)
+            .depth_fail_op(face.depth_fail_op)
+            .compare_op(face.compare_op)
+            .compare_mask(face.compare_mask)
+            .write_mask(face.write_mask)
+            .reference(face.reference)
+            .build()
+    }
+}
+
+/// The blend state of one color attachment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlendState {
+    pub blend_enable: bool,
+    pub src_color_blend_factor: vk::BlendFactor,
+    pub dst_color_blend_factor: vk::BlendFactor,
+    pub color_blend_op: vk::BlendOp,
+    pub src_alpha_blend_factor: vk::BlendFactor,
+    pub dst_alpha_blend_factor: vk::BlendFactor,
+    pub alpha_blend_op: vk::BlendOp,
+    pub color_write_mask: vk::ColorComponentFlags,
+}
+
+impl BlendState {
+    /// Writes the color as is.
+    pub fn opaque() -> Self {
+        BlendState {
+            blend_enable: false,
+            ..Self::alpha()
+        }
+    }
+
+    /// Blends by the source alpha, the blend mode the engine uses by default.
+    pub fn alpha() -> Self {
+        BlendState {
+            blend_enable: true,
+            src_color_blend_factor: vk::BlendFactor::SRC_ALPHA,
+            dst_color_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            color_blend_op: vk::BlendOp::ADD,
+            src_alpha_blend_factor: vk::BlendFactor::SRC_ALPHA,
+            dst_alpha_blend_factor: vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            alpha_blend_op: vk::BlendOp::SUBTRACT,
+            color_write_mask: vk::ColorComponentFlags::all(),
+        }
+    }
+
+    /// Adds the source color to the destination, for example for particles and light accumulation.
+    pub fn additive() -> Self {
+        BlendState {
+            blend_enable: true,
+            src_color_blend_factor: vk::BlendFactor::ONE,
+            dst_color_blend_factor: vk::BlendFactor::ONE,
+            color_blend_op: vk::BlendOp::ADD,
+            src_alpha_blend_factor: vk::BlendFactor::ONE,
+            dst_alpha_blend_factor: vk::BlendFactor::ONE,
+            alpha_blend_op: vk::BlendOp::ADD,
+            color_write_mask: vk::ColorComponentFlags::all(),
+        }
+    }
+
+    fn attachment_state(&self) -> vk::PipelineColorBlendAttachmentState {
+        vk::PipelineColorBlendAttachmentState::builder()
+            .blend_enable(self.blend_enable)
+            .src_color_blend_factor(self.src_color_blend_factor)
+            .dst_color_blend_factor(self.dst_color_blend_factor)
+            .color_blend_op(self.color_blend_op)
+            .src_alpha_blend_factor(self.src_alpha_blend_factor)
+            .dst_alpha_blend_factor(self.dst_alpha_blend_factor)
+            .alpha_blend_op(self.alpha_blend_op)
+            .color_write_mask(self.color_write_mask)
+            .build()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::{DepthBias, PipelineDescription};
+    use ash::vk;
+    use std::collections::HashSet;
+
+    #[test]
+    fn equal_descriptions_hash_the_same() {
+        let mut descriptions = HashSet::new();
+
+        descriptions.insert(PipelineDescription::default());
+        descriptions.insert(PipelineDescription::default());
+        descriptions.insert(PipelineDescription::default().with_cull_mode(vk::CullModeFlags::BACK));
+        descriptions.insert(PipelineDescription::default().with_depth_bias(DepthBias {
+            constant_factor: 1.25,
+            clamp: 0.0,
+            slope_factor: 1.75,
+        }));
+
+        assert_eq!(descriptions.len(), 3);
+    }
+}
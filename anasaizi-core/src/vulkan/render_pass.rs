@@ -5,6 +5,7 @@ use std::ops::Deref;
 pub struct VkSubpassDescriptor {
     color: (bool, usize),
     depth: (bool, usize),
+    resolve: (bool, usize),
 }
 
 impl VkSubpassDescriptor {
@@ -12,6 +13,7 @@ impl VkSubpassDescriptor {
         VkSubpassDescriptor {
             color: (false, usize::MAX),
             depth: (false, usize::MAX),
+            resolve: (false, usize::MAX),
         }
     }
     pub fn with_color(mut self, attachment_index: usize) -> VkSubpassDescriptor {
@@ -23,14 +25,32 @@ impl VkSubpassDescriptor {
         self.depth = (true, attachment_index);
         self
     }
+
+    /// Resolves the multisampled color attachments into the resolve attachments at the end of the subpass.
+    pub fn with_resolve(mut self, attachment_index: usize) -> VkSubpassDescriptor {
+        self.resolve = (true, attachment_index);
+        self
+    }
+}
+
+/// How a render pass uses an attachment that is added with `VkRenderPassBuilder::add_attachment`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AttachmentUsage {
+    Color,
+    Depth,
+    /// A multisampled color attachment is resolved into it.
+    Resolve,
 }
 
 pub struct VkRenderPassBuilder {
     attachments: Vec<vk::AttachmentDescription>,
     color_attachment_refs: Vec<vk::AttachmentReference>,
     depth_attachment_refs: vk::AttachmentReference,
+    resolve_attachment_refs: Vec<vk::AttachmentReference>,
     subpasses: Vec<vk::SubpassDescription>,
     dependencies: Vec<vk::SubpassDependency>,
+    samples: vk::SampleCountFlags,
+    debug_name: Option<String>,
 }
 
 impl VkRenderPassBuilder {
@@ -40,10 +60,19 @@ impl VkRenderPassBuilder {
             subpasses: Vec::new(),
             color_attachment_refs: Vec::new(),
             depth_attachment_refs: vk::AttachmentReference::default(),
+            resolve_attachment_refs: Vec::new(),
             dependencies: Vec::new(),
+            samples: vk::SampleCountFlags::TYPE_1,
+            debug_name: None,
         }
     }
 
+    /// Names the render pass in validation messages and captures.
+    pub fn with_debug_name(mut self, name: &str) -> VkRenderPassBuilder {
+        self.debug_name = Some(name.to_string());
+        self
+    }
+
     pub fn add_color_attachment(
         mut self,
         attachment_number: u32,
@@ -72,14 +101,77 @@ impl VkRenderPassBuilder {
         self
     }
 
+    /// Adds a multisampled color attachment, it only lives during the render pass and has to be
+    /// resolved into a resolve attachment.
+    pub fn add_multisampled_color_attachment(
+        mut self,
+        attachment_number: u32,
+        format: vk::Format,
+        samples: vk::SampleCountFlags,
+    ) -> VkRenderPassBuilder {
+        let color_attachment = vk::AttachmentDescription::builder()
+            .format(format)
+            .samples(samples)
+            .load_op(vk::AttachmentLoadOp::CLEAR)
+            .store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .final_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .build();
+
+        let color_attachment_ref = vk::AttachmentReference::builder()
+            .attachment(attachment_number)
+            .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .build();
+
+        self.attachments.push(color_attachment);
+        self.color_attachment_refs.push(color_attachment_ref);
+        self.samples = samples;
+        self
+    }
+
+    /// Adds a single sampled attachment the multisampled color attachment is resolved into.
+    ///
+    /// # Arguments
+    /// - `final_layout`: The layout the image is in after the render pass, for example PRESENT_SRC_KHR.
+    pub fn add_resolve_attachment(
+        mut self,
+        attachment_number: u32,
+        format: vk::Format,
+        final_layout: vk::ImageLayout,
+    ) -> VkRenderPassBuilder {
+        let resolve_attachment = vk::AttachmentDescription::builder()
+            .format(format)
+            .samples(vk::SampleCountFlags::TYPE_1)
+            .load_op(vk::AttachmentLoadOp::DONT_CARE)
+            .store_op(vk::AttachmentStoreOp::STORE)
+            .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .final_layout(final_layout)
+            .build();
+
+        let resolve_attachment_ref = vk::AttachmentReference::builder()
+            .attachment(attachment_number)
+            .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .build();
+
+        self.attachments.push(resolve_attachment);
+        self.resolve_attachment_refs.push(resolve_attachment_ref);
+        self
+    }
+
+    /// Adds the depth attachment, `samples` has to match the color attachments.
     pub fn add_depth_attachment(
         mut self,
         attachment_number: u32,
         format: vk::Format,
+        samples: vk::SampleCountFlags,
     ) -> VkRenderPassBuilder {
         let depth_color_attachment = vk::AttachmentDescription::builder()
             .format(format)
-            .samples(vk::SampleCountFlags::TYPE_1)
+            .samples(samples)
             .load_op(vk::AttachmentLoadOp::CLEAR)
             .store_op(vk::AttachmentStoreOp::DONT_CARE)
             .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
@@ -99,6 +191,44 @@ impl VkRenderPassBuilder {
         self
     }
 
+    /// Adds an attachment with the given load and store operations and layouts.
+    ///
+    /// The attachment is in its attachment layout during the subpasses, the render graph uses this to
+    /// derive the attachments of its passes.
+    pub fn add_attachment(
+        mut self,
+        attachment_number: u32,
+        usage: AttachmentUsage,
+        description: vk::AttachmentDescription,
+    ) -> VkRenderPassBuilder {
+        let layout = match usage {
+            AttachmentUsage::Depth => vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            AttachmentUsage::Color | AttachmentUsage::Resolve => {
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+            }
+        };
+
+        let attachment_ref = vk::AttachmentReference::builder()
+            .attachment(attachment_number)
+            .layout(layout)
+            .build();
+
+        match usage {
+            AttachmentUsage::Color => {
+                self.color_attachment_refs.push(attachment_ref);
+                self.samples = description.samples;
+            }
+            AttachmentUsage::Depth => {
+                self.depth_attachment_refs = attachment_ref;
+                self.samples = description.samples;
+            }
+            AttachmentUsage::Resolve => self.resolve_attachment_refs.push(attachment_ref),
+        }
+
+        self.attachments.push(description);
+        self
+    }
+
     pub fn add_subpasses(
         mut self,
         subpasses: Vec<VkSubpassDescriptor>,
@@ -115,6 +245,10 @@ impl VkRenderPassBuilder {
                 subpass_builder =
                     subpass_builder.depth_stencil_attachment(&self.depth_attachment_refs);
             }
+            if subpass.resolve.0 {
+                subpass_builder =
+                    subpass_builder.resolve_attachments(&self.resolve_attachment_refs);
+            }
 
             self.subpasses.push(
                 subpass_builder
@@ -139,7 +273,14 @@ impl VkRenderPassBuilder {
                 .expect("Failed to create render pass!")
         };
 
-        VkRenderPass { render_pass }
+        if let Some(name) = self.debug_name.as_ref() {
+            device.set_debug_name(render_pass, name);
+        }
+
+        VkRenderPass {
+            render_pass,
+            samples: self.samples,
+        }
     }
 }
 
@@ -153,9 +294,15 @@ impl VkRenderPassBuilder {
 #[derive(Clone)]
 pub struct VkRenderPass {
     render_pass: vk::RenderPass,
+    samples: vk::SampleCountFlags,
 }
 
 impl VkRenderPass {
+    /// The sample count of the color attachments, pipelines of this render pass have to use it.
+    pub fn samples(&self) -> vk::SampleCountFlags {
+        self.samples
+    }
+
     /// Destroys the render pass.
     pub unsafe fn destroy(&self, device: &VkLogicalDevice) {
         device.destroy_render_pass(self.render_pass, None);
@@ -1,13 +1,27 @@
+pub use bindless_texture_table::{
+    BindlessTextureTable, BindlessTextures, BINDLESS_TEXTURE_BINDING, BINDLESS_TEXTURE_CAPACITY,
+    BINDLESS_TEXTURE_SET,
+};
 pub use descriptor_pool::DescriptorPool;
 pub use descriptor_set::DescriptorSet;
+pub use reflection::{
+    DescriptorCount, ReflectedBinding, ReflectedInput, ReflectedSpecializationConstant,
+    ShaderReflection,
+};
 pub use shader_builder::ShaderBuilder;
+pub use shader_compiler::{ShaderCompiler, ShaderSource};
 pub use shader_flags::ShaderFlags;
-pub use shader_io::{ShaderIOBuilder, ShaderIo};
+pub use shader_io::{DescriptorFrequency, ShaderDescriptorSet, ShaderIOBuilder, ShaderIo};
 pub use shader_set::ShaderSet;
+pub use shader_watcher::ShaderWatcher;
 
+mod bindless_texture_table;
 mod descriptor_pool;
 mod descriptor_set;
+mod reflection;
 mod shader_builder;
+mod shader_compiler;
 mod shader_flags;
 mod shader_io;
 mod shader_set;
+mod shader_watcher;
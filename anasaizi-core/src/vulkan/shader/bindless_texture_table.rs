@@ -0,0 +1,181 @@
+use crate::{engine::image::Texture, vulkan::VkLogicalDevice, Error, Result};
+use ash::{version::DeviceV1_0, vk};
+
+/// The descriptor set shaders declare the bindless textures in, set 0 belongs to the shader itself.
+pub const BINDLESS_TEXTURE_SET: u32 = 1;
+/// The binding of the texture array in the bindless texture set.
+pub const BINDLESS_TEXTURE_BINDING: u32 = 0;
+/// How many textures the table of the renderer can hold.
+pub const BINDLESS_TEXTURE_CAPACITY: u32 = 1024;
+
+/// The handles a shader needs to use the bindless texture table.
+#[derive(Debug, Clone, Copy)]
+pub struct BindlessTextures {
+    pub descriptor_set_layout: vk::DescriptorSetLayout,
+    pub descriptor_set: vk::DescriptorSet,
+    pub capacity: u32,
+}
+
+/// A global array of sampled images that textures register into.
+///
+/// Shaders declare it as `layout(set = 1, binding = 0) uniform texture2D textures[N]`, with `N` at most
+/// the capacity, and index it with the index returned by `register`. The array is partially bound,
+/// slots without a texture must not be sampled, and textures can be registered while the set is in use
+/// by the device.
+pub struct BindlessTextureTable {
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_set_layout: vk::DescriptorSetLayout,
+    descriptor_set: vk::DescriptorSet,
+    capacity: u32,
+    len: u32,
+}
+
+impl BindlessTextureTable {
+    /// Creates an empty table.
+    ///
+    /// # Arguments
+    /// - `capacity`: The number of textures the table can hold.
+    pub fn new(device: &VkLogicalDevice, capacity: u32) -> BindlessTextureTable {
+        let descriptor_type = vk::DescriptorType::SAMPLED_IMAGE;
+
+        let bindings = [vk::DescriptorSetLayoutBinding::builder()
+            .binding(BINDLESS_TEXTURE_BINDING)
+            .descriptor_type(descriptor_type)
+            .descriptor_count(capacity)
+            .stage_flags(vk::ShaderStageFlags::ALL)
+            .build()];
+
+        let binding_flags = [vk::DescriptorBindingFlags::PARTIALLY_BOUND
+            | vk::DescriptorBindingFlags::UPDATE_AFTER_BIND];
+        let mut binding_flags_info =
+            vk::DescriptorSetLayoutBindingFlagsCreateInfo::builder().binding_flags(&binding_flags);
+
+        let layout_create_info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .flags(vk::DescriptorSetLayoutCreateFlags::UPDATE_AFTER_BIND_POOL)
+            .bindings(&bindings)
+            .push_next(&mut binding_flags_info);
+
+        let descriptor_set_layout = unsafe {
+            device
+                .create_descriptor_set_layout(&layout_create_info, None)
+                .expect("Failed to create bindless texture set layout!")
+        };
+
+        let pool_sizes = [vk::DescriptorPoolSize {
+            ty: descriptor_type,
+            descriptor_count: capacity,
+        }];
+
+        let pool_create_info = vk::DescriptorPoolCreateInfo::builder()
+            .flags(vk::DescriptorPoolCreateFlags::UPDATE_AFTER_BIND)
+            .max_sets(1)
+            .pool_sizes(&pool_sizes);
+
+        let descriptor_pool = unsafe {
+            device
+                .create_descriptor_pool(&pool_create_info, None)
+                .expect("Failed to create bindless texture pool!")
+        };
+
+        let set_layouts = [descriptor_set_layout];
+        let allocate_info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(descriptor_pool)
+            .set_layouts(&set_layouts);
+
+        let descriptor_set = unsafe {
+            device
+                .allocate_descriptor_sets(&allocate_info)
+                .expect("Failed to allocate bindless texture set!")[0]
+        };
+
+        BindlessTextureTable {
+            descriptor_pool,
+            descriptor_set_layout,
+            descriptor_set,
+            capacity,
+            len: 0,
+        }
+    }
+
+    /// Adds the texture to the table and returns its index, the index stays valid for the lifetime of the table.
+    ///
+    /// Every call takes a new slot, textures registered one after another get consecutive indices.
+    /// Fails when the table is full.
+    pub fn register(&mut self, device: &VkLogicalDevice, texture: &Texture) -> Result<u32> {
+        if self.len == self.capacity {
+            return Err(Error::Unsupported(format!(
+                "more than {} bindless textures",
+                self.capacity
+            )));
+        }
+
+        let index = self.len;
+
+        let image_info = [vk::DescriptorImageInfo::builder()
+            .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+            .image_view(*texture.image_view)
+            .build()];
+
+        let write_set = vk::WriteDescriptorSet::builder()
+            .dst_set(self.descriptor_set)
+            .dst_binding(BINDLESS_TEXTURE_BINDING)
+            .dst_array_element(index)
+            .descriptor_type(vk::DescriptorType::SAMPLED_IMAGE)
+            .image_info(&image_info)
+            .build();
+
+        unsafe {
+            device.update_descriptor_sets(&[write_set], &[]);
+        }
+
+        self.len += 1;
+
+        Ok(index)
+    }
+
+    /// Registers the textures in order and returns the index of the first one.
+    pub fn register_all(&mut self, device: &VkLogicalDevice, textures: &[Texture]) -> Result<u32> {
+        if self.len as usize + textures.len() > self.capacity as usize {
+            return Err(Error::Unsupported(format!(
+                "more than {} bindless textures",
+                self.capacity
+            )));
+        }
+
+        let first = self.len;
+
+        for texture in textures {
+            self.register(device, texture)?;
+        }
+
+        Ok(first)
+    }
+
+    /// The number of registered textures.
+    pub fn len(&self) -> u32 {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    /// The handles to add to a shader with `ShaderIOBuilder::bindless_textures`.
+    pub fn descriptors(&self) -> BindlessTextures {
+        BindlessTextures {
+            descriptor_set_layout: self.descriptor_set_layout,
+            descriptor_set: self.descriptor_set,
+            capacity: self.capacity,
+        }
+    }
+
+    /// Destroys the descriptor pool and set layout, the registered textures are owned by the caller.
+    pub unsafe fn destroy(&self, device: &VkLogicalDevice) {
+        device.destroy_descriptor_pool(self.descriptor_pool, None);
+        device.destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+    }
+}
@@ -1,20 +1,24 @@
-use crate::vulkan::{DescriptorSet, UniformBuffer, VkLogicalDevice};
+use crate::vulkan::{DescriptorSet, VkLogicalDevice};
 use ash::{version::DeviceV1_0, vk};
-use std::{collections::HashMap, ops::Deref, ptr};
+use std::{ops::Deref, ptr};
 
 /// Descriptor sets can't be created directly, they must be allocated from a pool like command buffers.
 /// The equivalent for descriptor sets is unsurprisingly called a descriptor pool.
 pub struct DescriptorPool {
     descriptor_pool: vk::DescriptorPool,
-    image_count: usize,
 }
 
 impl DescriptorPool {
     /// Creates a new `DescriptorPool`.
+    ///
+    /// # Arguments
+    /// - `descriptor_types`: One entry for every descriptor of a set, each entry has room for `image_count` descriptors.
+    /// - `max_sets`: The number of sets that can be allocated from the pool.
     pub fn new(
         device: &ash::Device,
         descriptor_types: &[vk::DescriptorType],
         image_count: usize,
+        max_sets: usize,
     ) -> DescriptorPool {
         let mut descriptor_pool_sizes = vec![];
 
@@ -26,7 +30,7 @@ impl DescriptorPool {
         }
 
         let mut pool_create_info = vk::DescriptorPoolCreateInfo::builder()
-            .max_sets(image_count as u32)
+            .max_sets(max_sets as u32)
             .build();
 
         if descriptor_types.len() != 0 {
@@ -40,23 +44,26 @@ impl DescriptorPool {
                 .expect("Could not create descriptor pool.")
         };
 
-        DescriptorPool {
-            image_count,
-            descriptor_pool,
-        }
+        DescriptorPool { descriptor_pool }
     }
 
     /// Creates descriptor sets.
+    ///
+    /// # Arguments
+    /// - `buffer_infos`: The buffers of the buffer writes, in write order. Every entry holds one buffer per set,
+    ///   or a single buffer that is used by every set.
+    /// - `set_count`: The number of sets, set `i` uses the buffers of frame `i`.
     pub fn create_descriptor_sets(
         &self,
         device: &ash::Device,
         descriptor_set_layout: vk::DescriptorSetLayout,
         descriptor_write_sets: Vec<vk::WriteDescriptorSet>,
-        uniform_buffers: &mut Vec<UniformBuffer>,
+        buffer_infos: &[Vec<vk::DescriptorBufferInfo>],
+        set_count: usize,
     ) -> Vec<DescriptorSet> {
         let mut layouts: Vec<vk::DescriptorSetLayout> = vec![];
 
-        for _ in 0..self.image_count {
+        for _ in 0..set_count {
             layouts.push(descriptor_set_layout);
         }
 
@@ -65,7 +72,7 @@ impl DescriptorPool {
             s_type: vk::StructureType::DESCRIPTOR_SET_ALLOCATE_INFO,
             p_next: ptr::null(),
             descriptor_pool: self.descriptor_pool,
-            descriptor_set_count: self.image_count as u32,
+            descriptor_set_count: set_count as u32,
             p_set_layouts: layouts.as_ptr(),
         };
 
@@ -79,34 +86,22 @@ impl DescriptorPool {
         let mut descriptor_set = vec![];
 
         for (i, descritptor_set) in descriptor_sets.iter().enumerate() {
-            let mut descriptor_buffer_infos = HashMap::new();
+            // Collected up front, the writes point into this vector.
+            let frame_buffer_infos: Vec<vk::DescriptorBufferInfo> = buffer_infos
+                .iter()
+                .map(|infos| if infos.len() == 1 { infos[0] } else { infos[i] })
+                .collect();
 
             let mut write_sets = vec![];
             write_sets.extend_from_slice(&descriptor_write_sets);
 
-            let mut uniform_buffer_index = 0;
+            let mut buffer_index = 0;
             for descriptor_write_set in write_sets.iter_mut() {
-                if descriptor_write_set.descriptor_type == vk::DescriptorType::UNIFORM_BUFFER {
-                    let uniform_buffer = &mut uniform_buffers[uniform_buffer_index];
-                    let frame_uniform_buffer = uniform_buffer.buffers(i);
-
-                    descriptor_buffer_infos.insert(
-                        uniform_buffer_index,
-                        vk::DescriptorBufferInfo {
-                            buffer: frame_uniform_buffer,
-                            offset: 0,
-                            range: uniform_buffer.uniform_object_size as u64,
-                        },
-                    );
-
-                    let buffer_info = descriptor_buffer_infos
-                        .get(&(uniform_buffer_index))
-                        .unwrap();
-
-                    descriptor_write_set.p_buffer_info = buffer_info;
+                if is_buffer_descriptor(descriptor_write_set.descriptor_type) {
+                    descriptor_write_set.p_buffer_info = &frame_buffer_infos[buffer_index];
                     descriptor_write_set.descriptor_count = 1;
 
-                    uniform_buffer_index += 1;
+                    buffer_index += 1;
                 }
             }
 
@@ -124,6 +119,17 @@ impl DescriptorPool {
     }
 }
 
+/// Returns whether descriptors of this type are written with buffer infos.
+fn is_buffer_descriptor(descriptor_type: vk::DescriptorType) -> bool {
+    matches!(
+        descriptor_type,
+        vk::DescriptorType::UNIFORM_BUFFER
+            | vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC
+            | vk::DescriptorType::STORAGE_BUFFER
+            | vk::DescriptorType::STORAGE_BUFFER_DYNAMIC
+    )
+}
+
 impl Deref for DescriptorPool {
     type Target = vk::DescriptorPool;
 
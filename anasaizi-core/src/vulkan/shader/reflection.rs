@@ -0,0 +1,1053 @@
+use crate::{
+    engine::BufferLayout,
+    vulkan::{ShaderIo, BINDLESS_TEXTURE_BINDING, BINDLESS_TEXTURE_SET},
+    Error, Result,
+};
+use ash::vk;
+use std::collections::HashMap;
+
+const SPIRV_MAGIC: u32 = 0x0723_0203;
+const SPIRV_HEADER_WORDS: usize = 5;
+
+// Opcodes
+const OP_ENTRY_POINT: u32 = 15;
+const OP_EXECUTION_MODE: u32 = 16;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_SPEC_CONSTANT_TRUE: u32 = 48;
+const OP_SPEC_CONSTANT_FALSE: u32 = 49;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+
+// Execution modes
+const EXECUTION_MODE_LOCAL_SIZE: u32 = 17;
+
+// Decorations
+const DECORATION_SPEC_ID: u32 = 1;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+// Storage classes
+const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_INPUT: u32 = 1;
+const STORAGE_UNIFORM: u32 = 2;
+const STORAGE_PUSH_CONSTANT: u32 = 9;
+const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+// Image dimensions
+const DIM_BUFFER: u32 = 5;
+const DIM_SUBPASS_DATA: u32 = 6;
+
+/// The number of descriptors a binding holds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DescriptorCount {
+    Fixed(u32),
+    /// The array is sized by a specialization constant, the count is only known at pipeline creation.
+    Specialized,
+    /// A runtime sized array.
+    Unbounded,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReflectedBinding {
+    pub set: u32,
+    pub binding: u32,
+    pub descriptor_type: vk::DescriptorType,
+    pub count: DescriptorCount,
+    pub stages: vk::ShaderStageFlags,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReflectedInput {
+    pub location: u32,
+    pub format: vk::Format,
+    pub size: u32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReflectedSpecializationConstant {
+    pub constant_id: u32,
+    pub size: u32,
+}
+
+/// The interface of one or more shader stages, read from SPIR-V.
+#[derive(Debug, Clone, Default)]
+pub struct ShaderReflection {
+    pub stages: vk::ShaderStageFlags,
+    /// The vertex inputs, sorted by location. Only filled for vertex shaders.
+    pub vertex_inputs: Vec<ReflectedInput>,
+    /// The descriptor bindings, sorted by set and binding.
+    pub bindings: Vec<ReflectedBinding>,
+    /// The size of the push constant block, zero when the stages have none.
+    pub push_constant_size: u32,
+    pub push_constant_stages: vk::ShaderStageFlags,
+    /// The specialization constants, sorted by constant id.
+    pub specialization_constants: Vec<ReflectedSpecializationConstant>,
+    /// The workgroup size of a compute shader, when it is declared with literals.
+    pub local_size: Option<[u32; 3]>,
+}
+
+#[derive(Clone)]
+enum SpirvType {
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Image { dim: u32, sampled: u32 },
+    Sampler,
+    SampledImage,
+    Array { element: u32, length: u32 },
+    RuntimeArray { element: u32 },
+    Struct { members: Vec<u32> },
+    Pointer { pointee: u32 },
+}
+
+#[derive(Default)]
+struct Decorations {
+    spec_id: Option<u32>,
+    buffer_block: bool,
+    array_stride: Option<u32>,
+    built_in: bool,
+    location: Option<u32>,
+    binding: Option<u32>,
+    set: Option<u32>,
+}
+
+#[derive(Default)]
+struct MemberDecorations {
+    offset: Option<u32>,
+    matrix_stride: Option<u32>,
+}
+
+/// The parsed SPIR-V instructions that are needed for reflection.
+#[derive(Default)]
+struct SpirvModule {
+    stage: vk::ShaderStageFlags,
+    local_size: Option<[u32; 3]>,
+    types: HashMap<u32, SpirvType>,
+    constants: HashMap<u32, u32>,
+    spec_constants: Vec<(u32, u32)>,
+    variables: Vec<(u32, u32, u32)>,
+    decorations: HashMap<u32, Decorations>,
+    member_decorations: HashMap<(u32, u32), MemberDecorations>,
+}
+
+impl ShaderReflection {
+    /// Reflects a SPIR-V binary.
+    ///
+    /// Fails when the code is not valid SPIR-V or contains no entry point.
+    pub fn from_spirv(code: &[u8]) -> Result<ShaderReflection> {
+        if !code.len().is_multiple_of(4) || code.len() < SPIRV_HEADER_WORDS * 4 {
+            return Err(Error::Unsupported(String::from(
+                "SPIR-V code with a size that is not a multiple of four",
+            )));
+        }
+
+        let mut words: Vec<u32> = code
+            .chunks_exact(4)
+            .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+            .collect();
+
+        if words[0] == SPIRV_MAGIC.swap_bytes() {
+            words.iter_mut().for_each(|word| *word = word.swap_bytes());
+        } else if words[0] != SPIRV_MAGIC {
+            return Err(Error::Unsupported(String::from(
+                "SPIR-V code without the SPIR-V magic number",
+            )));
+        }
+
+        SpirvModule::parse(&words)?.reflect()
+    }
+
+    /// Combines the reflection of multiple stages, bindings used by several stages get all their stages.
+    pub fn merge(mut self, other: ShaderReflection) -> ShaderReflection {
+        self.stages |= other.stages;
+
+        if self.vertex_inputs.is_empty() {
+            self.vertex_inputs = other.vertex_inputs;
+        }
+
+        for binding in other.bindings {
+            match self
+                .bindings
+                .iter_mut()
+                .find(|b| b.set == binding.set && b.binding == binding.binding)
+            {
+                Some(existing) => existing.stages |= binding.stages,
+                None => self.bindings.push(binding),
+            }
+        }
+        self.bindings.sort_by_key(|b| (b.set, b.binding));
+
+        self.push_constant_size = self.push_constant_size.max(other.push_constant_size);
+        self.push_constant_stages |= other.push_constant_stages;
+
+        for constant in other.specialization_constants {
+            if !self
+                .specialization_constants
+                .iter()
+                .any(|c| c.constant_id == constant.constant_id)
+            {
+                self.specialization_constants.push(constant);
+            }
+        }
+        self.specialization_constants
+            .sort_by_key(|constant| constant.constant_id);
+
+        self.local_size = self.local_size.or(other.local_size);
+
+        self
+    }
+
+    /// A buffer layout with the vertex inputs tightly packed in location order.
+    pub fn vertex_input_layout(&self) -> BufferLayout {
+        self.vertex_inputs
+            .iter()
+            .fold(BufferLayout::new(), |layout, input| {
+                layout.add_element(input.location as u8, input.size as usize, input.format)
+            })
+    }
+
+    /// One push constant range covering the whole block, visible to every stage that uses it.
+    pub fn push_constant_ranges(&self) -> Vec<vk::PushConstantRange> {
+        if self.push_constant_size == 0 {
+            return vec![];
+        }
+
+        vec![vk::PushConstantRange {
+            stage_flags: self.push_constant_stages,
+            offset: 0,
+            size: self.push_constant_size,
+        }]
+    }
+
+    /// Map entries for specialization data that holds the constants tightly packed in constant id order.
+    pub fn specialization_map_entries(&self) -> Vec<vk::SpecializationMapEntry> {
+        let mut offset = 0;
+
+        self.specialization_constants
+            .iter()
+            .map(|constant| {
+                let entry = vk::SpecializationMapEntry {
+                    constant_id: constant.constant_id,
+                    offset,
+                    size: constant.size as usize,
+                };
+                offset += constant.size;
+                entry
+            })
+            .collect()
+    }
+
+    /// The layout bindings of the given descriptor set.
+    ///
+    /// Arrays sized by a specialization constant or at runtime get `array_count` descriptors.
+    pub fn descriptor_set_layout_bindings(
+        &self,
+        set: u32,
+        array_count: u32,
+    ) -> Vec<vk::DescriptorSetLayoutBinding> {
+        self.bindings
+            .iter()
+            .filter(|binding| binding.set == set)
+            .map(|binding| {
+                let count = match binding.count {
+                    DescriptorCount::Fixed(count) => count,
+                    DescriptorCount::Specialized | DescriptorCount::Unbounded => array_count,
+                };
+
+                vk::DescriptorSetLayoutBinding::builder()
+                    .binding(binding.binding)
+                    .descriptor_type(binding.descriptor_type)
+                    .descriptor_count(count)
+                    .stage_flags(binding.stages)
+                    .build()
+            })
+            .collect()
+    }
+
+    /// Validates the user supplied shader io against the reflected interface.
+    ///
+    /// Every mismatch is reported in the returned `Error::ShaderValidation`.
+    pub fn validate(&self, io: &ShaderIo) -> Result<()> {
+        let mut errors = vec![];
+
+        for binding in self.bindings.iter() {
+            if binding.set == BINDLESS_TEXTURE_SET {
+                Self::validate_bindless_binding(binding, io, &mut errors);
+                continue;
+            }
+
+            let provided = match io.descriptor_set(binding.set).and_then(|descriptor_set| {
+                descriptor_set
+                    .layout_bindings
+                    .iter()
+                    .find(|provided| provided.binding == binding.binding)
+            }) {
+                Some(provided) => provided,
+                None => {
+                    errors.push(format!(
+                        "set {} binding {} is not provided",
+                        binding.set, binding.binding
+                    ));
+                    continue;
+                }
+            };
+
+            if !is_compatible_descriptor_type(binding.descriptor_type, provided.descriptor_type) {
+                errors.push(format!(
+                    "set {} binding {} is a {:?} in the shader, but a {:?} is provided",
+                    binding.set, binding.binding, binding.descriptor_type, provided.descriptor_type
+                ));
+            }
+
+            if let DescriptorCount::Fixed(count) = binding.count {
+                if provided.descriptor_count < count {
+                    errors.push(format!(
+                        "set {} binding {} holds {} descriptors in the shader, but only {} are provided",
+                        binding.set, binding.binding, count, provided.descriptor_count
+                    ));
+                }
+            }
+
+            if !provided.stage_flags.contains(binding.stages) {
+                errors.push(format!(
+                    "set {} binding {} is used by {:?}, but only visible to {:?}",
+                    binding.set, binding.binding, binding.stages, provided.stage_flags
+                ));
+            }
+        }
+
+        if let Some(layout) = io.input_buffer_layout.as_ref() {
+            for input in self.vertex_inputs.iter() {
+                match layout.get(input.location as u8) {
+                    None => errors.push(format!(
+                        "vertex input at location {} is not in the buffer layout",
+                        input.location
+                    )),
+                    Some(element) if element.format != input.format => errors.push(format!(
+                        "vertex input at location {} is {:?} in the shader, but {:?} in the buffer layout",
+                        input.location, input.format, element.format
+                    )),
+                    Some(_) => {}
+                }
+            }
+        }
+
+        if self.push_constant_size > 0 {
+            let provided_size = io
+                .push_constant_ranges
+                .iter()
+                .map(|range| range.offset + range.size)
+                .max()
+                .unwrap_or(0);
+            let provided_stages = io
+                .push_constant_ranges
+                .iter()
+                .fold(vk::ShaderStageFlags::empty(), |stages, range| {
+                    stages | range.stage_flags
+                });
+
+            if provided_size < self.push_constant_size {
+                errors.push(format!(
+                    "the push constant block is {} bytes, but the ranges cover {} bytes",
+                    self.push_constant_size, provided_size
+                ));
+            }
+
+            if !provided_stages.contains(self.push_constant_stages) {
+                errors.push(format!(
+                    "push constants are used by {:?}, but only visible to {:?}",
+                    self.push_constant_stages, provided_stages
+                ));
+            }
+        }
+
+        let specialization_data_size = io.specialization_data_ref().len();
+        let specialized_size: usize = io
+            .specialization_constants
+            .iter()
+            .map(|entry| entry.size)
+            .sum();
+
+        // The entries are packed in constant id order, a constant missing from the shaders shifts the others.
+        if io.specialization_constant_data.is_some() && specialized_size != specialization_data_size
+        {
+            errors.push(format!(
+                "the shaders declare {} bytes of specialization constants, but {} bytes of data are provided",
+                specialized_size, specialization_data_size
+            ));
+        }
+
+        for entry in io.specialization_constants.iter() {
+            if entry.offset as usize + entry.size > specialization_data_size {
+                errors.push(format!(
+                    "specialization constant {} lies outside the {} bytes of specialization data",
+                    entry.constant_id, specialization_data_size
+                ));
+            }
+
+            match self
+                .specialization_constants
+                .iter()
+                .find(|constant| constant.constant_id == entry.constant_id)
+            {
+                None => errors.push(format!(
+                    "specialization constant {} does not exist in the shader",
+                    entry.constant_id
+                )),
+                Some(constant) if constant.size as usize != entry.size => errors.push(format!(
+                    "specialization constant {} is {} bytes in the shader, but {} bytes are provided",
+                    entry.constant_id, constant.size, entry.size
+                )),
+                Some(_) => {}
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::ShaderValidation(errors.join(", ")))
+        }
+    }
+
+    /// Validates a binding of the bindless texture set against the table bound to the shader io.
+    fn validate_bindless_binding(
+        binding: &ReflectedBinding,
+        io: &ShaderIo,
+        errors: &mut Vec<String>,
+    ) {
+        let bindless_textures = match io.bindless_textures.as_ref() {
+            Some(bindless_textures) => bindless_textures,
+            None => {
+                errors.push(format!(
+                    "set {} binding {} is used, but no bindless texture table is provided",
+                    binding.set, binding.binding
+                ));
+                return;
+            }
+        };
+
+        if binding.binding != BINDLESS_TEXTURE_BINDING
+            || binding.descriptor_type != vk::DescriptorType::SAMPLED_IMAGE
+        {
+            errors.push(format!(
+                "set {} binding {} is a {:?}, but the bindless texture set only has sampled images at binding {}",
+                binding.set, binding.binding, binding.descriptor_type, BINDLESS_TEXTURE_BINDING
+            ));
+        }
+
+        // A fixed array must span the whole table, the texture ids index into all of it.
+        if let DescriptorCount::Fixed(count) = binding.count {
+            if count != bindless_textures.capacity {
+                errors.push(format!(
+                    "the bindless texture array holds {} textures in the shader, but the table {}, \
+                     declare it as a runtime array or size it with the table capacity",
+                    count, bindless_textures.capacity
+                ));
+            }
+        }
+    }
+}
+
+impl SpirvModule {
+    fn parse(words: &[u32]) -> Result<SpirvModule> {
+        let mut module = SpirvModule::default();
+        let mut has_entry_point = false;
+        let mut index = SPIRV_HEADER_WORDS;
+
+        while index < words.len() {
+            let word_count = (words[index] >> 16) as usize;
+            let opcode = words[index] & 0xFFFF;
+
+            if word_count == 0 || index + word_count > words.len() {
+                return Err(Error::Unsupported(String::from(
+                    "SPIR-V code with a truncated instruction",
+                )));
+            }
+
+            let operands = &words[index + 1..index + word_count];
+            index += word_count;
+
+            match opcode {
+                OP_ENTRY_POINT if !has_entry_point => {
+                    module.stage = execution_model_stage(operands[0])?;
+                    has_entry_point = true;
+                }
+                OP_EXECUTION_MODE if operands[1] == EXECUTION_MODE_LOCAL_SIZE => {
+                    module.local_size = Some([operands[2], operands[3], operands[4]]);
+                }
+                OP_TYPE_BOOL => {
+                    module.types.insert(operands[0], SpirvType::Bool);
+                }
+                OP_TYPE_INT => {
+                    module.types.insert(
+                        operands[0],
+                        SpirvType::Int {
+                            width: operands[1],
+                            signed: operands[2] == 1,
+                        },
+                    );
+                }
+                OP_TYPE_FLOAT => {
+                    module
+                        .types
+                        .insert(operands[0], SpirvType::Float { width: operands[1] });
+                }
+                OP_TYPE_VECTOR => {
+                    module.types.insert(
+                        operands[0],
+                        SpirvType::Vector {
+                            component: operands[1],
+                            count: operands[2],
+                        },
+                    );
+                }
+                OP_TYPE_MATRIX => {
+                    module.types.insert(
+                        operands[0],
+                        SpirvType::Matrix {
+                            column: operands[1],
+                            count: operands[2],
+                        },
+                    );
+                }
+                OP_TYPE_IMAGE => {
+                    module.types.insert(
+                        operands[0],
+                        SpirvType::Image {
+                            dim: operands[2],
+                            sampled: operands[6],
+                        },
+                    );
+                }
+                OP_TYPE_SAMPLER => {
+                    module.types.insert(operands[0], SpirvType::Sampler);
+                }
+                OP_TYPE_SAMPLED_IMAGE => {
+                    module.types.insert(operands[0], SpirvType::SampledImage);
+                }
+                OP_TYPE_ARRAY => {
+                    module.types.insert(
+                        operands[0],
+                        SpirvType::Array {
+                            element: operands[1],
+                            length: operands[2],
+                        },
+                    );
+                }
+                OP_TYPE_RUNTIME_ARRAY => {
+                    module.types.insert(
+                        operands[0],
+                        SpirvType::RuntimeArray {
+                            element: operands[1],
+                        },
+                    );
+                }
+                OP_TYPE_STRUCT => {
+                    module.types.insert(
+                        operands[0],
+                        SpirvType::Struct {
+                            members: operands[1..].to_vec(),
+                        },
+                    );
+                }
+                OP_TYPE_POINTER => {
+                    module.types.insert(
+                        operands[0],
+                        SpirvType::Pointer {
+                            pointee: operands[2],
+                        },
+                    );
+                }
+                OP_CONSTANT => {
+                    module.constants.insert(operands[1], operands[2]);
+                }
+                OP_SPEC_CONSTANT | OP_SPEC_CONSTANT_TRUE | OP_SPEC_CONSTANT_FALSE => {
+                    module.spec_constants.push((operands[0], operands[1]));
+                }
+                OP_VARIABLE => {
+                    module
+                        .variables
+                        .push((operands[0], operands[1], operands[2]));
+                }
+                OP_DECORATE => {
+                    let decorations = module.decorations.entry(operands[0]).or_default();
+                    let literal = operands.get(2).copied();
+
+                    match operands[1] {
+                        DECORATION_SPEC_ID => decorations.spec_id = literal,
+                        DECORATION_BUFFER_BLOCK => decorations.buffer_block = true,
+                        DECORATION_ARRAY_STRIDE => decorations.array_stride = literal,
+                        DECORATION_BUILT_IN => decorations.built_in = true,
+                        DECORATION_LOCATION => decorations.location = literal,
+                        DECORATION_BINDING => decorations.binding = literal,
+                        DECORATION_DESCRIPTOR_SET => decorations.set = literal,
+                        _ => {}
+                    }
+                }
+                OP_MEMBER_DECORATE => {
+                    let decorations = module
+                        .member_decorations
+                        .entry((operands[0], operands[1]))
+                        .or_default();
+                    let literal = operands.get(3).copied();
+
+                    match operands[2] {
+                        DECORATION_OFFSET => decorations.offset = literal,
+                        DECORATION_MATRIX_STRIDE => decorations.matrix_stride = literal,
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if !has_entry_point {
+            return Err(Error::Unsupported(String::from(
+                "SPIR-V code without an entry point",
+            )));
+        }
+
+        Ok(module)
+    }
+
+    fn reflect(&self) -> Result<ShaderReflection> {
+        let mut reflection = ShaderReflection {
+            stages: self.stage,
+            local_size: self.local_size,
+            ..Default::default()
+        };
+
+        for &(pointer_type, id, storage_class) in self.variables.iter() {
+            let decorations = self.decorations.get(&id);
+            let pointee = match self.types.get(&pointer_type) {
+                Some(SpirvType::Pointer { pointee }) => *pointee,
+                _ => continue,
+            };
+
+            match storage_class {
+                STORAGE_INPUT if self.stage == vk::ShaderStageFlags::VERTEX => {
+                    let decorations = match decorations {
+                        Some(decorations) if !decorations.built_in => decorations,
+                        _ => continue,
+                    };
+
+                    if let Some(location) = decorations.location {
+                        self.reflect_input(location, pointee, &mut reflection.vertex_inputs)?;
+                    }
+                }
+                STORAGE_UNIFORM_CONSTANT | STORAGE_UNIFORM | STORAGE_STORAGE_BUFFER => {
+                    let (set, binding) = match decorations {
+                        Some(Decorations {
+                            binding: Some(binding),
+                            set,
+                            ..
+                        }) => (set.unwrap_or(0), *binding),
+                        _ => continue,
+                    };
+
+                    let (descriptor_type, count) =
+                        self.descriptor_type(pointee, storage_class, DescriptorCount::Fixed(1))?;
+
+                    reflection.bindings.push(ReflectedBinding {
+                        set,
+                        binding,
+                        descriptor_type,
+                        count,
+                        stages: self.stage,
+                    });
+                }
+                STORAGE_PUSH_CONSTANT => {
+                    reflection.push_constant_size = self.type_size(pointee, None);
+                    reflection.push_constant_stages = self.stage;
+                }
+                _ => {}
+            }
+        }
+
+        for &(type_id, id) in self.spec_constants.iter() {
+            if let Some(constant_id) = self.decorations.get(&id).and_then(|d| d.spec_id) {
+                reflection
+                    .specialization_constants
+                    .push(ReflectedSpecializationConstant {
+                        constant_id,
+                        size: self.type_size(type_id, None),
+                    });
+            }
+        }
+
+        reflection.vertex_inputs.sort_by_key(|input| input.location);
+        reflection.bindings.sort_by_key(|b| (b.set, b.binding));
+        reflection
+            .specialization_constants
+            .sort_by_key(|constant| constant.constant_id);
+
+        Ok(reflection)
+    }
+
+    /// Adds the input, matrices and arrays take one location per column or element.
+    fn reflect_input(
+        &self,
+        location: u32,
+        type_id: u32,
+        inputs: &mut Vec<ReflectedInput>,
+    ) -> Result<u32> {
+        match self.types.get(&type_id) {
+            Some(SpirvType::Matrix { column, count }) => {
+                for i in 0..*count {
+                    self.reflect_input(location + i, *column, inputs)?;
+                }
+                Ok(*count)
+            }
+            Some(SpirvType::Array { element, length }) => {
+                let length = self.constants.get(length).copied().unwrap_or(1);
+                let mut used = 0;
+                for _ in 0..length {
+                    used += self.reflect_input(location + used, *element, inputs)?;
+                }
+                Ok(used)
+            }
+            _ => {
+                inputs.push(ReflectedInput {
+                    location,
+                    format: self.vertex_format(type_id)?,
+                    size: self.type_size(type_id, None),
+                });
+                Ok(1)
+            }
+        }
+    }
+
+    fn vertex_format(&self, type_id: u32) -> Result<vk::Format> {
+        let (component, count) = match self.types.get(&type_id) {
+            Some(SpirvType::Vector { component, count }) => (*component, *count),
+            _ => (type_id, 1),
+        };
+
+        let format = match (self.types.get(&component), count) {
+            (Some(SpirvType::Float { width: 32 }), 1) => vk::Format::R32_SFLOAT,
+            (Some(SpirvType::Float { width: 32 }), 2) => vk::Format::R32G32_SFLOAT,
+            (Some(SpirvType::Float { width: 32 }), 3) => vk::Format::R32G32B32_SFLOAT,
+            (Some(SpirvType::Float { width: 32 }), 4) => vk::Format::R32G32B32A32_SFLOAT,
+            (Some(SpirvType::Int { width: 32, signed }), count) => match (signed, count) {
+                (true, 1) => vk::Format::R32_SINT,
+                (true, 2) => vk::Format::R32G32_SINT,
+                (true, 3) => vk::Format::R32G32B32_SINT,
+                (true, 4) => vk::Format::R32G32B32A32_SINT,
+                (false, 1) => vk::Format::R32_UINT,
+                (false, 2) => vk::Format::R32G32_UINT,
+                (false, 3) => vk::Format::R32G32B32_UINT,
+                (false, 4) => vk::Format::R32G32B32A32_UINT,
+                _ => vk::Format::UNDEFINED,
+            },
+            _ => vk::Format::UNDEFINED,
+        };
+
+        if format == vk::Format::UNDEFINED {
+            return Err(Error::Unsupported(String::from(
+                "vertex input type, only 32 bit scalars and vectors are supported",
+            )));
+        }
+
+        Ok(format)
+    }
+
+    fn descriptor_type(
+        &self,
+        type_id: u32,
+        storage_class: u32,
+        count: DescriptorCount,
+    ) -> Result<(vk::DescriptorType, DescriptorCount)> {
+        let descriptor_type = match self.types.get(&type_id) {
+            Some(SpirvType::Array { element, length }) => {
+                let count = match self.constants.get(length) {
+                    Some(&length) => DescriptorCount::Fixed(length),
+                    None => DescriptorCount::Specialized,
+                };
+                return self.descriptor_type(*element, storage_class, count);
+            }
+            Some(SpirvType::RuntimeArray { element }) => {
+                return self.descriptor_type(*element, storage_class, DescriptorCount::Unbounded);
+            }
+            Some(SpirvType::Sampler) => vk::DescriptorType::SAMPLER,
+            Some(SpirvType::SampledImage) => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            Some(SpirvType::Image { dim, sampled }) => match (*dim, *sampled) {
+                (DIM_BUFFER, 2) => vk::DescriptorType::STORAGE_TEXEL_BUFFER,
+                (DIM_BUFFER, _) => vk::DescriptorType::UNIFORM_TEXEL_BUFFER,
+                (DIM_SUBPASS_DATA, _) => vk::DescriptorType::INPUT_ATTACHMENT,
+                (_, 2) => vk::DescriptorType::STORAGE_IMAGE,
+                _ => vk::DescriptorType::SAMPLED_IMAGE,
+            },
+            Some(SpirvType::Struct { .. }) => {
+                let decorations = self.decorations.get(&type_id);
+
+                if storage_class == STORAGE_STORAGE_BUFFER
+                    || decorations.is_some_and(|d| d.buffer_block)
+                {
+                    vk::DescriptorType::STORAGE_BUFFER
+                } else {
+                    vk::DescriptorType::UNIFORM_BUFFER
+                }
+            }
+            _ => {
+                return Err(Error::Unsupported(String::from(
+                    "descriptor type in SPIR-V reflection",
+                )))
+            }
+        };
+
+        Ok((descriptor_type, count))
+    }
+
+    /// The size in bytes of a type, `matrix_stride` comes from the struct member that holds the matrix.
+    fn type_size(&self, type_id: u32, matrix_stride: Option<u32>) -> u32 {
+        match self.types.get(&type_id) {
+            Some(SpirvType::Bool) => 4,
+            Some(SpirvType::Int { width, .. }) | Some(SpirvType::Float { width }) => width / 8,
+            Some(SpirvType::Vector { component, count }) => {
+                self.type_size(*component, None) * count
+            }
+            Some(SpirvType::Matrix { column, count }) => {
+                matrix_stride.unwrap_or_else(|| self.type_size(*column, None)) * count
+            }
+            Some(SpirvType::Array { element, length }) => {
+                let length = self.constants.get(length).copied().unwrap_or(1);
+                let stride = self
+                    .decorations
+                    .get(&type_id)
+                    .and_then(|d| d.array_stride)
+                    .unwrap_or_else(|| self.type_size(*element, None));
+
+                stride * length
+            }
+            Some(SpirvType::Struct { members }) => members
+                .iter()
+                .enumerate()
+                .map(|(index, &member)| {
+                    let decorations = self.member_decorations.get(&(type_id, index as u32));
+                    let offset = decorations.and_then(|d| d.offset).unwrap_or(0);
+                    let matrix_stride = decorations.and_then(|d| d.matrix_stride);
+
+                    offset + self.type_size(member, matrix_stride)
+                })
+                .max()
+                .unwrap_or(0),
+            _ => 0,
+        }
+    }
+}
+
+fn execution_model_stage(execution_model: u32) -> Result<vk::ShaderStageFlags> {
+    Ok(match execution_model {
+        0 => vk::ShaderStageFlags::VERTEX,
+        1 => vk::ShaderStageFlags::TESSELLATION_CONTROL,
+        2 => vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+        3 => vk::ShaderStageFlags::GEOMETRY,
+        4 => vk::ShaderStageFlags::FRAGMENT,
+        5 => vk::ShaderStageFlags::COMPUTE,
+        _ => {
+            return Err(Error::Unsupported(format!(
+                "SPIR-V execution model {}",
+                execution_model
+            )))
+        }
+    })
+}
+
+/// SPIR-V does not tell dynamic buffers apart, a buffer block accepts both the plain and the dynamic type.
+fn is_compatible_descriptor_type(
+    reflected: vk::DescriptorType,
+    provided: vk::DescriptorType,
+) -> bool {
+    match (reflected, provided) {
+        (vk::DescriptorType::UNIFORM_BUFFER, vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
+        | (vk::DescriptorType::STORAGE_BUFFER, vk::DescriptorType::STORAGE_BUFFER_DYNAMIC) => true,
+        _ => reflected == provided,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::{
+        shader::reflection::is_compatible_descriptor_type, DescriptorCount, ShaderReflection,
+    };
+    use ash::vk;
+
+    fn instruction(opcode: u32, operands: &[u32]) -> Vec<u32> {
+        let mut words = vec![((operands.len() as u32 + 1) << 16) | opcode];
+        words.extend_from_slice(operands);
+        words
+    }
+
+    fn to_bytes(instructions: &[Vec<u32>]) -> Vec<u8> {
+        let mut words = vec![0x0723_0203, 0x0001_0000, 0, 100, 0];
+        instructions
+            .iter()
+            .for_each(|instruction| words.extend_from_slice(instruction));
+        words.iter().flat_map(|word| word.to_le_bytes()).collect()
+    }
+
+    /// layout(location = 1) in vec3 position;
+    /// layout(set = 0, binding = 2) uniform Camera { mat4 view; };
+    /// layout(push_constant) uniform Push { vec4 color; int id; };
+    /// layout(constant_id = 3) const int LENGTH = 4;
+    /// layout(set = 0, binding = 1) uniform texture2D textures[LENGTH];
+    fn vertex_shader() -> Vec<u8> {
+        to_bytes(&[
+            instruction(15, &[0, 1, 0x6e69_616d, 0]),
+            instruction(71, &[10, 30, 1]),
+            instruction(71, &[11, 34, 0]),
+            instruction(71, &[11, 33, 2]),
+            instruction(72, &[7, 0, 35, 0]),
+            instruction(72, &[7, 0, 7, 16]),
+            instruction(72, &[8, 0, 35, 0]),
+            instruction(72, &[8, 1, 35, 16]),
+            instruction(71, &[13, 1, 3]),
+            instruction(71, &[16, 34, 0]),
+            instruction(71, &[16, 33, 1]),
+            instruction(22, &[2, 32]),
+            instruction(21, &[3, 32, 1]),
+            instruction(23, &[4, 2, 3]),
+            instruction(23, &[5, 2, 4]),
+            instruction(24, &[6, 5, 4]),
+            instruction(30, &[7, 6]),
+            instruction(30, &[8, 5, 3]),
+            instruction(32, &[9, 1, 4]),
+            instruction(59, &[9, 10, 1]),
+            instruction(32, &[17, 2, 7]),
+            instruction(59, &[17, 11, 2]),
+            instruction(32, &[18, 9, 8]),
+            instruction(59, &[18, 12, 9]),
+            instruction(50, &[3, 13, 4]),
+            instruction(25, &[14, 2, 1, 0, 0, 0, 1, 0]),
+            instruction(28, &[15, 14, 13]),
+            instruction(32, &[19, 0, 15]),
+            instruction(59, &[19, 16, 0]),
+        ])
+    }
+
+    #[test]
+    fn reflect_vertex_shader() {
+        let reflection = ShaderReflection::from_spirv(&vertex_shader()).unwrap();
+
+        assert_eq!(reflection.stages, vk::ShaderStageFlags::VERTEX);
+
+        assert_eq!(reflection.vertex_inputs.len(), 1);
+        assert_eq!(reflection.vertex_inputs[0].location, 1);
+        assert_eq!(
+            reflection.vertex_inputs[0].format,
+            vk::Format::R32G32B32_SFLOAT
+        );
+
+        assert_eq!(reflection.bindings.len(), 2);
+        assert_eq!(reflection.bindings[0].binding, 1);
+        assert_eq!(
+            reflection.bindings[0].descriptor_type,
+            vk::DescriptorType::SAMPLED_IMAGE
+        );
+        assert_eq!(reflection.bindings[0].count, DescriptorCount::Specialized);
+        assert_eq!(reflection.bindings[1].binding, 2);
+        assert_eq!(
+            reflection.bindings[1].descriptor_type,
+            vk::DescriptorType::UNIFORM_BUFFER
+        );
+        assert_eq!(reflection.bindings[1].count, DescriptorCount::Fixed(1));
+
+        assert_eq!(reflection.push_constant_size, 20);
+        assert_eq!(reflection.specialization_constants.len(), 1);
+        assert_eq!(reflection.specialization_constants[0].constant_id, 3);
+        assert_eq!(reflection.specialization_constants[0].size, 4);
+    }
+
+    #[test]
+    fn dynamic_buffers_match_buffer_blocks() {
+        assert!(is_compatible_descriptor_type(
+            vk::DescriptorType::UNIFORM_BUFFER,
+            vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC
+        ));
+        assert!(is_compatible_descriptor_type(
+            vk::DescriptorType::STORAGE_BUFFER,
+            vk::DescriptorType::STORAGE_BUFFER_DYNAMIC
+        ));
+        assert!(!is_compatible_descriptor_type(
+            vk::DescriptorType::UNIFORM_BUFFER,
+            vk::DescriptorType::STORAGE_BUFFER
+        ));
+    }
+
+    #[test]
+    fn reflect_compute_shader() {
+        let code = to_bytes(&[
+            instruction(15, &[5, 1, 0x6e69_616d, 0]),
+            instruction(16, &[1, 17, 64, 1, 1]),
+            instruction(71, &[4, 3]),
+            instruction(72, &[4, 0, 35, 0]),
+            instruction(71, &[3, 6, 4]),
+            instruction(71, &[6, 34, 0]),
+            instruction(71, &[6, 33, 0]),
+            instruction(22, &[2, 32]),
+            instruction(29, &[3, 2]),
+            instruction(30, &[4, 3]),
+            instruction(32, &[5, 2, 4]),
+            instruction(59, &[5, 6, 2]),
+        ]);
+
+        let reflection = ShaderReflection::from_spirv(&code).unwrap();
+
+        assert_eq!(reflection.stages, vk::ShaderStageFlags::COMPUTE);
+        assert_eq!(reflection.local_size, Some([64, 1, 1]));
+        assert_eq!(reflection.bindings.len(), 1);
+        assert_eq!(
+            reflection.bindings[0].descriptor_type,
+            vk::DescriptorType::STORAGE_BUFFER
+        );
+    }
+
+    #[test]
+    fn merge_combines_stages() {
+        let vertex = ShaderReflection::from_spirv(&vertex_shader()).unwrap();
+        let mut fragment = vertex.clone();
+        fragment.stages = vk::ShaderStageFlags::FRAGMENT;
+        fragment.vertex_inputs.clear();
+        fragment
+            .bindings
+            .iter_mut()
+            .for_each(|binding| binding.stages = vk::ShaderStageFlags::FRAGMENT);
+
+        let merged = vertex.merge(fragment);
+
+        assert_eq!(merged.bindings.len(), 2);
+        assert_eq!(
+            merged.bindings[1].stages,
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT
+        );
+        assert_eq!(merged.vertex_inputs.len(), 1);
+    }
+
+    #[test]
+    fn reject_invalid_code() {
+        assert!(ShaderReflection::from_spirv(&[0u8; 7]).is_err());
+        assert!(ShaderReflection::from_spirv(&[0u8; 20]).is_err());
+    }
+}
@@ -1,56 +1,151 @@
 use crate::{
     engine::VulkanApplication,
     vulkan::{
-        shader::{shader_flags::ShaderFlags, shader_set::ShaderSet},
-        ShaderIo,
+        shader::{reflection::ShaderReflection, shader_flags::ShaderFlags, shader_set::ShaderSet},
+        ShaderIOBuilder, ShaderIo, ShaderSource,
     },
+    Result,
 };
-use std::path::Path;
+use std::path::PathBuf;
 
 pub struct ShaderBuilder<'a> {
-    shader_io: Option<ShaderIo>,
+    shader_io: Option<(ShaderIOBuilder, usize)>,
+    debug_name: Option<String>,
 
-    vertex_shader: &'static str,
-    fragment_shader: &'static str,
+    vertex_shader: ShaderSource,
+    fragment_shader: ShaderSource,
 
     application: &'a VulkanApplication,
 }
 
 impl<'a> ShaderBuilder<'a> {
-    /// Creates a new shader builder.
+    /// Creates a new shader builder for precompiled SPIR-V files.
     pub fn builder(
         application: &'a VulkanApplication,
-        vertex_shader: &'static str,
-        fragment_shader: &'static str,
+        vertex_shader: impl Into<PathBuf>,
+        fragment_shader: impl Into<PathBuf>,
     ) -> ShaderBuilder<'a> {
         ShaderBuilder::<'a> {
-            vertex_shader,
-            fragment_shader,
+            vertex_shader: ShaderSource::Spirv(vertex_shader.into()),
+            fragment_shader: ShaderSource::Spirv(fragment_shader.into()),
             application,
             shader_io: None,
+            debug_name: None,
         }
     }
 
-    pub fn with_descriptors(mut self, descriptors: ShaderIo) -> ShaderBuilder<'a> {
-        self.shader_io = Some(descriptors);
+    /// Creates a new shader builder for GLSL files that are compiled when the shader is built.
+    pub fn glsl(
+        application: &'a VulkanApplication,
+        vertex_shader: impl Into<PathBuf>,
+        fragment_shader: impl Into<PathBuf>,
+    ) -> ShaderBuilder<'a> {
+        ShaderBuilder::<'a> {
+            vertex_shader: ShaderSource::Glsl {
+                path: vertex_shader.into(),
+                defines: vec![],
+            },
+            fragment_shader: ShaderSource::Glsl {
+                path: fragment_shader.into(),
+                defines: vec![],
+            },
+            application,
+            shader_io: None,
+            debug_name: None,
+        }
+    }
+
+    /// Names the shader modules in validation messages and captures.
+    pub fn with_debug_name(mut self, name: &str) -> ShaderBuilder<'a> {
+        self.debug_name = Some(name.to_string());
+        self
+    }
+
+    /// Adds a preprocessor define to both GLSL stages, it is ignored for SPIR-V.
+    pub fn with_define(mut self, name: &str, value: Option<&str>) -> ShaderBuilder<'a> {
+        for source in [&mut self.vertex_shader, &mut self.fragment_shader].iter_mut() {
+            if let ShaderSource::Glsl { defines, .. } = source {
+                defines.push((name.to_string(), value.map(str::to_string)));
+            }
+        }
+        self
+    }
+
+    /// The resources of the shader, they are built into the shader io once the shaders are reflected.
+    ///
+    /// # Arguments
+    /// - `frames`: The number of frames in flight, per frame sets are allocated this many times.
+    pub fn with_descriptors(
+        mut self,
+        descriptors: ShaderIOBuilder,
+        frames: usize,
+    ) -> ShaderBuilder<'a> {
+        self.shader_io = Some((descriptors, frames));
         self
     }
 
     /// Build shader.
-    pub fn build(self) -> ShaderSet {
-        let vertex_shader_code = ShaderSet::read_shader_code(Path::new(self.vertex_shader));
-        let vertex_shader_module =
-            ShaderSet::create_shader_module(&self.application.device, vertex_shader_code);
+    ///
+    /// The SPIR-V of both stages is reflected, the stages of the descriptor set layout bindings and the
+    /// specialization constant entries are derived from it, as are the input buffer layout and push
+    /// constant ranges when they were not supplied.
+    /// Fails when a shader file can not be read or compiled, the shader module can not be created
+    /// or the supplied shader io does not match the shaders.
+    pub fn build(self) -> Result<ShaderSet> {
+        let vertex_shader_code = self.vertex_shader.load()?;
+        let fragment_shader_code = self.fragment_shader.load()?;
+
+        let reflection = ShaderReflection::from_spirv(&vertex_shader_code)?
+            .merge(ShaderReflection::from_spirv(&fragment_shader_code)?);
 
-        let fragment_shader_code = ShaderSet::read_shader_code(Path::new(self.fragment_shader));
+        let (descriptors, frames) = self.shader_io.expect("Shader builder without descriptors!");
+        let mut shader_io = descriptors.build(&self.application.device, &reflection, frames);
+        Self::derive_missing_io(&reflection, &mut shader_io);
+        reflection.validate(&shader_io)?;
+
+        let vertex_shader_module =
+            ShaderSet::create_shader_module(&self.application.device, vertex_shader_code)?;
         let fragment_shader_module =
-            ShaderSet::create_shader_module(&self.application.device, fragment_shader_code);
+            ShaderSet::create_shader_module(&self.application.device, fragment_shader_code)?;
 
-        ShaderSet::new(
+        let mut shader_set = ShaderSet::new(
             vertex_shader_module,
             fragment_shader_module,
-            self.shader_io.unwrap(),
+            self.vertex_shader,
+            self.fragment_shader,
+            shader_io,
             ShaderFlags::empty(),
-        )
+        );
+
+        if let Some(name) = self.debug_name.as_ref() {
+            shader_set.set_debug_name(&self.application.device, name);
+        }
+
+        Ok(shader_set)
+    }
+
+    /// Fills the parts of the shader io that were not supplied from the reflection.
+    fn derive_missing_io(reflection: &ShaderReflection, shader_io: &mut ShaderIo) {
+        if shader_io.input_buffer_layout.is_none() {
+            shader_io.input_buffer_layout = Some(reflection.vertex_input_layout());
+        }
+
+        Self::derive_missing_constants(reflection, shader_io);
+    }
+
+    /// Fills the push constant ranges that were not supplied and the specialization constant entries,
+    /// these are shared by graphics and compute shaders.
+    pub(crate) fn derive_missing_constants(
+        reflection: &ShaderReflection,
+        shader_io: &mut ShaderIo,
+    ) {
+        if shader_io.push_constant_ranges.is_empty() {
+            shader_io.push_constant_ranges = reflection.push_constant_ranges();
+        }
+
+        // Without data the shaders keep their default constant values.
+        if shader_io.specialization_constant_data.is_some() {
+            shader_io.specialization_constants = reflection.specialization_map_entries();
+        }
     }
 }
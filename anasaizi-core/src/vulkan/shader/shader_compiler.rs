@@ -0,0 +1,150 @@
+use crate::{Error, Result};
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// Where the code of a shader stage comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShaderSource {
+    /// Precompiled SPIR-V, for example from the `glslc` build script.
+    Spirv(PathBuf),
+    /// GLSL that is compiled at runtime with the given preprocessor defines.
+    Glsl {
+        path: PathBuf,
+        defines: Vec<(String, Option<String>)>,
+    },
+}
+
+impl ShaderSource {
+    /// The file the shader is read from.
+    pub fn path(&self) -> &Path {
+        match self {
+            ShaderSource::Spirv(path) => path,
+            ShaderSource::Glsl { path, .. } => path,
+        }
+    }
+
+    /// Returns whether the shader is read from the given file.
+    pub fn is_read_from(&self, path: &Path) -> bool {
+        match (fs::canonicalize(self.path()), fs::canonicalize(path)) {
+            (Ok(source), Ok(path)) => source == path,
+            _ => self.path() == path,
+        }
+    }
+
+    /// Reads the SPIR-V, GLSL is compiled first.
+    ///
+    /// Fails when the file can not be read or the GLSL does not compile.
+    pub fn load(&self) -> Result<Vec<u8>> {
+        match self {
+            ShaderSource::Spirv(path) => read_file(path),
+            ShaderSource::Glsl { path, defines } => ShaderCompiler::new()?.compile(path, defines),
+        }
+    }
+}
+
+/// Compiles GLSL to SPIR-V in process.
+pub struct ShaderCompiler {
+    compiler: shaderc::Compiler,
+}
+
+impl ShaderCompiler {
+    pub fn new() -> Result<ShaderCompiler> {
+        let compiler = shaderc::Compiler::new().ok_or_else(|| {
+            Error::Unsupported(String::from("shader compilation, shaderc is not available"))
+        })?;
+
+        Ok(ShaderCompiler { compiler })
+    }
+
+    /// Compiles the GLSL file, the stage is taken from the file extension.
+    ///
+    /// # Arguments
+    /// - `path`: The GLSL file, ending with `.vert`, `.frag` or `.comp`.
+    /// - `defines`: Preprocessor defines, a `None` value defines the name without a value.
+    pub fn compile(
+        &mut self,
+        path: &Path,
+        defines: &[(String, Option<String>)],
+    ) -> Result<Vec<u8>> {
+        let kind = Self::shader_kind(path)?;
+        let source = String::from_utf8(read_file(path)?).map_err(|error| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("Shader {:?} is not UTF-8: {}", path, error),
+            )
+        })?;
+
+        let mut options = shaderc::CompileOptions::new().ok_or_else(|| {
+            Error::Unsupported(String::from("shader compilation, shaderc is not available"))
+        })?;
+
+        for (name, value) in defines {
+            options.add_macro_definition(name, value.as_deref());
+        }
+
+        let artifact = self
+            .compiler
+            .compile_into_spirv(
+                &source,
+                kind,
+                &path.to_string_lossy(),
+                "main",
+                Some(&options),
+            )
+            .map_err(|error| Error::ShaderCompilation(error.to_string()))?;
+
+        if artifact.get_num_warnings() > 0 {
+            log::warn!("{}", artifact.get_warning_messages());
+        }
+
+        Ok(artifact.as_binary_u8().to_vec())
+    }
+
+    fn shader_kind(path: &Path) -> Result<shaderc::ShaderKind> {
+        let extension = path.extension().and_then(|extension| extension.to_str());
+
+        Ok(match extension {
+            Some("vert") => shaderc::ShaderKind::Vertex,
+            Some("frag") => shaderc::ShaderKind::Fragment,
+            Some("comp") => shaderc::ShaderKind::Compute,
+            Some("geom") => shaderc::ShaderKind::Geometry,
+            Some("tesc") => shaderc::ShaderKind::TessControl,
+            Some("tese") => shaderc::ShaderKind::TessEvaluation,
+            _ => {
+                return Err(Error::Unsupported(format!(
+                    "shader file {:?}, the extension does not name a stage",
+                    path
+                )))
+            }
+        })
+    }
+}
+
+fn read_file(path: &Path) -> Result<Vec<u8>> {
+    fs::read(path).map_err(|error| {
+        io::Error::new(
+            error.kind(),
+            format!("Failed to read shader file at {:?}: {}", path, error),
+        )
+        .into()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::ShaderSource;
+    use std::path::{Path, PathBuf};
+
+    #[test]
+    fn source_is_read_from_its_path() {
+        let source = ShaderSource::Glsl {
+            path: PathBuf::from("assets/shaders/missing.vert"),
+            defines: vec![],
+        };
+
+        assert!(source.is_read_from(Path::new("assets/shaders/missing.vert")));
+        assert!(!source.is_read_from(Path::new("assets/shaders/missing.frag")));
+    }
+}
@@ -68,6 +68,9 @@ impl From<ShaderFlags> for GltfPBRShaderConstants {
         if flags.contains(ShaderFlags::USE_IBL) {
             constants.use_ibl = 1;
         }
+        if flags.contains(ShaderFlags::USE_TEX_LOD) {
+            constants.use_tex_lod = 1;
+        }
 
         constants
     }
@@ -2,65 +2,116 @@ use crate::{
     engine::{
         image::Texture, BufferLayout, GltfPBRShaderConstants, RenderContext, UniformObjectTemplate,
     },
-    vulkan::{DescriptorPool, DescriptorSet, UniformBuffer, VkLogicalDevice},
+    vulkan::{
+        BindlessTextures, DescriptorPool, DescriptorSet, GPUBuffer, ShaderReflection,
+        UniformBuffer, VkLogicalDevice, BINDLESS_TEXTURE_SET,
+    },
 };
 use ash::{
     version::DeviceV1_0,
     vk,
-    vk::{PipelineLayout, PipelineVertexInputStateCreateInfo, PushConstantRange, ShaderStageFlags},
+    vk::{PipelineLayout, PipelineVertexInputStateCreateInfo, PushConstantRange},
 };
+use std::collections::HashMap;
+
+/// How often the descriptors of a set change, it decides how many sets are allocated and when they are bound.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DescriptorFrequency {
+    /// One set per frame in flight, bound with the pipeline for the frame that is rendered.
+    Frame,
+    /// One set for all frames, bound with the pipeline.
+    /// For descriptors that do not change while frames are in flight, such as textures and samplers.
+    Material,
+    /// Only the layout is created, every object provides its own set with `ObjectDescriptorSet`.
+    Object,
+}
+
+/// Where the buffer of a buffer write comes from.
+enum BufferSource {
+    /// The uniform buffer with this index, it has a buffer per frame.
+    Uniform(usize),
+    /// Buffers supplied by the caller, one per frame or one for all frames.
+    Buffers(Vec<vk::DescriptorBufferInfo>),
+}
 
+/// Collects the resources of a shader, `ShaderBuilder` builds it into a `ShaderIo`.
+///
+/// The stages a binding is visible to are derived from the shaders, `binding_stages` makes a binding
+/// visible to more stages.
 pub struct ShaderIOBuilder {
     descriptor_types: Vec<vk::DescriptorType>,
-    descriptor_layout_bindingen: Vec<vk::DescriptorSetLayoutBinding>,
+    descriptor_layout_bindingen: Vec<(u32, vk::DescriptorSetLayoutBinding)>,
+    set_frequencies: HashMap<u32, DescriptorFrequency>,
+    binding_stages: HashMap<(u32, u32), vk::ShaderStageFlags>,
 
-    uniform_buffers: Vec<UniformBuffer>,
+    uniform_buffers: Vec<(u32, UniformBuffer)>,
+    buffer_sources: Vec<(u32, BufferSource)>,
 
-    write_descriptor_sets: Vec<vk::WriteDescriptorSet>,
+    write_descriptor_sets: Vec<(u32, vk::WriteDescriptorSet)>,
 
     input_buffer_layout: Option<BufferLayout>,
     push_constant_ranges: Vec<vk::PushConstantRange>,
 
-    specialization_constants: Vec<vk::SpecializationMapEntry>,
-    specialization_constant_data: GltfPBRShaderConstants,
+    specialization_constant_data: Option<GltfPBRShaderConstants>,
 
     descriptor_image_info: Vec<vk::DescriptorImageInfo>,
     dynamic_descriptor_image_info: Vec<vk::DescriptorImageInfo>,
     sampler: Vec<vk::DescriptorImageInfo>,
+
+    bindless_textures: Option<BindlessTextures>,
 }
 
 impl ShaderIOBuilder {
     pub fn builder() -> Self {
         ShaderIOBuilder {
             descriptor_layout_bindingen: vec![],
+            set_frequencies: HashMap::new(),
+            binding_stages: HashMap::new(),
             descriptor_types: vec![],
             write_descriptor_sets: vec![],
             input_buffer_layout: None,
             push_constant_ranges: vec![],
 
-            // used to keep pointer alive.
-            specialization_constants: vec![],
-            specialization_constant_data: Default::default(),
+            specialization_constant_data: None,
 
             descriptor_image_info: vec![],
             dynamic_descriptor_image_info: vec![],
             sampler: vec![],
             uniform_buffers: vec![],
+            buffer_sources: vec![],
+            bindless_textures: None,
         }
     }
 
     /// Shader with the given input buffer.
+    ///
+    /// When no layout is given it is reflected from the vertex shader with the inputs tightly packed.
     pub fn add_input_buffer_layout(mut self, input_buffer_layout: BufferLayout) -> ShaderIOBuilder {
         self.input_buffer_layout = Some(input_buffer_layout);
         self
     }
 
-    pub fn sampler(
+    /// Sets how often the descriptors of a set change, sets default to `DescriptorFrequency::Frame`.
+    pub fn descriptor_set(mut self, set: u32, frequency: DescriptorFrequency) -> ShaderIOBuilder {
+        self.set_frequencies.insert(set, frequency);
+        self
+    }
+
+    /// Makes a binding visible to `stages` in addition to the stages that use it in the shaders.
+    pub fn binding_stages(
         mut self,
+        set: u32,
         binding_id: u32,
-        stage_flags: vk::ShaderStageFlags,
-        sampler: vk::Sampler,
+        stages: vk::ShaderStageFlags,
     ) -> ShaderIOBuilder {
+        *self
+            .binding_stages
+            .entry((set, binding_id))
+            .or_insert_with(vk::ShaderStageFlags::empty) |= stages;
+        self
+    }
+
+    pub fn sampler(mut self, set: u32, binding_id: u32, sampler: vk::Sampler) -> ShaderIOBuilder {
         let descriptor_type = vk::DescriptorType::SAMPLER;
 
         self.sampler.push(
@@ -70,23 +121,24 @@ impl ShaderIOBuilder {
                 .build(),
         );
 
-        self.write_descriptor_sets.push(
+        self.write_descriptor_sets.push((
+            set,
             vk::WriteDescriptorSet::builder()
                 .dst_binding(binding_id)
                 .descriptor_type(descriptor_type)
                 .dst_array_element(0)
                 .image_info(&self.sampler)
                 .build(),
-        );
+        ));
 
-        self.descriptor_layout_bindingen.push(
+        self.descriptor_layout_bindingen.push((
+            set,
             vk::DescriptorSetLayoutBinding::builder()
                 .descriptor_type(descriptor_type)
                 .descriptor_count(1) // update texture count
-                .stage_flags(stage_flags) //
                 .binding(binding_id)
                 .build(),
-        );
+        ));
 
         self.descriptor_types.push(descriptor_type);
 
@@ -95,12 +147,13 @@ impl ShaderIOBuilder {
 
     pub fn texture_array(
         mut self,
+        set: u32,
         binding_id: u32,
-        stage_flags: vk::ShaderStageFlags,
         textures: &[Texture],
         sampler: vk::Sampler,
     ) -> ShaderIOBuilder {
-        let descriptor_type = vk::DescriptorType::COMBINED_IMAGE_SAMPLER;
+        // The textures are sampled with the separate sampler binding.
+        let descriptor_type = vk::DescriptorType::SAMPLED_IMAGE;
 
         for texture in textures.iter() {
             self.dynamic_descriptor_image_info.push(
@@ -112,44 +165,53 @@ impl ShaderIOBuilder {
             );
         }
 
-        self.write_descriptor_sets.push(
+        self.write_descriptor_sets.push((
+            set,
             vk::WriteDescriptorSet::builder()
                 .dst_binding(binding_id)
                 .descriptor_type(descriptor_type)
                 .dst_array_element(0)
                 .image_info(&self.dynamic_descriptor_image_info)
                 .build(),
-        );
+        ));
 
-        self.descriptor_layout_bindingen.push(
+        self.descriptor_layout_bindingen.push((
+            set,
             vk::DescriptorSetLayoutBinding::builder()
                 .descriptor_type(descriptor_type)
                 .descriptor_count(textures.len() as u32) // update texture count
-                .stage_flags(stage_flags) //
                 .binding(binding_id)
                 .build(),
-        );
+        ));
 
-        self.descriptor_types.push(descriptor_type);
+        // The pool needs room for every texture in the array.
+        self.descriptor_types
+            .extend(std::iter::repeat(descriptor_type).take(textures.len()));
+
+        self
+    }
 
+    /// Binds the bindless texture table as descriptor set 1, the shader can not use set 1 for its own bindings.
+    pub fn bindless_textures(mut self, bindless_textures: BindlessTextures) -> ShaderIOBuilder {
+        self.bindless_textures = Some(bindless_textures);
         self
     }
 
+    /// Specializes the shader with the given constants.
+    ///
+    /// The map entries are reflected from the shaders, the constants are tightly packed in constant id order.
     pub fn add_specialization_constants(
         mut self,
         constant_data: GltfPBRShaderConstants,
-        entries: Vec<vk::SpecializationMapEntry>,
     ) -> ShaderIOBuilder {
-        self.specialization_constants = entries;
-        self.specialization_constant_data = constant_data;
-
+        self.specialization_constant_data = Some(constant_data);
         self
     }
 
     pub fn add_static_image(
         mut self,
+        set: u32,
         binding_id: u32,
-        stage_flags: vk::ShaderStageFlags,
         texture: &Texture,
         sampler: vk::Sampler,
     ) -> ShaderIOBuilder {
@@ -173,28 +235,95 @@ impl ShaderIOBuilder {
         let descriptor_layout_binding = vk::DescriptorSetLayoutBinding::builder()
             .descriptor_type(descriptor_type)
             .descriptor_count(1)
-            .stage_flags(stage_flags)
             .binding(binding_id)
             .build();
 
-        self.write_descriptor_sets.push(write_descriptor_set);
+        self.write_descriptor_sets.push((set, write_descriptor_set));
         self.descriptor_layout_bindingen
-            .push(descriptor_layout_binding);
+            .push((set, descriptor_layout_binding));
         self.descriptor_types.push(descriptor_type);
         self
     }
 
     pub fn add_uniform_buffer(
         mut self,
+        set: u32,
         binding_id: u32,
-        stage_flags: ShaderStageFlags,
         render_context: &RenderContext,
         frames: usize,
         buffer_object_size: usize,
     ) -> ShaderIOBuilder {
         let buffer = UniformBuffer::new(render_context, frames, buffer_object_size);
-        let descriptor_type = vk::DescriptorType::UNIFORM_BUFFER;
 
+        self.buffer_sources
+            .push((set, BufferSource::Uniform(self.uniform_buffers.len())));
+        self.uniform_buffers.push((set, buffer));
+
+        self.add_buffer_binding(set, binding_id, vk::DescriptorType::UNIFORM_BUFFER)
+    }
+
+    /// Binds storage buffers, the shader declares them as a `buffer` block.
+    ///
+    /// # Arguments
+    /// - `buffers`: One buffer per frame in flight, or a single buffer that is used by every frame.
+    ///   The buffers are owned by the caller.
+    pub fn add_storage_buffer(
+        mut self,
+        set: u32,
+        binding_id: u32,
+        buffers: &[GPUBuffer],
+    ) -> ShaderIOBuilder {
+        let buffer_infos = buffers
+            .iter()
+            .map(|buffer| vk::DescriptorBufferInfo {
+                buffer: **buffer,
+                offset: 0,
+                // The size is zero for an empty buffer, which is not a valid range.
+                range: vk::WHOLE_SIZE,
+            })
+            .collect();
+
+        self.buffer_sources
+            .push((set, BufferSource::Buffers(buffer_infos)));
+
+        self.add_buffer_binding(set, binding_id, vk::DescriptorType::STORAGE_BUFFER)
+    }
+
+    /// Binds dynamic uniform buffers created with `GPUBuffer::dynamic_uniform`, the descriptor covers one element.
+    ///
+    /// The element is selected with a dynamic offset when the set is bound, see `GPUBuffer::dynamic_offset`.
+    ///
+    /// # Arguments
+    /// - `buffers`: One buffer per frame in flight, or a single buffer that is used by every frame.
+    ///   The buffers are owned by the caller.
+    pub fn add_dynamic_uniform_buffer(
+        mut self,
+        set: u32,
+        binding_id: u32,
+        buffers: &[GPUBuffer],
+    ) -> ShaderIOBuilder {
+        let buffer_infos = buffers
+            .iter()
+            .map(|buffer| vk::DescriptorBufferInfo {
+                buffer: **buffer,
+                offset: 0,
+                range: buffer.stride(),
+            })
+            .collect();
+
+        self.buffer_sources
+            .push((set, BufferSource::Buffers(buffer_infos)));
+
+        self.add_buffer_binding(set, binding_id, vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC)
+    }
+
+    /// Adds the write and layout binding of a buffer, the buffer info is filled in per set by the descriptor pool.
+    fn add_buffer_binding(
+        mut self,
+        set: u32,
+        binding_id: u32,
+        descriptor_type: vk::DescriptorType,
+    ) -> ShaderIOBuilder {
         let write_descriptor_set = vk::WriteDescriptorSet::builder()
             .dst_binding(binding_id)
             .descriptor_type(descriptor_type)
@@ -204,16 +333,13 @@ impl ShaderIOBuilder {
         let descriptor_layout_binding = vk::DescriptorSetLayoutBinding::builder()
             .descriptor_type(descriptor_type)
             .descriptor_count(1)
-            .stage_flags(stage_flags)
             .binding(binding_id)
             .build();
 
-        self.write_descriptor_sets.push(write_descriptor_set);
+        self.write_descriptor_sets.push((set, write_descriptor_set));
         self.descriptor_layout_bindingen
-            .push(descriptor_layout_binding);
+            .push((set, descriptor_layout_binding));
         self.descriptor_types.push(descriptor_type);
-
-        self.uniform_buffers.push(buffer);
         self
     }
 
@@ -226,55 +352,245 @@ impl ShaderIOBuilder {
         self
     }
 
-    pub fn build(mut self, render_context: &RenderContext, frames: usize) -> ShaderIo {
-        let layout_create_info = vk::DescriptorSetLayoutCreateInfo::builder()
-            .bindings(&self.descriptor_layout_bindingen)
-            .build();
+    /// Builds a layout for every set up to the highest set that is used, unused sets get an empty layout.
+    ///
+    /// The stages a binding is visible to are derived from the reflection, `ShaderBuilder` and
+    /// `ComputePipeline` build the shader io once they reflected their shaders.
+    /// Panics when a binding is added to the set of the bindless texture table.
+    pub(crate) fn build(
+        mut self,
+        device: &VkLogicalDevice,
+        reflection: &ShaderReflection,
+        frames: usize,
+    ) -> ShaderIo {
+        self.derive_stage_flags(reflection);
+
+        let bindless_set = self.bindless_textures.map(|_| BINDLESS_TEXTURE_SET);
+
+        let last_set = self
+            .descriptor_layout_bindingen
+            .iter()
+            .map(|(set, _)| *set)
+            .chain(self.set_frequencies.keys().copied())
+            .chain(bindless_set)
+            .max()
+            .unwrap_or(0);
+
+        if let Some(bindless_set) = bindless_set {
+            assert!(
+                self.descriptor_layout_bindingen
+                    .iter()
+                    .all(|(set, _)| *set != bindless_set),
+                "Shader io binds to the set of the bindless texture table!"
+            );
+        }
 
-        let descriptor_set_layout = unsafe {
-            render_context
-                .device()
-                .create_descriptor_set_layout(&layout_create_info, None)
-                .expect("failed to create descriptor set layout!")
-        };
+        let set_indices: Vec<u32> = (0..=last_set)
+            .filter(|set| Some(*set) != bindless_set)
+            .collect();
+
+        let frequencies: Vec<DescriptorFrequency> = set_indices
+            .iter()
+            .map(|set| {
+                self.set_frequencies
+                    .get(set)
+                    .copied()
+                    .unwrap_or(DescriptorFrequency::Frame)
+            })
+            .collect();
+
+        let max_sets = frequencies
+            .iter()
+            .map(|frequency| Self::set_count(*frequency, frames))
+            .sum::<usize>()
+            .max(1);
 
         self.descriptor_types
             .push(vk::DescriptorType::INPUT_ATTACHMENT); // TODO: this is not required I think+
 
-        let descriptor_pool =
-            DescriptorPool::new(&render_context.device(), &self.descriptor_types, frames);
+        let descriptor_pool = DescriptorPool::new(device, &self.descriptor_types, frames, max_sets);
+
+        let mut descriptor_sets = vec![];
+
+        for (set, frequency) in set_indices.into_iter().zip(frequencies) {
+            let layout_bindings: Vec<vk::DescriptorSetLayoutBinding> = self
+                .descriptor_layout_bindingen
+                .iter()
+                .filter(|(binding_set, _)| *binding_set == set)
+                .map(|(_, binding)| *binding)
+                .collect();
+
+            let layout_create_info = vk::DescriptorSetLayoutCreateInfo::builder()
+                .bindings(&layout_bindings)
+                .build();
+
+            let layout = unsafe {
+                device
+                    .create_descriptor_set_layout(&layout_create_info, None)
+                    .expect("failed to create descriptor set layout!")
+            };
+
+            let set_count = Self::set_count(frequency, frames);
+
+            let sets = if set_count > 0 {
+                let write_sets = self
+                    .write_descriptor_sets
+                    .iter()
+                    .filter(|(write_set, _)| *write_set == set)
+                    .map(|(_, write)| *write)
+                    .collect();
+
+                let buffer_infos: Vec<Vec<vk::DescriptorBufferInfo>> = self
+                    .buffer_sources
+                    .iter()
+                    .filter(|(buffer_set, _)| *buffer_set == set)
+                    .map(|(_, source)| self.buffer_infos(source, set_count))
+                    .collect();
+
+                descriptor_pool.create_descriptor_sets(
+                    device,
+                    layout,
+                    write_sets,
+                    &buffer_infos,
+                    set_count,
+                )
+            } else {
+                vec![]
+            };
+
+            descriptor_sets.push(ShaderDescriptorSet {
+                set,
+                frequency,
+                layout,
+                layout_bindings,
+                descriptor_sets: sets,
+            });
+        }
 
-        let descriptor_sets = descriptor_pool.create_descriptor_sets(
-            &render_context.device(),
-            descriptor_set_layout,
-            self.write_descriptor_sets,
-            &mut self.uniform_buffers,
-        );
+        let uniform_buffers: Vec<UniformBuffer> = self
+            .uniform_buffers
+            .into_iter()
+            .map(|(_, buffer)| buffer)
+            .collect();
 
         ShaderIo {
             descriptor_pool,
             descriptor_sets,
-            uniform_buffer_objects: Vec::with_capacity(self.uniform_buffers.len()),
-            uniform_buffers: self.uniform_buffers,
-            descriptor_set_layout,
-            input_buffer_layout: self.input_buffer_layout.unwrap(),
+            uniform_buffer_objects: Vec::with_capacity(uniform_buffers.len()),
+            uniform_buffers,
+            input_buffer_layout: self.input_buffer_layout,
             push_constant_ranges: self.push_constant_ranges,
-            specialization_constants: self.specialization_constants,
+            // Reflected once the shaders are known, see `ShaderBuilder`.
+            specialization_constants: vec![],
             specialization_constant_data: self.specialization_constant_data,
+            bindless_textures: self.bindless_textures,
+        }
+    }
+
+    /// Makes every binding visible to the stages that use it in the shaders and the stages added with
+    /// `binding_stages`.
+    ///
+    /// Bindings the shaders do not use are made visible to all their stages, a binding needs at least one stage.
+    fn derive_stage_flags(&mut self, reflection: &ShaderReflection) {
+        for (set, binding) in self.descriptor_layout_bindingen.iter_mut() {
+            let reflected_stages = reflection
+                .descriptor_set_layout_bindings(*set, binding.descriptor_count)
+                .iter()
+                .find(|reflected| reflected.binding == binding.binding)
+                .map(|reflected| reflected.stage_flags)
+                .unwrap_or(reflection.stages);
+
+            binding.stage_flags = self
+                .binding_stages
+                .get(&(*set, binding.binding))
+                .map_or(reflected_stages, |stages| reflected_stages | *stages);
+        }
+    }
+
+    /// The buffer infos of a buffer write, one per set or one for all sets.
+    fn buffer_infos(
+        &self,
+        source: &BufferSource,
+        set_count: usize,
+    ) -> Vec<vk::DescriptorBufferInfo> {
+        match source {
+            BufferSource::Uniform(index) => {
+                let uniform_buffer = &self.uniform_buffers[*index].1;
+
+                (0..set_count)
+                    .map(|frame| vk::DescriptorBufferInfo {
+                        buffer: uniform_buffer.buffers(frame),
+                        offset: 0,
+                        range: uniform_buffer.uniform_object_size as u64,
+                    })
+                    .collect()
+            }
+            BufferSource::Buffers(buffer_infos) => {
+                assert!(
+                    buffer_infos.len() == 1 || buffer_infos.len() >= set_count,
+                    "Expected a buffer for every frame or a single buffer!"
+                );
+                buffer_infos.clone()
+            }
+        }
+    }
+
+    /// The number of sets the shader allocates for a set with the given frequency.
+    fn set_count(frequency: DescriptorFrequency, frames: usize) -> usize {
+        match frequency {
+            DescriptorFrequency::Frame => frames,
+            DescriptorFrequency::Material => 1,
+            DescriptorFrequency::Object => 0,
+        }
+    }
+}
+
+/// The layout and descriptor sets of one set index of a shader.
+pub struct ShaderDescriptorSet {
+    pub set: u32,
+    pub frequency: DescriptorFrequency,
+    pub layout: vk::DescriptorSetLayout,
+    pub layout_bindings: Vec<vk::DescriptorSetLayoutBinding>,
+    /// One set per frame in flight for `Frame`, a single set for `Material` and none for `Object`.
+    pub descriptor_sets: Vec<DescriptorSet>,
+}
+
+impl ShaderDescriptorSet {
+    /// The number of dynamic offsets that must be given when binding the set.
+    pub fn dynamic_offset_count(&self) -> usize {
+        self.layout_bindings
+            .iter()
+            .filter(|binding| {
+                binding.descriptor_type == vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC
+                    || binding.descriptor_type == vk::DescriptorType::STORAGE_BUFFER_DYNAMIC
+            })
+            .map(|binding| binding.descriptor_count as usize)
+            .sum()
+    }
+
+    /// The set to bind while rendering the given frame, `None` for per object sets.
+    pub fn frame_set(&self, frame: usize) -> Option<vk::DescriptorSet> {
+        match self.frequency {
+            DescriptorFrequency::Frame => self.descriptor_sets.get(frame).map(|set| **set),
+            DescriptorFrequency::Material => self.descriptor_sets.first().map(|set| **set),
+            DescriptorFrequency::Object => None,
         }
     }
 }
 
 pub struct ShaderIo {
     pub descriptor_pool: DescriptorPool,
-    pub descriptor_sets: Vec<DescriptorSet>,
+    /// The sets of the shader by set index, without the set of the bindless texture table.
+    pub descriptor_sets: Vec<ShaderDescriptorSet>,
     pub uniform_buffers: Vec<UniformBuffer>,
-    pub descriptor_set_layout: vk::DescriptorSetLayout,
-    pub input_buffer_layout: BufferLayout,
+    /// Filled from the vertex shader reflection by `ShaderBuilder` when not supplied.
+    pub input_buffer_layout: Option<BufferLayout>,
     pub push_constant_ranges: Vec<vk::PushConstantRange>,
     pub uniform_buffer_objects: Vec<Box<dyn UniformObjectTemplate>>,
     pub specialization_constants: Vec<vk::SpecializationMapEntry>,
-    pub specialization_constant_data: GltfPBRShaderConstants,
+    pub specialization_constant_data: Option<GltfPBRShaderConstants>,
+    /// The bindless texture table bound as set 1, it is owned by the renderer.
+    pub bindless_textures: Option<BindlessTextures>,
 }
 
 impl ShaderIo {
@@ -283,17 +599,68 @@ impl ShaderIo {
         for buffer in &self.uniform_buffers {
             buffer.destroy(device);
         }
-        device.destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+        for descriptor_set in &self.descriptor_sets {
+            device.destroy_descriptor_set_layout(descriptor_set.layout, None);
+        }
+    }
+
+    /// The set with the given index, `None` for the bindless texture set and sets past the last one.
+    pub fn descriptor_set(&self, set: u32) -> Option<&ShaderDescriptorSet> {
+        self.descriptor_sets
+            .iter()
+            .find(|descriptor_set| descriptor_set.set == set)
     }
 
     pub fn specialization_data_ref(&self) -> Vec<u8> {
-        let a = bincode::serialize(&self.specialization_constant_data).unwrap();
+        match self.specialization_constant_data.as_ref() {
+            Some(data) => bincode::serialize(data).unwrap(),
+            None => vec![],
+        }
+    }
+
+    /// The sets that are bound with the pipeline while rendering the frame, with their set index.
+    ///
+    /// Per object sets are not included, they are bound for every object.
+    pub fn frame_descriptor_sets(&self, frame: usize) -> Vec<(u32, vk::DescriptorSet)> {
+        let mut sets: Vec<(u32, vk::DescriptorSet)> = self
+            .descriptor_sets
+            .iter()
+            .filter_map(|descriptor_set| {
+                descriptor_set
+                    .frame_set(frame)
+                    .map(|set| (descriptor_set.set, set))
+            })
+            .collect();
+
+        if let Some(bindless_textures) = self.bindless_textures.as_ref() {
+            sets.push((BINDLESS_TEXTURE_SET, bindless_textures.descriptor_set));
+        }
+
+        sets.sort_by_key(|(set, _)| *set);
+        sets
+    }
+
+    /// The layouts of the descriptor sets, in set order.
+    pub fn descriptor_set_layouts(&self) -> Vec<vk::DescriptorSetLayout> {
+        let mut layouts: Vec<(u32, vk::DescriptorSetLayout)> = self
+            .descriptor_sets
+            .iter()
+            .map(|descriptor_set| (descriptor_set.set, descriptor_set.layout))
+            .collect();
+
+        if let Some(bindless_textures) = self.bindless_textures.as_ref() {
+            layouts.push((
+                BINDLESS_TEXTURE_SET,
+                bindless_textures.descriptor_set_layout,
+            ));
+        }
 
-        return a;
+        layouts.sort_by_key(|(set, _)| *set);
+        layouts.into_iter().map(|(_, layout)| layout).collect()
     }
 
     pub fn create_pipeline_layout(&self, device: &VkLogicalDevice) -> PipelineLayout {
-        let descriptor_set_layouts = [self.descriptor_set_layout];
+        let descriptor_set_layouts = self.descriptor_set_layouts();
         let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo::builder()
             .set_layouts(&descriptor_set_layouts)
             .push_constant_ranges(&self.push_constant_ranges)
@@ -310,14 +677,78 @@ impl ShaderIo {
     }
 
     pub fn vertex_input_info(&mut self) -> PipelineVertexInputStateCreateInfo {
-        self.input_buffer_layout.build_attrib_description();
-        self.input_buffer_layout.build_binding_description();
+        let input_buffer_layout = self
+            .input_buffer_layout
+            .as_mut()
+            .expect("Shader io without an input buffer layout!");
+
+        input_buffer_layout.build_attrib_description();
+        input_buffer_layout.build_binding_description();
 
         let vertex_input_info = vk::PipelineVertexInputStateCreateInfo::builder()
-            .vertex_binding_descriptions(&self.input_buffer_layout.binding_desc)
-            .vertex_attribute_descriptions(&self.input_buffer_layout.attrib_desc)
+            .vertex_binding_descriptions(&input_buffer_layout.binding_desc)
+            .vertex_attribute_descriptions(&input_buffer_layout.attrib_desc)
             .build();
 
         vertex_input_info
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::{DescriptorCount, ReflectedBinding, ShaderIOBuilder, ShaderReflection};
+    use ash::vk;
+
+    #[test]
+    fn stage_flags_are_derived_from_reflection() {
+        let mut reflection = ShaderReflection::default();
+        reflection.stages = vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT;
+        reflection.bindings.push(ReflectedBinding {
+            set: 0,
+            binding: 1,
+            descriptor_type: vk::DescriptorType::SAMPLER,
+            count: DescriptorCount::Fixed(1),
+            stages: vk::ShaderStageFlags::FRAGMENT,
+        });
+
+        let mut builder = ShaderIOBuilder::builder()
+            .sampler(0, 1, vk::Sampler::null())
+            .sampler(0, 2, vk::Sampler::null());
+        builder.derive_stage_flags(&reflection);
+
+        let stages: Vec<vk::ShaderStageFlags> = builder
+            .descriptor_layout_bindingen
+            .iter()
+            .map(|(_, binding)| binding.stage_flags)
+            .collect();
+
+        // Binding 2 is not used by the shaders, it is visible to all of their stages.
+        assert_eq!(
+            stages,
+            vec![vk::ShaderStageFlags::FRAGMENT, reflection.stages]
+        );
+    }
+
+    #[test]
+    fn binding_stages_are_added_to_the_reflected_stages() {
+        let mut reflection = ShaderReflection::default();
+        reflection.stages = vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT;
+        reflection.bindings.push(ReflectedBinding {
+            set: 0,
+            binding: 1,
+            descriptor_type: vk::DescriptorType::SAMPLER,
+            count: DescriptorCount::Fixed(1),
+            stages: vk::ShaderStageFlags::FRAGMENT,
+        });
+
+        let mut builder = ShaderIOBuilder::builder()
+            .sampler(0, 1, vk::Sampler::null())
+            .binding_stages(0, 1, vk::ShaderStageFlags::VERTEX);
+        builder.derive_stage_flags(&reflection);
+
+        assert_eq!(
+            builder.descriptor_layout_bindingen[0].1.stage_flags,
+            vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT
+        );
+    }
+}
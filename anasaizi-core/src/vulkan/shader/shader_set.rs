@@ -1,6 +1,10 @@
 use crate::{
-    engine::UniformObjectTemplate,
-    vulkan::{shader::shader_flags::ShaderFlags, ShaderIo, VkLogicalDevice},
+    engine::{FrameShaderResources, UniformObjectTemplate},
+    vulkan::{
+        shader::{reflection::ShaderReflection, shader_flags::ShaderFlags},
+        ShaderIo, ShaderSource, VkLogicalDevice,
+    },
+    Result,
 };
 use ash::{version::DeviceV1_0, vk, vk::ShaderModule};
 use std::{path::Path, ptr};
@@ -14,31 +18,48 @@ use std::{path::Path, ptr};
 pub struct ShaderSet {
     vertex_module: vk::ShaderModule,
     fragment_module: ShaderModule,
+    vertex_source: ShaderSource,
+    fragment_source: ShaderSource,
     pub io: ShaderIo,
     pub flags: ShaderFlags,
+    debug_name: Option<String>,
 }
 
 impl ShaderSet {
     pub fn new(
         vertex_module: ShaderModule,
         fragment_module: ShaderModule,
+        vertex_source: ShaderSource,
+        fragment_source: ShaderSource,
         io: ShaderIo,
         flags: ShaderFlags,
     ) -> ShaderSet {
         ShaderSet {
             vertex_module,
             fragment_module,
+            vertex_source,
+            fragment_source,
             io,
             flags,
+            debug_name: None,
         }
     }
 
-    pub fn get_descriptor_sets(&self, frame: usize, _texture: String) -> Vec<vk::DescriptorSet> {
-        vec![*self.io.descriptor_sets[frame]]
+    /// Names the shader modules in validation messages and captures, the name is kept on reload.
+    pub fn set_debug_name(&mut self, device: &VkLogicalDevice, name: &str) {
+        self.debug_name = Some(name.to_string());
+        self.apply_debug_name(device);
+    }
+
+    fn apply_debug_name(&self, device: &VkLogicalDevice) {
+        if let Some(name) = self.debug_name.as_ref() {
+            device.set_debug_name(self.vertex_module, &format!("{} (vertex)", name));
+            device.set_debug_name(self.fragment_module, &format!("{} (fragment)", name));
+        }
     }
 
     pub fn descriptor_set_layout(&self) -> Vec<vk::DescriptorSetLayout> {
-        vec![self.io.descriptor_set_layout]
+        self.io.descriptor_set_layouts()
     }
 }
 
@@ -51,16 +72,57 @@ impl ShaderSet {
         self.vertex_module
     }
 
+    /// Returns whether one of the stages is read from the given file.
+    pub fn is_read_from(&self, path: &Path) -> bool {
+        self.vertex_source.is_read_from(path) || self.fragment_source.is_read_from(path)
+    }
+
+    /// Reads, or compiles, both stages again and replaces the shader modules.
+    ///
+    /// The shader io is kept, the new code must match it. On failure the old modules stay in use.
+    /// The pipelines using this shader must be refreshed afterwards.
+    pub fn reload(&mut self, device: &VkLogicalDevice) -> Result<()> {
+        let vertex_code = self.vertex_source.load()?;
+        let fragment_code = self.fragment_source.load()?;
+
+        ShaderReflection::from_spirv(&vertex_code)?
+            .merge(ShaderReflection::from_spirv(&fragment_code)?)
+            .validate(&self.io)?;
+
+        let vertex_module = Self::create_shader_module(device, vertex_code)?;
+        let fragment_module = match Self::create_shader_module(device, fragment_code) {
+            Ok(module) => module,
+            Err(error) => {
+                unsafe { device.destroy_shader_module(vertex_module, None) };
+                return Err(error);
+            }
+        };
+
+        unsafe {
+            device.destroy_shader_module(self.vertex_module, None);
+            device.destroy_shader_module(self.fragment_module, None);
+        }
+
+        self.vertex_module = vertex_module;
+        self.fragment_module = fragment_module;
+        self.apply_debug_name(device);
+
+        Ok(())
+    }
+
     pub fn add_uniform_object<U: UniformObjectTemplate + 'static>(&mut self, uniform_object: U) {
         self.io
             .uniform_buffer_objects
             .push(Box::new(uniform_object));
     }
 
+    /// Writes the uniform object with the given index into its uniform buffer of a frame.
+    ///
+    /// # Arguments
+    /// - `frame`: The resources of this shader in the frame, see `FrameData::shader_resources`.
     pub fn update_uniform<U: UniformObjectTemplate + Clone + 'static>(
-        &mut self,
-        device: &VkLogicalDevice,
-        current_image: usize,
+        &self,
+        frame: &FrameShaderResources,
         object_index: usize,
         update_fn: &dyn Fn(&mut U),
     ) {
@@ -68,8 +130,7 @@ impl ShaderSet {
             panic!("Trying to update shader uniform without uniform buffer.");
         }
 
-        let uniform_object = if let Some(ubo) = self.io.uniform_buffer_objects.get_mut(object_index)
-        {
+        let uniform_object = if let Some(ubo) = self.io.uniform_buffer_objects.get(object_index) {
             ubo.clone()
         } else {
             panic!(
@@ -78,9 +139,11 @@ impl ShaderSet {
             );
         };
 
-        let uniform_buffer = if let Some(uniform_buffer) = self.io.uniform_buffers.get(object_index)
-        {
-            uniform_buffer
+        let (uniform_buffer, (_, memory)) = if let (Some(uniform_buffer), Some(frame_buffer)) = (
+            self.io.uniform_buffers.get(object_index),
+            frame.uniform_buffers.get(object_index),
+        ) {
+            (uniform_buffer, frame_buffer)
         } else {
             panic!("Could not get uniformbuffer with index: {}", object_index);
         };
@@ -96,21 +159,13 @@ impl ShaderSet {
 
             let updating_ubos = [casted_uniform_object];
 
-            let buffer_size = (obj.size() * updating_ubos.len()) as u64;
-
             unsafe {
-                let data_ptr = device
-                    .map_memory(
-                        uniform_buffer.buffers_memory(current_image),
-                        0,
-                        buffer_size,
-                        vk::MemoryMapFlags::empty(),
-                    )
-                    .expect("Failed to Map Memory") as *mut U;
+                let data_ptr = memory
+                    .mapped_ptr()
+                    .expect("Uniform buffer memory is not host visible!")
+                    as *mut U;
 
                 data_ptr.copy_from_nonoverlapping(updating_ubos.as_ptr(), updating_ubos.len());
-
-                device.unmap_memory(uniform_buffer.buffers_memory(current_image));
             };
         } else {
             println!("Could not cast the uniform object to its specific implementation.");
@@ -134,7 +189,7 @@ impl ShaderSet {
     pub(crate) fn create_shader_module(
         device: &VkLogicalDevice,
         code: Vec<u8>,
-    ) -> vk::ShaderModule {
+    ) -> Result<vk::ShaderModule> {
         let shader_module_create_info = vk::ShaderModuleCreateInfo {
             s_type: vk::StructureType::SHADER_MODULE_CREATE_INFO,
             p_next: ptr::null(),
@@ -143,20 +198,9 @@ impl ShaderSet {
             p_code: code.as_ptr() as *const u32,
         };
 
-        unsafe {
-            device
-                .create_shader_module(&shader_module_create_info, None)
-                .expect("Failed to create Shader Module!")
-        }
-    }
-
-    pub(crate) fn read_shader_code(shader_path: &Path) -> Vec<u8> {
-        use std::{fs::File, io::Read};
-
-        let spv_file = File::open(shader_path)
-            .expect(&format!("Failed to find spv file at {:?}", shader_path));
-        let bytes_code: Vec<u8> = spv_file.bytes().filter_map(|byte| byte.ok()).collect();
+        let shader_module =
+            unsafe { device.create_shader_module(&shader_module_create_info, None)? };
 
-        bytes_code
+        Ok(shader_module)
     }
 }
@@ -0,0 +1,55 @@
+use crate::Result;
+use notify::{DebouncedEvent, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{channel, Receiver},
+    time::Duration,
+};
+
+/// How long file events are collected before they are reported, editors often write a file in several steps.
+const DEBOUNCE_DELAY: Duration = Duration::from_millis(200);
+
+/// Watches a shader directory, including subdirectories, for changed files.
+pub struct ShaderWatcher {
+    // The watcher stops when it is dropped.
+    _watcher: RecommendedWatcher,
+    events: Receiver<DebouncedEvent>,
+}
+
+impl ShaderWatcher {
+    /// Starts watching the directory.
+    pub fn new(directory: &Path) -> Result<ShaderWatcher> {
+        let (sender, events) = channel();
+
+        let mut watcher: RecommendedWatcher = Watcher::new(sender, DEBOUNCE_DELAY)?;
+        watcher.watch(directory, RecursiveMode::Recursive)?;
+
+        Ok(ShaderWatcher {
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// The files that were written since the last call, without blocking.
+    pub fn changed_files(&self) -> Vec<PathBuf> {
+        let mut changed_files: Vec<PathBuf> = vec![];
+
+        for event in self.events.try_iter() {
+            let path = match event {
+                DebouncedEvent::Write(path)
+                | DebouncedEvent::Create(path)
+                | DebouncedEvent::Rename(_, path) => path,
+                _ => continue,
+            };
+
+            let path = fs::canonicalize(&path).unwrap_or(path);
+
+            if !changed_files.contains(&path) {
+                changed_files.push(path);
+            }
+        }
+
+        changed_files
+    }
+}
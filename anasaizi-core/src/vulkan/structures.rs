@@ -1,42 +1,101 @@
-use crate::vulkan::VkLogicalDevice;
-use ash::{version::DeviceV1_0, vk};
+use crate::vulkan::{DebugMessageSink, LogSink, MessageSeverity};
 
-use std::ffi::CString;
+use std::{ffi::CString, sync::Arc};
 
-pub struct VkSyncObjects {
-    pub image_available_semaphores: Vec<vk::Semaphore>,
-    pub render_finished_semaphores: Vec<vk::Semaphore>,
-    pub inflight_fences: Vec<vk::Fence>,
-}
-
-impl VkSyncObjects {
-    pub(crate) unsafe fn destroy(&self, device: &VkLogicalDevice) {
-        for i in 0..self.image_available_semaphores.len() {
-            device.destroy_semaphore(self.image_available_semaphores[i], None);
-            device.destroy_semaphore(self.render_finished_semaphores[i], None);
-            device.destroy_fence(self.inflight_fences[i], None);
-        }
-    }
-}
+/// Environment variable that turns the validation layers off with `0`, `false` or `off`.
+pub const VALIDATION_ENV: &str = "ANASAIZI_VALIDATION";
 
+/// Configures the validation layers and where their messages go.
+#[derive(Clone)]
 pub struct VkValidationInfo {
     pub is_enable: bool,
-    pub required_validation_layers: [&'static str; 1],
+    pub required_validation_layers: Vec<String>,
+    /// Receives the messages of the validation layers.
+    pub sink: Arc<dyn DebugMessageSink>,
+    /// Less severe messages are dropped.
+    pub min_severity: MessageSeverity,
+    /// Test mode, validation errors fail the test that created the application.
+    pub fail_on_error: bool,
 }
 
 impl VkValidationInfo {
+    /// Validation with the Khronos layer, warnings and errors go to the `log` crate.
+    pub fn enabled() -> VkValidationInfo {
+        VkValidationInfo {
+            is_enable: true,
+            required_validation_layers: vec![String::from("VK_LAYER_KHRONOS_validation")],
+            sink: Arc::new(LogSink),
+            min_severity: MessageSeverity::Warning,
+            fail_on_error: false,
+        }
+    }
+
+    pub fn disabled() -> VkValidationInfo {
+        VkValidationInfo {
+            is_enable: false,
+            ..Self::enabled()
+        }
+    }
+
+    /// Validation is enabled unless `ANASAIZI_VALIDATION` turns it off.
+    pub fn from_env() -> VkValidationInfo {
+        match std::env::var(VALIDATION_ENV) {
+            Ok(value) if Self::is_off(value.trim()) => Self::disabled(),
+            _ => Self::enabled(),
+        }
+    }
+
+    /// Validation in test mode, any validation error fails the test that created the application.
+    pub fn for_tests() -> VkValidationInfo {
+        VkValidationInfo {
+            fail_on_error: true,
+            ..Self::enabled()
+        }
+    }
+
+    pub fn with_sink(mut self, sink: Arc<dyn DebugMessageSink>) -> VkValidationInfo {
+        self.sink = sink;
+        self
+    }
+
+    pub fn with_min_severity(mut self, min_severity: MessageSeverity) -> VkValidationInfo {
+        self.min_severity = min_severity;
+        self
+    }
+
     pub fn to_vec_owned(&self) -> Vec<String> {
-        return self
-            .required_validation_layers
-            .map(|l| l.to_string())
-            .to_vec();
+        self.required_validation_layers.clone()
     }
 
     pub fn to_vec_ptr(&self) -> Vec<CString> {
         return self
             .required_validation_layers
             .iter()
-            .map(|layer_name| CString::new(*layer_name).expect("Could not parse cstr"))
+            .map(|layer_name| CString::new(layer_name.as_str()).expect("Could not parse cstr"))
             .collect();
     }
+
+    fn is_off(value: &str) -> bool {
+        ["0", "false", "off"]
+            .iter()
+            .any(|off| value.eq_ignore_ascii_case(off))
+    }
+}
+
+impl Default for VkValidationInfo {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::structures::VkValidationInfo;
+
+    #[test]
+    fn validation_env_values() {
+        assert!(VkValidationInfo::is_off("0"));
+        assert!(VkValidationInfo::is_off("OFF"));
+        assert!(!VkValidationInfo::is_off("1"));
+    }
 }
@@ -1,9 +1,65 @@
-use ash::extensions::khr::Win32Surface;
+use ash::extensions::khr;
 
 use crate::vulkan::VkInstance;
 use ash::vk;
 use std::ops::Deref;
 
+/// The window system a surface is created for.
+///
+/// Chosen from the platform handle of the winit window.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SurfacePlatform {
+    Win32,
+    Xlib,
+    Xcb,
+    Wayland,
+}
+
+impl SurfacePlatform {
+    /// Returns the platform that backs the given winit window.
+    #[cfg(target_os = "windows")]
+    pub fn from_window(_window: &winit::window::Window) -> SurfacePlatform {
+        SurfacePlatform::Win32
+    }
+
+    /// Returns the platform that backs the given winit window.
+    ///
+    /// Wayland is used when the window has a wayland display.
+    /// On X11 Xlib is preferred and XCB is used when only the xcb connection is exposed.
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    pub fn from_window(window: &winit::window::Window) -> SurfacePlatform {
+        use winit::platform::unix::WindowExtUnix;
+
+        if window.wayland_display().is_some() {
+            SurfacePlatform::Wayland
+        } else if window.xlib_display().is_some() {
+            SurfacePlatform::Xlib
+        } else if window.xcb_connection().is_some() {
+            SurfacePlatform::Xcb
+        } else {
+            panic!("Unsupported window system, expected wayland, xlib or xcb!")
+        }
+    }
+
+    /// Returns the name of the instance extension needed to create a surface on this platform.
+    pub fn extension_name(&self) -> &'static str {
+        let name = match self {
+            SurfacePlatform::Win32 => khr::Win32Surface::name(),
+            SurfacePlatform::Xlib => khr::XlibSurface::name(),
+            SurfacePlatform::Xcb => khr::XcbSurface::name(),
+            SurfacePlatform::Wayland => khr::WaylandSurface::name(),
+        };
+
+        name.to_str().unwrap()
+    }
+}
+
 /// In Vulkan, the windowing system particulars are exposed via the WSI (Window System Integration) extensions.
 ///
 /// Vulkan uses the VkSurfaceKHR object to abstract the native platform surface or window.
@@ -16,9 +72,8 @@ pub struct VkSurfaceData {
 
 impl VkSurfaceData {
     pub fn new(instance: &VkInstance, window: &winit::window::Window) -> VkSurfaceData {
-        let surface = unsafe {
-            Self::create_surface_windows(instance, window).expect("Failed to create surface.")
-        };
+        let surface =
+            unsafe { Self::create_surface(instance, window).expect("Failed to create surface.") };
 
         let surface_loader = ash::extensions::khr::Surface::new(instance.entry(), instance.deref());
 
@@ -28,8 +83,26 @@ impl VkSurfaceData {
         }
     }
 
+    /// Returns the instance extensions that enable the HDR color spaces, when the driver has them.
+    pub fn optional_extensions() -> Vec<String> {
+        vec![vk::ExtSwapchainColorspaceFn::name()
+            .to_str()
+            .unwrap()
+            .to_string()]
+    }
+
+    /// Returns the instance extensions required to create a surface for the given window.
+    pub fn required_extensions(window: &winit::window::Window) -> Vec<String> {
+        vec![
+            khr::Surface::name().to_str().unwrap().to_string(),
+            SurfacePlatform::from_window(window)
+                .extension_name()
+                .to_string(),
+        ]
+    }
+
     #[cfg(target_os = "windows")]
-    pub unsafe fn create_surface_windows(
+    pub unsafe fn create_surface(
         instance: &VkInstance,
         window: &winit::window::Window,
     ) -> Result<vk::SurfaceKHR, vk::Result> {
@@ -43,7 +116,51 @@ impl VkSurfaceData {
             .hinstance(hinstance)
             .hwnd(hwnd);
 
-        let win32_surface_loader = Win32Surface::new(instance.entry(), instance.deref());
+        let win32_surface_loader = khr::Win32Surface::new(instance.entry(), instance.deref());
         win32_surface_loader.create_win32_surface(&win32_create_info, None)
     }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    pub unsafe fn create_surface(
+        instance: &VkInstance,
+        window: &winit::window::Window,
+    ) -> Result<vk::SurfaceKHR, vk::Result> {
+        use winit::platform::unix::WindowExtUnix;
+
+        match SurfacePlatform::from_window(window) {
+            SurfacePlatform::Wayland => {
+                let wayland_create_info = vk::WaylandSurfaceCreateInfoKHR::builder()
+                    .display(window.wayland_display().unwrap() as *mut vk::wl_display)
+                    .surface(window.wayland_surface().unwrap() as *mut vk::wl_surface);
+
+                let wayland_surface_loader =
+                    khr::WaylandSurface::new(instance.entry(), instance.deref());
+                wayland_surface_loader.create_wayland_surface(&wayland_create_info, None)
+            }
+            SurfacePlatform::Xlib => {
+                let xlib_create_info = vk::XlibSurfaceCreateInfoKHR::builder()
+                    .dpy(window.xlib_display().unwrap() as *mut vk::Display)
+                    .window(window.xlib_window().unwrap() as vk::Window);
+
+                let xlib_surface_loader = khr::XlibSurface::new(instance.entry(), instance.deref());
+                xlib_surface_loader.create_xlib_surface(&xlib_create_info, None)
+            }
+            SurfacePlatform::Xcb => {
+                // X11 windows share their id between xlib and xcb.
+                let xcb_create_info = vk::XcbSurfaceCreateInfoKHR::builder()
+                    .connection(window.xcb_connection().unwrap() as *mut vk::xcb_connection_t)
+                    .window(window.xlib_window().unwrap() as vk::xcb_window_t);
+
+                let xcb_surface_loader = khr::XcbSurface::new(instance.entry(), instance.deref());
+                xcb_surface_loader.create_xcb_surface(&xcb_create_info, None)
+            }
+            SurfacePlatform::Win32 => unreachable!(),
+        }
+    }
 }
@@ -1,11 +1,10 @@
 use ash::vk;
 
 use crate::{
-    engine::{image::Texture, RenderContext},
-    vulkan::{VkImageView, VkLogicalDevice, VkSurfaceData},
-    WINDOW_HEIGHT, WINDOW_WIDTH,
+    engine::RenderContext,
+    vulkan::{SwapchainConfig, VkImageView, VkLogicalDevice, VkSurfaceData},
+    Error, Result,
 };
-use ash::vk::Image;
 use std::ops::Deref;
 
 /// A Vulkan Swapchain.
@@ -17,15 +16,14 @@ pub struct VkSwapChain {
     pub swapchain: vk::SwapchainKHR,
     pub images: Vec<vk::Image>,
     pub image_format: vk::Format,
+    pub color_space: vk::ColorSpaceKHR,
+    pub present_mode: vk::PresentModeKHR,
     pub extent: vk::Extent2D,
     pub image_views: Vec<VkImageView>,
-    pub depth_image: vk::Image,
-    pub depth_image_view: VkImageView,
 }
 
 impl VkSwapChain {
     pub(crate) unsafe fn destroy(&self, device: &VkLogicalDevice) {
-        self.depth_image_view.destroy(device);
         for image_view in self.image_views.iter() {
             image_view.destroy(device);
         }
@@ -35,53 +33,69 @@ impl VkSwapChain {
 }
 
 impl VkSwapChain {
-    pub fn new(render_context: &RenderContext, surface_data: &VkSurfaceData) -> VkSwapChain {
+    /// Creates the swapchain.
+    ///
+    /// The depth and multisampled color images are attachments of the render graph.
+    ///
+    /// # Arguments
+    /// - `extent`: The inner size of the window, used when the surface leaves the extent to the swapchain.
+    /// - `config`: The preferred present mode and format, the closest supported ones are used.
+    pub fn new(
+        render_context: &RenderContext,
+        surface_data: &VkSurfaceData,
+        extent: vk::Extent2D,
+        config: &SwapchainConfig,
+    ) -> Result<VkSwapChain> {
         let swap_chain_support =
-            Self::query_swapchain_support(render_context.physical_device(), surface_data);
+            Self::query_swapchain_support(render_context.physical_device(), surface_data)?;
 
         if swap_chain_support.formats.is_empty() && swap_chain_support.present_modes.is_empty() {
-            panic!("Swapchain can not be configured with present modes!")
+            return Err(Error::Unsupported(String::from(
+                "surface has no formats and present modes for a swapchain",
+            )));
         }
 
-        return Self::create_swapchain(render_context, surface_data);
+        Self::create_swapchain(render_context, surface_data, extent, config)
     }
 
     fn query_swapchain_support(
         physical_device: &vk::PhysicalDevice,
         surface_data: &VkSurfaceData,
-    ) -> SwapChainSupportDetails {
+    ) -> Result<SwapChainSupportDetails> {
         unsafe {
             let capabilities = surface_data
                 .surface_loader
-                .get_physical_device_surface_capabilities(*physical_device, surface_data.surface)
-                .expect("Failed to query for surface capabilities.");
+                .get_physical_device_surface_capabilities(*physical_device, surface_data.surface)?;
             let formats = surface_data
                 .surface_loader
-                .get_physical_device_surface_formats(*physical_device, surface_data.surface)
-                .expect("Failed to query for surface formats.");
+                .get_physical_device_surface_formats(*physical_device, surface_data.surface)?;
             let present_modes = surface_data
                 .surface_loader
-                .get_physical_device_surface_present_modes(*physical_device, surface_data.surface)
-                .expect("Failed to query for surface present mode.");
+                .get_physical_device_surface_present_modes(
+                    *physical_device,
+                    surface_data.surface,
+                )?;
 
-            SwapChainSupportDetails {
+            Ok(SwapChainSupportDetails {
                 capabilities,
                 formats,
                 present_modes,
-            }
+            })
         }
     }
 
     fn create_swapchain(
         render_context: &RenderContext,
         surface_stuff: &VkSurfaceData,
-    ) -> VkSwapChain {
+        window_extent: vk::Extent2D,
+        config: &SwapchainConfig,
+    ) -> Result<VkSwapChain> {
         let swapchain_support =
-            Self::query_swapchain_support(render_context.physical_device(), surface_stuff);
+            Self::query_swapchain_support(render_context.physical_device(), surface_stuff)?;
 
-        let surface_format = Self::choose_swapchain_format(&swapchain_support.formats);
-        let present_mode = Self::choose_swapchain_present_mode(&swapchain_support.present_modes);
-        let extent = Self::choose_swapchain_extent(&swapchain_support.capabilities);
+        let surface_format = config.choose_surface_format(&swapchain_support.formats);
+        let present_mode = config.choose_present_mode(&swapchain_support.present_modes);
+        let extent = Self::choose_swapchain_extent(&swapchain_support.capabilities, window_extent);
 
         let image_count = swapchain_support.capabilities.min_image_count + 1;
         let image_count = if swapchain_support.capabilities.max_image_count > 0 {
@@ -127,19 +141,9 @@ impl VkSwapChain {
             render_context.raw_instance(),
             render_context.device(),
         );
-        let swapchain = unsafe {
-            swapchain_loader
-                .create_swapchain(&swapchain_create_info, None)
-                .expect("Failed to create Swapchain!")
-        };
+        let swapchain = unsafe { swapchain_loader.create_swapchain(&swapchain_create_info, None)? };
 
-        let swapchain_images = unsafe {
-            swapchain_loader
-                .get_swapchain_images(swapchain)
-                .expect("Failed to get Swapchain Images.")
-        };
-
-        let depth_image = Self::create_depth_resources(render_context, extent);
+        let swapchain_images = unsafe { swapchain_loader.get_swapchain_images(swapchain)? };
 
         let image_views = Self::create_image_views(
             render_context.device(),
@@ -147,16 +151,16 @@ impl VkSwapChain {
             &surface_format.format,
         );
 
-        VkSwapChain {
+        Ok(VkSwapChain {
             loader: swapchain_loader,
             swapchain,
             image_format: surface_format.format,
+            color_space: surface_format.color_space,
+            present_mode,
             extent,
             image_views,
             images: swapchain_images,
-            depth_image: depth_image.0,
-            depth_image_view: depth_image.1,
-        }
+        })
     }
 
     /// An image view defines how the swapchain is going to use an image.
@@ -177,66 +181,12 @@ impl VkSwapChain {
         swapchain_imageviews
     }
 
-    pub fn create_depth_resources(
-        render_context: &RenderContext,
-        swapchain_extent: vk::Extent2D,
-    ) -> (Image, VkImageView, vk::DeviceMemory) {
-        let depth_format = render_context
-            .logical_device()
-            .find_depth_format(render_context.raw_instance());
-
-        let (depth_image, depth_image_memory) = Texture::create_image(
-            render_context,
-            swapchain_extent.width,
-            swapchain_extent.height,
-            depth_format,
-            vk::ImageTiling::OPTIMAL,
-            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_DST,
-            vk::MemoryPropertyFlags::DEVICE_LOCAL,
-        );
-
-        let depth_image_view = VkImageView::create(
-            render_context.device(),
-            depth_image,
-            depth_format,
-            vk::ImageAspectFlags::DEPTH,
-        );
-
-        (depth_image, depth_image_view, depth_image_memory)
-    }
-
-    /// Pick a format to use for the swapchain.
-    fn choose_swapchain_format(
-        available_formats: &Vec<vk::SurfaceFormatKHR>,
-    ) -> vk::SurfaceFormatKHR {
-        // check if list contains most widely used R8G8B8A8 format with nonlinear color space
-        for available_format in available_formats {
-            if available_format.format == vk::Format::B8G8R8A8_SRGB
-                && available_format.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR
-            {
-                return available_format.clone();
-            }
-        }
-
-        // return the first format from the list
-        return available_formats.first().unwrap().clone();
-    }
-
-    /// Pick mailbox present mode if possible, otherwise pick FIFO.
-    fn choose_swapchain_present_mode(
-        available_present_modes: &Vec<vk::PresentModeKHR>,
-    ) -> vk::PresentModeKHR {
-        for &available_present_mode in available_present_modes.iter() {
-            if available_present_mode == vk::PresentModeKHR::MAILBOX {
-                return available_present_mode;
-            }
-        }
-
-        vk::PresentModeKHR::FIFO
-    }
-
-    /// Create extend 2d
-    fn choose_swapchain_extent(capabilities: &vk::SurfaceCapabilitiesKHR) -> vk::Extent2D {
+    /// The extent of the surface, or the window extent clamped to the supported extents when the
+    /// surface leaves it to the swapchain.
+    fn choose_swapchain_extent(
+        capabilities: &vk::SurfaceCapabilitiesKHR,
+        window_extent: vk::Extent2D,
+    ) -> vk::Extent2D {
         if capabilities.current_extent.width != u32::MAX {
             capabilities.current_extent
         } else {
@@ -244,12 +194,12 @@ impl VkSwapChain {
 
             vk::Extent2D {
                 width: clamp(
-                    WINDOW_WIDTH,
+                    window_extent.width,
                     capabilities.min_image_extent.width,
                     capabilities.max_image_extent.width,
                 ),
                 height: clamp(
-                    WINDOW_HEIGHT,
+                    window_extent.height,
                     capabilities.min_image_extent.height,
                     capabilities.max_image_extent.height,
                 ),
@@ -0,0 +1,207 @@
+use ash::vk;
+
+/// Environment variable that selects the present mode: `on`, `off`, `adaptive` or `mailbox`.
+pub const VSYNC_ENV: &str = "ANASAIZI_VSYNC";
+
+/// How finished frames are handed to the presentation engine.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PresentMode {
+    /// Vsync on, frames wait for the vertical blank. Always supported.
+    Fifo,
+    /// Adaptive vsync, a late frame is presented right away and may tear.
+    FifoRelaxed,
+    /// Vsync off, frames are presented right away and may tear.
+    Immediate,
+    /// Frames are not limited by the refresh rate, the newest one is presented at the vertical blank.
+    Mailbox,
+}
+
+impl PresentMode {
+    /// Parses the value of `ANASAIZI_VSYNC`.
+    pub fn parse(value: &str) -> Option<PresentMode> {
+        match value.to_ascii_lowercase().as_str() {
+            "on" | "1" | "true" | "fifo" => Some(PresentMode::Fifo),
+            "adaptive" | "relaxed" => Some(PresentMode::FifoRelaxed),
+            "off" | "0" | "false" | "immediate" => Some(PresentMode::Immediate),
+            "mailbox" => Some(PresentMode::Mailbox),
+            _ => None,
+        }
+    }
+
+    pub fn to_vk(&self) -> vk::PresentModeKHR {
+        match self {
+            PresentMode::Fifo => vk::PresentModeKHR::FIFO,
+            PresentMode::FifoRelaxed => vk::PresentModeKHR::FIFO_RELAXED,
+            PresentMode::Immediate => vk::PresentModeKHR::IMMEDIATE,
+            PresentMode::Mailbox => vk::PresentModeKHR::MAILBOX,
+        }
+    }
+
+    /// The modes tried in order when this one is not supported by the surface.
+    fn fallbacks(&self) -> &'static [PresentMode] {
+        match self {
+            PresentMode::Fifo => &[],
+            PresentMode::FifoRelaxed => &[PresentMode::Fifo],
+            PresentMode::Immediate => &[PresentMode::Mailbox, PresentMode::Fifo],
+            PresentMode::Mailbox => &[PresentMode::Fifo],
+        }
+    }
+}
+
+/// The present mode and the format of the swapchain images.
+///
+/// Changes are applied when the swapchain is recreated, see `RenderLayer::set_swapchain_config`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SwapchainConfig {
+    pub present_mode: PresentMode,
+    /// The preferred format, sRGB is used when the surface does not offer it.
+    pub format: vk::Format,
+    pub color_space: vk::ColorSpaceKHR,
+}
+
+impl SwapchainConfig {
+    /// 8 bit sRGB images, offered by every surface that can be presented to.
+    pub const SRGB: (vk::Format, vk::ColorSpaceKHR) =
+        (vk::Format::B8G8R8A8_SRGB, vk::ColorSpaceKHR::SRGB_NONLINEAR);
+
+    /// 10 bit images with the PQ transfer function and BT.2020 primaries.
+    pub const HDR10: (vk::Format, vk::ColorSpaceKHR) = (
+        vk::Format::A2B10G10R10_UNORM_PACK32,
+        vk::ColorSpaceKHR::HDR10_ST2084_EXT,
+    );
+
+    /// Linear 16 bit float images with sRGB primaries, values above 1.0 are brighter than SDR white.
+    pub const SCRGB: (vk::Format, vk::ColorSpaceKHR) = (
+        vk::Format::R16G16B16A16_SFLOAT,
+        vk::ColorSpaceKHR::EXTENDED_SRGB_LINEAR_EXT,
+    );
+
+    /// Mailbox presentation to sRGB images.
+    pub fn new() -> SwapchainConfig {
+        SwapchainConfig {
+            present_mode: PresentMode::Mailbox,
+            format: Self::SRGB.0,
+            color_space: Self::SRGB.1,
+        }
+    }
+
+    /// Reads the present mode from `ANASAIZI_VSYNC`, mailbox is used when it is not set.
+    pub fn from_env() -> SwapchainConfig {
+        let config = Self::new();
+
+        match std::env::var(VSYNC_ENV)
+            .ok()
+            .and_then(|value| PresentMode::parse(value.trim()))
+        {
+            Some(present_mode) => config.with_present_mode(present_mode),
+            None => config,
+        }
+    }
+
+    pub fn with_present_mode(mut self, present_mode: PresentMode) -> SwapchainConfig {
+        self.present_mode = present_mode;
+        self
+    }
+
+    /// Prefers the given format, e.g. `SwapchainConfig::HDR10` or `SwapchainConfig::SCRGB`.
+    pub fn with_surface_format(
+        mut self,
+        (format, color_space): (vk::Format, vk::ColorSpaceKHR),
+    ) -> SwapchainConfig {
+        self.format = format;
+        self.color_space = color_space;
+        self
+    }
+
+    /// Picks the configured present mode, or the closest one the surface supports.
+    pub fn choose_present_mode(
+        &self,
+        available_present_modes: &[vk::PresentModeKHR],
+    ) -> vk::PresentModeKHR {
+        std::iter::once(&self.present_mode)
+            .chain(self.present_mode.fallbacks())
+            .map(PresentMode::to_vk)
+            .find(|present_mode| available_present_modes.contains(present_mode))
+            .unwrap_or(vk::PresentModeKHR::FIFO)
+    }
+
+    /// Picks the configured format, falls back to sRGB and then to the first format of the surface.
+    pub fn choose_surface_format(
+        &self,
+        available_formats: &[vk::SurfaceFormatKHR],
+    ) -> vk::SurfaceFormatKHR {
+        let find = |(format, color_space): (vk::Format, vk::ColorSpaceKHR)| {
+            available_formats.iter().find(|available| {
+                available.format == format && available.color_space == color_space
+            })
+        };
+
+        *find((self.format, self.color_space))
+            .or_else(|| find(Self::SRGB))
+            .unwrap_or(&available_formats[0])
+    }
+}
+
+impl Default for SwapchainConfig {
+    fn default() -> Self {
+        Self::from_env()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::{PresentMode, SwapchainConfig};
+    use ash::vk;
+
+    #[test]
+    fn present_mode_falls_back_to_fifo() {
+        let config = SwapchainConfig::new().with_present_mode(PresentMode::Immediate);
+
+        assert_eq!(
+            config.choose_present_mode(&[vk::PresentModeKHR::FIFO, vk::PresentModeKHR::MAILBOX]),
+            vk::PresentModeKHR::MAILBOX
+        );
+        assert_eq!(
+            config.choose_present_mode(&[vk::PresentModeKHR::FIFO]),
+            vk::PresentModeKHR::FIFO
+        );
+    }
+
+    #[test]
+    fn surface_format_falls_back_to_srgb() {
+        let surface_format = |(format, color_space)| vk::SurfaceFormatKHR {
+            format,
+            color_space,
+        };
+        let available = [
+            surface_format((
+                vk::Format::B8G8R8A8_UNORM,
+                vk::ColorSpaceKHR::SRGB_NONLINEAR,
+            )),
+            surface_format(SwapchainConfig::SRGB),
+            surface_format(SwapchainConfig::SCRGB),
+        ];
+
+        let scrgb = SwapchainConfig::new().with_surface_format(SwapchainConfig::SCRGB);
+        assert_eq!(
+            scrgb.choose_surface_format(&available).format,
+            vk::Format::R16G16B16A16_SFLOAT
+        );
+
+        let hdr10 = SwapchainConfig::new().with_surface_format(SwapchainConfig::HDR10);
+        assert_eq!(
+            hdr10.choose_surface_format(&available).format,
+            vk::Format::B8G8R8A8_SRGB
+        );
+    }
+
+    #[test]
+    fn vsync_env_values() {
+        assert_eq!(PresentMode::parse("OFF"), Some(PresentMode::Immediate));
+        assert_eq!(
+            PresentMode::parse("adaptive"),
+            Some(PresentMode::FifoRelaxed)
+        );
+        assert_eq!(PresentMode::parse("sometimes"), None);
+    }
+}
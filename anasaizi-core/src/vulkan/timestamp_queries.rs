@@ -0,0 +1,192 @@
+use crate::vulkan::VkLogicalDevice;
+use ash::{version::DeviceV1_0, vk};
+use std::time::Duration;
+
+/// A named span of GPU work, the start is relative to the first timestamp of the frame.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GpuScope {
+    pub name: String,
+    pub start: Duration,
+    pub duration: Duration,
+}
+
+/// Timestamp query pools, one per frame in flight, to measure how long the GPU works on scopes of a frame.
+///
+/// Every frame the pool is reset with `reset` outside of a render pass, after that scopes are recorded
+/// with `begin_scope` and `end_scope`. The results are read with `results` once the frame has finished.
+pub struct TimestampQueries {
+    query_pools: Vec<vk::QueryPool>,
+    /// The names of the scopes recorded in each frame, scope `i` uses queries `2i` and `2i + 1`.
+    scopes: Vec<Vec<String>>,
+    max_scopes: u32,
+    /// Nanoseconds per timestamp tick.
+    timestamp_period: f32,
+}
+
+impl TimestampQueries {
+    /// Creates the query pools, `None` when the device can not write timestamps on graphics queues.
+    ///
+    /// # Arguments
+    /// - `frames`: The number of frames in flight.
+    /// - `max_scopes`: The number of scopes that can be recorded in a frame.
+    pub fn new(device: &VkLogicalDevice, frames: usize, max_scopes: u32) -> Option<Self> {
+        let limits = device.device_properties().limits;
+
+        if limits.timestamp_compute_and_graphics == vk::FALSE {
+            return None;
+        }
+
+        let create_info = vk::QueryPoolCreateInfo::builder()
+            .query_type(vk::QueryType::TIMESTAMP)
+            .query_count(max_scopes * 2);
+
+        let query_pools = (0..frames)
+            .map(|_| unsafe {
+                device
+                    .create_query_pool(&create_info, None)
+                    .expect("Failed to create timestamp query pool!")
+            })
+            .collect();
+
+        Some(TimestampQueries {
+            query_pools,
+            scopes: vec![vec![]; frames],
+            max_scopes,
+            timestamp_period: limits.timestamp_period,
+        })
+    }
+
+    /// Resets the queries of the frame and forgets its scopes, must be recorded outside of a render pass.
+    pub fn reset(
+        &mut self,
+        device: &VkLogicalDevice,
+        command_buffer: vk::CommandBuffer,
+        frame: usize,
+    ) {
+        self.scopes[frame].clear();
+
+        unsafe {
+            device.cmd_reset_query_pool(
+                command_buffer,
+                self.query_pools[frame],
+                0,
+                self.max_scopes * 2,
+            );
+        }
+    }
+
+    /// Writes the start timestamp of a scope and returns the scope, `None` when all scopes are used.
+    pub fn begin_scope(
+        &mut self,
+        device: &VkLogicalDevice,
+        command_buffer: vk::CommandBuffer,
+        frame: usize,
+        name: &str,
+    ) -> Option<u32> {
+        let scope = self.scopes[frame].len() as u32;
+
+        if scope == self.max_scopes {
+            return None;
+        }
+
+        self.scopes[frame].push(name.to_string());
+
+        unsafe {
+            device.cmd_write_timestamp(
+                command_buffer,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                self.query_pools[frame],
+                scope * 2,
+            );
+        }
+
+        Some(scope)
+    }
+
+    /// Writes the end timestamp of a scope that was started with `begin_scope`.
+    pub fn end_scope(
+        &self,
+        device: &VkLogicalDevice,
+        command_buffer: vk::CommandBuffer,
+        frame: usize,
+        scope: Option<u32>,
+    ) {
+        if let Some(scope) = scope {
+            unsafe {
+                device.cmd_write_timestamp(
+                    command_buffer,
+                    vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                    self.query_pools[frame],
+                    scope * 2 + 1,
+                );
+            }
+        }
+    }
+
+    /// Reads the scopes of the frame, empty when the frame has not finished on the GPU.
+    pub fn results(&self, device: &VkLogicalDevice, frame: usize) -> Vec<GpuScope> {
+        let scopes = &self.scopes[frame];
+
+        if scopes.is_empty() {
+            return vec![];
+        }
+
+        let mut timestamps = vec![0u64; scopes.len() * 2];
+
+        let result = unsafe {
+            device.get_query_pool_results(
+                self.query_pools[frame],
+                0,
+                timestamps.len() as u32,
+                &mut timestamps,
+                vk::QueryResultFlags::TYPE_64,
+            )
+        };
+
+        match result {
+            Ok(()) => scope_timings(scopes, &timestamps, self.timestamp_period),
+            Err(_) => vec![],
+        }
+    }
+
+    pub unsafe fn destroy(&self, device: &VkLogicalDevice) {
+        for query_pool in &self.query_pools {
+            device.destroy_query_pool(*query_pool, None);
+        }
+    }
+}
+
+/// Converts pairs of begin and end timestamps to scopes, relative to the earliest timestamp.
+fn scope_timings(names: &[String], timestamps: &[u64], timestamp_period: f32) -> Vec<GpuScope> {
+    let first = timestamps.iter().copied().min().unwrap_or(0);
+    let to_duration =
+        |ticks: u64| Duration::from_nanos((ticks as f64 * timestamp_period as f64) as u64);
+
+    names
+        .iter()
+        .zip(timestamps.chunks_exact(2))
+        .map(|(name, timestamps)| GpuScope {
+            name: name.clone(),
+            start: to_duration(timestamps[0] - first),
+            duration: to_duration(timestamps[1].saturating_sub(timestamps[0])),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vulkan::timestamp_queries::scope_timings;
+    use std::time::Duration;
+
+    #[test]
+    fn scopes_are_relative_to_the_first_timestamp() {
+        let names = vec![String::from("Meshes"), String::from("UI")];
+        let scopes = scope_timings(&names, &[1000, 1500, 1600, 1700], 2.0);
+
+        assert_eq!(scopes[0].start, Duration::from_nanos(0));
+        assert_eq!(scopes[0].duration, Duration::from_nanos(1000));
+        assert_eq!(scopes[1].name, "UI");
+        assert_eq!(scopes[1].start, Duration::from_nanos(1200));
+        assert_eq!(scopes[1].duration, Duration::from_nanos(200));
+    }
+}
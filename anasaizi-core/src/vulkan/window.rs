@@ -9,22 +9,28 @@ pub struct Window {
 }
 
 impl Window {
-    pub fn new(
+    /// Creates a Vulkan window from a winit window.
+    ///
+    /// The instance has to be created with the extensions from `VkSurfaceData::required_extensions`.
+    pub fn new(window: winit::window::Window, instance: &VkInstance) -> Window {
+        let surface = VkSurfaceData::new(&instance, &window);
+
+        Window { window, surface }
+    }
+
+    /// Builds the winit window, this is done before the instance is created
+    /// because the required surface extensions depend on the window platform.
+    pub fn build_winit_window(
         window_title: &str,
         window_width: u32,
         window_height: u32,
-        instance: &VkInstance,
         event_loop: &EventLoop<()>,
-    ) -> Window {
-        let window = winit::window::WindowBuilder::new()
+    ) -> winit::window::Window {
+        winit::window::WindowBuilder::new()
             .with_title(window_title)
             .with_inner_size(winit::dpi::LogicalSize::new(window_width, window_height))
             .build(event_loop)
-            .expect("Failed to create window.");
-
-        let surface = VkSurfaceData::new(&instance, &window);
-
-        Window { window, surface }
+            .expect("Failed to create window.")
     }
 
     pub fn surface_data(&self) -> &VkSurfaceData {
@@ -1,6 +1,7 @@
 #version 450
 
 #extension GL_ARB_separate_shader_objects : enable
+#extension GL_EXT_nonuniform_qualifier : require
 
 layout(location = 0) in vec4 fragColor;
 layout(location = 1) in vec2 fragTexCoord;
@@ -9,8 +10,8 @@ layout(location = 2) in flat int imgIndex;
 layout(location = 0) out vec4 outColor;
 
 layout(set = 0, binding = 1) uniform sampler samp;
-layout(set = 0, binding = 2) uniform texture2D textures[2];
+layout(set = 1, binding = 0) uniform texture2D textures[];
 
 void main() {
-    outColor = vec4(fragColor.rgb * texture(sampler2D(textures[imgIndex], samp), fragTexCoord).rgb, 1.0);
+    outColor = vec4(fragColor.rgb * texture(sampler2D(textures[nonuniformEXT(imgIndex)], samp), fragTexCoord).rgb, 1.0);
 }
\ No newline at end of file
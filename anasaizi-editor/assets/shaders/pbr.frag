@@ -1,5 +1,6 @@
 #version 450
 #extension GL_ARB_separate_shader_objects : enable
+#extension GL_EXT_nonuniform_qualifier : require
 
 layout(location = 0) in vec4 in_fragColor;
 layout(location = 1) in vec2 in_fragTexCoord;
@@ -20,7 +21,7 @@ layout(binding = 0) uniform UniformBufferObject {
 } ubo;
 
 layout(set = 0, binding = 1) uniform sampler samp;
-layout(set = 0, binding = 2) uniform texture2D textures[8];
+layout(set = 1, binding = 0) uniform texture2D textures[];
 
 layout(binding = 3) uniform Light {
     vec4 position;
@@ -38,10 +39,11 @@ vec3 getNormalFromMap();
 
 void main() {
     // Get values from material textures.
-    vec3 albedo     = pow(texture(sampler2D(textures[in_albedoMap], samp), in_fragTexCoord).rgb, vec3(2.2));
-    float metallic  = texture(sampler2D(textures[in_metallicMap], samp), in_fragTexCoord).r;
-    float roughness = texture(sampler2D(textures[in_roughnessMap], samp), in_fragTexCoord).r;
-    float ao        = texture(sampler2D(textures[in_albedoMap], samp), in_fragTexCoord).r;
+    // The albedo map is an sRGB texture, it is sampled as linear values.
+    vec3 albedo     = texture(sampler2D(textures[nonuniformEXT(in_albedoMap)], samp), in_fragTexCoord).rgb;
+    float metallic  = texture(sampler2D(textures[nonuniformEXT(in_metallicMap)], samp), in_fragTexCoord).r;
+    float roughness = texture(sampler2D(textures[nonuniformEXT(in_roughnessMap)], samp), in_fragTexCoord).r;
+    float ao        = texture(sampler2D(textures[nonuniformEXT(in_albedoMap)], samp), in_fragTexCoord).r;
 
     // Setup constants.
     vec3 N = getNormalFromMap();
@@ -93,7 +95,7 @@ void main() {
 
 vec3 getNormalFromMap()
 {
-    vec3 normal_map = texture(sampler2D(textures[in_normalMap], samp), in_fragTexCoord).xyz;
+    vec3 normal_map = texture(sampler2D(textures[nonuniformEXT(in_normalMap)], samp), in_fragTexCoord).xyz;
     vec3 tangentNormal = normal_map * 2.0 - 1.0;
 
     vec3 Q1  = dFdx(in_fragPos);
@@ -33,6 +33,7 @@ layout(push_constant) uniform MeshPushConstants {
     int metallicMap;
     int roughnessMap;
     int aoMap;
+    int displacementMap;
 } push_constants;
 
 void main() {
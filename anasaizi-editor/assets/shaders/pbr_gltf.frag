@@ -12,6 +12,7 @@ layout (constant_id = 7) const int HAS_TANGENTS = 0;
 layout (constant_id = 8) const int HAS_COLORS = 0;
 layout (constant_id = 9) const int HAS_UV = 0;
 layout (constant_id = 10) const int TEXTURE_ARRAY_LENGHT = 30;
+layout (constant_id = 11) const int USE_TEX_LOD = 0;
 
 layout(location=0) in vec4 v_Position;
 layout(location=2) in vec2 v_UV[2];
@@ -20,7 +21,7 @@ layout(location=6) in vec4 v_Color;
 layout(location=8) in mat3 v_TBN;
 
 layout(set = 0, binding = 1) uniform sampler samp;
-layout(set = 0, binding = 2) uniform texture2D textures[TEXTURE_ARRAY_LENGHT];
+layout(set = 1, binding = 0) uniform texture2D textures[TEXTURE_ARRAY_LENGHT];
 
 layout(binding = 3) uniform Light {
     vec4 position;
@@ -84,6 +85,11 @@ const float M_PI = 3.141592653589793;
 const float c_MinRoughness = 0.04;
 
 vec3 get_texture(int map, vec2 coords) {
+    // Without mip levels only the first level is sampled.
+    if (USE_TEX_LOD == 0) {
+        return textureLod(sampler2D(textures[map], samp), coords, 0.0).rgb;
+    }
+
     return texture(sampler2D(textures[map], samp), coords).rgb;
 }
 
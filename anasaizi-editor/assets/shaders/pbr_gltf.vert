@@ -17,6 +17,7 @@ layout (constant_id = 7) const int HAS_TANGENTS = 0;
 layout (constant_id = 8) const int HAS_COLORS = 0;
 layout (constant_id = 9) const int HAS_UV = 0;
 layout (constant_id = 10) const int TEXTURE_ARRAY_LENGHT = 30;
+layout (constant_id = 11) const int USE_TEX_LOD = 0;
 
 /// ====== IN variables ======
 
@@ -83,6 +83,9 @@ impl Application {
                             .send(engine::Event::MouseInput(state, button))
                             .unwrap();
                     }
+                    WindowEvent::Resized(size) => {
+                        self.input_sender.send(engine::Event::Resize(size)).unwrap();
+                    }
                     _ => {}
                 },
                 _ => (),
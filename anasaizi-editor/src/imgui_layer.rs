@@ -1,7 +1,8 @@
 use anasaizi_core::{
     engine::{
-        image::Texture, Event, GpuMeshMemory, Layer, RenderContext, RenderLayer, Transform,
-        VulkanApplication, World,
+        image::{Texture, TextureColorSpace},
+        Event, GpuMeshMemory, Layer, RenderContext, RenderLayer, Transform, VulkanApplication,
+        World,
     },
     libs::{
         hecs::Entity,
@@ -11,7 +12,7 @@ use anasaizi_core::{
         imgui_winit_support::{HiDpiMode, WinitPlatform},
         nalgebra::Vector3,
     },
-    vulkan::Window,
+    vulkan::{VkLogicalDevice, Window},
 };
 use std::{f32::consts::PI, mem, time::Duration};
 use winit::event::{ElementState, VirtualKeyCode, WindowEvent};
@@ -43,7 +44,10 @@ pub struct ImguiLayer {
     pub imgui_context: Context,
     pub ui_font_texture: Texture,
 
-    pub ui_mesh: Option<GpuMeshMemory>,
+    /// One mesh per frame in flight, the GPU may still read the meshes of the other frames.
+    ui_meshes: Vec<Option<GpuMeshMemory>>,
+    /// The frame the ui is drawn for, set to `RenderLayer::current_frame` before the update.
+    pub current_frame: usize,
 
     pub window: *mut Window,
     pub draw_data: *const DrawData,
@@ -52,6 +56,8 @@ pub struct ImguiLayer {
     pub transform_input: TransformInput,
     pub lighting_input: LightingInput,
     pub selected_entity: Option<Entity>,
+    /// Shader reload errors, shown until the shader compiles again.
+    pub shader_errors: Vec<String>,
 }
 
 impl ImguiLayer {
@@ -67,8 +73,12 @@ impl ImguiLayer {
             imgui_context: imgui,
             platform,
             ui_font_texture: unsafe { mem::zeroed() },
-            ui_mesh: None,
-            window: &mut application.window,
+            ui_meshes: (0.._vulkan_renderer.frame_count()).map(|_| None).collect(),
+            current_frame: 0,
+            window: application
+                .window
+                .as_mut()
+                .expect("Application is headless and has no window!"),
             draw_data: std::ptr::null(),
             world: &mut _vulkan_renderer.world,
             transform_input: TransformInput {
@@ -90,6 +100,19 @@ impl ImguiLayer {
                 light_color: [1.0, 1.0, 1.0],
             },
             selected_entity: None,
+            shader_errors: vec![],
+        }
+    }
+
+    /// The mesh of the ui drawn for `current_frame`, `None` before the first update of that frame.
+    pub fn ui_mesh(&self) -> Option<&GpuMeshMemory> {
+        self.ui_meshes[self.current_frame].as_ref()
+    }
+
+    /// Destroys the ui meshes, the device must be idle.
+    pub fn destroy(&self, device: &VkLogicalDevice) {
+        for ui_mesh in self.ui_meshes.iter().flatten() {
+            ui_mesh.destroy(device);
         }
     }
 }
@@ -132,6 +155,8 @@ impl Layer for ImguiLayer {
                 &atlas_texture.data,
                 atlas_texture.width,
                 atlas_texture.height,
+                // The atlas holds the coverage of the glyphs.
+                TextureColorSpace::Linear,
             )
         };
 
@@ -297,20 +322,31 @@ impl Layer for ImguiLayer {
                         .build(&ui, &mut self.transform_input.object_scale);
         }
 
+        if !self.shader_errors.is_empty() {
+            ui.separator();
+            ui.text_colored([1.0, 0.3, 0.3, 1.0], "Shader Errors");
+
+            for error in self.shader_errors.iter() {
+                ui.text_colored([1.0, 0.3, 0.3, 1.0], error);
+            }
+        }
+
         self.platform
             .prepare_render(&ui, unsafe { &mut (*self.window).window });
 
         self.draw_data = ui.render();
 
-        if let None = self.ui_mesh {
+        let ui_mesh = &mut self.ui_meshes[self.current_frame];
+
+        if let None = ui_mesh {
             unsafe {
-                self.ui_mesh = Some(GpuMeshMemory::from_draw_data(
+                *ui_mesh = Some(GpuMeshMemory::from_draw_data(
                     _render_context,
                     &*self.draw_data,
                 ));
             }
         } else {
-            let mesh = self.ui_mesh.as_mut().unwrap();
+            let mesh = ui_mesh.as_mut().unwrap();
             unsafe {
                 mesh.update_from_draw_data(_render_context, &*self.draw_data);
             }
@@ -2,21 +2,23 @@ use crate::sandbox::VulkanApp;
 
 use anasaizi_profile::profile;
 
-use anasaizi_core::libs::tokio;
-use std::io;
+use anasaizi_core::libs::{log::LevelFilter, tokio};
 use winit::event_loop::EventLoop;
 
 #[macro_use]
 mod sandbox;
 mod game_layer;
 mod imgui_layer;
+mod logger;
 
 #[tokio::main]
 #[profile]
-async fn main() -> io::Result<()> {
+async fn main() -> anasaizi_core::Result<()> {
+    logger::init(LevelFilter::Info);
+
     let event_loop = EventLoop::new();
 
-    let app = VulkanApp::new(&event_loop).await;
+    let app = VulkanApp::new(&event_loop).await?;
     app.main_loop(event_loop);
 
     Ok(())
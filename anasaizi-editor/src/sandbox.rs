@@ -1,4 +1,6 @@
-use anasaizi_core::vulkan::{ShaderBuilder, ShaderIOBuilder, ShaderSet, VkPipeline};
+use anasaizi_core::vulkan::{
+    PipelineDescription, ShaderBuilder, ShaderIOBuilder, ShaderSet, VkPipeline,
+};
 use winit::event_loop::EventLoop;
 
 use anasaizi_profile::profile;
@@ -6,34 +8,29 @@ use anasaizi_profile::profile;
 use anasaizi_core::{
     debug::start_profiler,
     engine::{
-        image::Texture, resources::TextureLoader, BufferLayout, GpuMeshMemory, Layer,
-        LightUniformObject, MeshPushConstants, PBRMeshPushConstants, RenderLayer,
-        Transform, UIPushConstants, ViewProjectionMatrixUniformObject, VulkanApplication,
-        FRAGMENT_SHADER, VERTEX_SHADER,
+        image::{Texture, TextureColorSpace},
+        resources::TextureLoader,
+        BufferLayout, GpuMeshMemory, Layer, LightUniformObject, RenderLayer, Transform,
+        ViewProjectionMatrixUniformObject, VulkanApplication, FRAGMENT_SHADER, UI_PASS,
+        VERTEX_SHADER,
     },
     libs::{
-        ash::{self, vk},
         hecs::Entity,
         image::GenericImageView,
+        log,
         nalgebra::{Vector3, Vector4},
     },
     model::Object,
+    Result,
 };
 
 use crate::{game_layer::Application, imgui_layer::ImguiLayer};
 
 use anasaizi_core::engine::{
     gltf::{load_gltf_scene},
-    GLTFLightUniformObject, GLTFMaterial, GltfPBRShaderConstants,
-};
-use std::{
-    ffi::{c_void, CStr},
-    mem,
-    mem::size_of,
-    path::Path,
-    ptr,
-    sync::Arc,
+    GLTFLightUniformObject, GltfPBRShaderConstants,
 };
+use std::{mem::size_of, path::Path, sync::Arc};
 
 pub const MAIN_MESH_PIPELINE_ID: u32 = 0;
 const GRID_PIPELINE_ID: u32 = 1;
@@ -52,43 +49,39 @@ pub struct VulkanApp {
     pub textures: Vec<Texture>,
 
     pub light_entity: Entity,
-
-    debug_utils_loader: Option<ash::extensions::ext::DebugUtils>,
-    debug_merssager: Option<vk::DebugUtilsMessengerEXT>,
 }
 
 impl VulkanApp {
     async fn load_scene(
         vulkan_renderer: &mut RenderLayer,
         application: &VulkanApplication,
-        texture: &[Texture],
-    ) {
+    ) -> Result<Vec<Texture>> {
         //let a = load_gltf_scene(vulkan_renderer.render_context(application), "E:\\programming\\Anasazi\\anasaizi-editor\\assets\\gltf\\basic\\BoxMultiScene.gltf", 0).await;
         let a = load_gltf_scene(
             vulkan_renderer.render_context(application),
             "E:\\programming\\Anasazi\\anasaizi-editor\\assets\\gltf\\sponza\\Sponza.gltf",
             0,
         )
-        .await;
+        .await?;
         let root = a.0;
         let _scene = a.1;
 
         let mut pipeline_id = START_GLFT_PIPELINE_ID;
 
-        for (flag, entities) in root.entities {
-            let tx = if root.textures.len() == 0 {
-                texture
-            } else {
-                &root.textures
-            };
+        // The material texture indices refer to the scene textures, move them to the table.
+        let first_texture = vulkan_renderer
+            .bindless_textures
+            .register_all(&application.device, &root.textures)?;
 
+        for (flag, entities) in root.entities {
             let mut constants = GltfPBRShaderConstants::from(flag);
-            constants.texture_array_lenght = tx.len() as u32;
+            constants.texture_array_lenght = vulkan_renderer.bindless_textures.capacity();
 
-            let shader =
-                Self::setup_gltf_pbr_shader(application, &vulkan_renderer, tx, constants);
+            let shader = Self::setup_gltf_pbr_shader(application, &vulkan_renderer, constants)?;
+
+            for (memory, transform, mut material) in entities {
+                material.offset_textures(first_texture as i32);
 
-            for (memory, transform, material) in entities {
                 vulkan_renderer.world.spawn((
                     memory.clone(),
                     transform.clone(),
@@ -97,20 +90,34 @@ impl VulkanApp {
                 ));
             }
 
-            vulkan_renderer.create_pipeline(application, shader, pipeline_id);
+            vulkan_renderer.create_pipeline(
+                application,
+                shader,
+                PipelineDescription::default(),
+                pipeline_id,
+                &format!("glTF {:?}", flag),
+            )?;
 
             pipeline_id += 1;
         }
+
+        // The table only refers to the textures, they are destroyed on shutdown.
+        Ok(root.textures)
     }
 
-    pub async fn new(event_loop: &EventLoop<()>) -> VulkanApp {
-        let application = VulkanApplication::new("Vulkan Engine", event_loop);
+    pub async fn new(event_loop: &EventLoop<()>) -> Result<VulkanApp> {
+        let application = VulkanApplication::new("Vulkan Engine", event_loop)?;
 
-        let mut vulkan_renderer = RenderLayer::new(&application);
+        let mut vulkan_renderer = RenderLayer::new(&application)?;
 
         let mut texture_loader =
             TextureLoader::new(Arc::from(vulkan_renderer.render_context(&application)));
-        texture_loader.load_path("assets/textures/white.png", "colors.white", false);
+        texture_loader.load_path(
+            "assets/textures/white.png",
+            "colors.white",
+            TextureColorSpace::Srgb,
+            false,
+        );
         //
         // texture_loader.load_path("assets/textures/marble/albedo.jpg", "marble.albedo", false);
         // texture_loader.load_path(
@@ -147,14 +154,24 @@ impl VulkanApp {
                                                             // textures.query("cabin.normal").owned_texture(),
         ];
 
-        Self::load_scene(&mut vulkan_renderer, &application, &main_shader_textures).await;
+        // Registered first, so white is texture 0 in the bindless texture table.
+        vulkan_renderer
+            .bindless_textures
+            .register_all(&application.device, &main_shader_textures)?;
+
+        let scene_textures = Self::load_scene(&mut vulkan_renderer, &application).await?;
 
         let (sphere_vertices, sphere_indices) =
             Object::load_model(Path::new("assets/obj/sphere.obj"));
         //let (cabin_vertices, cabin_indices) = Object::load_model(Path::new("assets/obj/cabin.obj"));
 
         let render_context = vulkan_renderer.render_context(&application);
-        vulkan_renderer.initialize(&application.window, &render_context);
+        vulkan_renderer.initialize(application.window(), &render_context);
+
+        // Edited shaders are recompiled and their pipelines rebuilt while the editor runs.
+        if let Err(error) = vulkan_renderer.watch_shaders(Path::new("assets/shaders")) {
+            log::warn!("Shader hot reload is disabled: {}", error);
+        }
 
         let light_cube_mesh_memory = GpuMeshMemory::from_raw(
             &render_context,
@@ -163,17 +180,33 @@ impl VulkanApp {
             0,
         );
 
-        let lighting_shader_set =
-            Self::setup_pbr_shader(&application, &vulkan_renderer, &main_shader_textures);
+        let lighting_shader_set = Self::setup_pbr_shader(&application, &vulkan_renderer)?;
 
-        let main_shader_set =
-            Self::setup_main_shader(&application, &vulkan_renderer, &main_shader_textures);
+        let main_shader_set = Self::setup_main_shader(&application, &vulkan_renderer)?;
 
-        let (grid_shader, grid_mesh) = vulkan_renderer.grid_mesh(&application, &render_context);
+        let (grid_shader, grid_mesh) = vulkan_renderer.grid_mesh(&application, &render_context)?;
 
-        vulkan_renderer.create_pipeline(&application, main_shader_set, MAIN_MESH_PIPELINE_ID);
-        vulkan_renderer.create_pipeline(&application, lighting_shader_set, PBR_MESH_PIPELINE_ID);
-        vulkan_renderer.create_pipeline(&application, grid_shader, GRID_PIPELINE_ID);
+        vulkan_renderer.create_pipeline(
+            &application,
+            main_shader_set,
+            PipelineDescription::default(),
+            MAIN_MESH_PIPELINE_ID,
+            "Main",
+        )?;
+        vulkan_renderer.create_pipeline(
+            &application,
+            lighting_shader_set,
+            PipelineDescription::default(),
+            PBR_MESH_PIPELINE_ID,
+            "PBR",
+        )?;
+        vulkan_renderer.create_pipeline(
+            &application,
+            grid_shader,
+            PipelineDescription::default(),
+            GRID_PIPELINE_ID,
+            "Grid",
+        )?;
 
         Self::initialize_uniform_objects(&mut vulkan_renderer);
 
@@ -239,25 +272,23 @@ impl VulkanApp {
             .world
             .spawn((grid_mesh, Transform::new(1.0), GRID_PIPELINE_ID));
 
-        let (debug_utils_loader, debug_merssager) =
-            setup_debug_utils(true, &application.instance.entry(), &application.instance);
-
-        VulkanApp {
+        Ok(VulkanApp {
             vulkan_renderer,
             application,
-            textures: Vec::from(main_shader_textures),
-
-            debug_merssager: Some(debug_merssager),
-            debug_utils_loader: Some(debug_utils_loader),
+            textures: main_shader_textures
+                .iter()
+                .cloned()
+                .chain(scene_textures)
+                .collect(),
             light_entity,
-        }
+        })
     }
 
     pub fn setup_pbr_shader(
         application: &VulkanApplication,
         vulkan_renderer: &RenderLayer,
-        textures: &[Texture],
-    ) -> ShaderSet {
+    ) -> Result<ShaderSet> {
+        // The vertices also hold the tangent and bitangent, which the shader does not read.
         let input_buffer_layout = BufferLayout::new()
             .add_float_vec3(0)
             .add_float_vec4(1)
@@ -266,131 +297,74 @@ impl VulkanApp {
             .add_float_vec3(4)
             .add_float_vec3(5);
 
-        let push_const_ranges = [vk::PushConstantRange {
-            stage_flags: vk::ShaderStageFlags::VERTEX,
-            offset: 0,
-            size: mem::size_of::<PBRMeshPushConstants>() as u32,
-        }];
-
         let descriptors = ShaderIOBuilder::builder()
             .add_uniform_buffer(
                 0,
-                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                0,
                 &vulkan_renderer.render_context(application),
-                vulkan_renderer.swapchain.images.len(),
+                vulkan_renderer.frame_count(),
                 unsafe { size_of::<ViewProjectionMatrixUniformObject>() },
             )
             .add_uniform_buffer(
+                0,
                 3,
-                vk::ShaderStageFlags::FRAGMENT | vk::ShaderStageFlags::VERTEX,
                 &vulkan_renderer.render_context(application),
-                vulkan_renderer.swapchain.images.len(),
+                vulkan_renderer.frame_count(),
                 unsafe { size_of::<LightUniformObject>() },
             )
-            .sampler(
-                1,
-                vk::ShaderStageFlags::FRAGMENT,
-                vulkan_renderer.texture_sampler.unwrap(),
-            )
-            .texture_array(
-                2,
-                vk::ShaderStageFlags::FRAGMENT,
-                &textures,
-                vulkan_renderer.texture_sampler.unwrap(),
-            )
-            .add_input_buffer_layout(input_buffer_layout)
-            .add_push_constant_ranges(&push_const_ranges)
-            .build(
-                &vulkan_renderer.render_context(application),
-                vulkan_renderer.swapchain.images.len(),
-            );
+            .sampler(0, 1, vulkan_renderer.texture_sampler.unwrap())
+            .bindless_textures(vulkan_renderer.bindless_textures.descriptors())
+            .add_input_buffer_layout(input_buffer_layout);
 
-        ShaderBuilder::builder(
+        ShaderBuilder::glsl(
             application,
-            "assets\\shaders\\build\\pbr.vert.spv",
-            "assets\\shaders\\build\\pbr.frag.spv",
+            "assets/shaders/pbr.vert",
+            "assets/shaders/pbr.frag",
         )
-        .with_descriptors(descriptors)
+        .with_descriptors(descriptors, vulkan_renderer.frame_count())
+        .with_debug_name("PBR")
         .build()
     }
 
     pub fn setup_gltf_pbr_shader(
         application: &VulkanApplication,
         vulkan_renderer: &RenderLayer,
-        textures: &[Texture],
         specialisation_constant_data: GltfPBRShaderConstants,
-    ) -> ShaderSet {
-        let input_buffer_layout = BufferLayout::new()
-            .add_float_vec4(0) // position
-            .add_float_vec4(2) // normal
-            .add_float_vec4(4) // tangent
-            .add_float_vec2(6) // tex coord 0
-            .add_float_vec2(7) // tex coord 1
-            .add_float_vec4(8); // color
-
-        let mut constant_layout = BufferLayout::new();
-
-        for i in 0..11 {
-            constant_layout = constant_layout.add_bool(i);
-        }
-
-        let specialization_constants = constant_layout.build_specialisation_constants();
-
-        let push_const_ranges = [vk::PushConstantRange {
-            stage_flags: vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
-            offset: 0,
-            size: mem::size_of::<GLTFMaterial>() as u32,
-        }];
-
+    ) -> Result<ShaderSet> {
         let descriptors = ShaderIOBuilder::builder()
             .add_uniform_buffer(
                 0,
-                vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                0,
                 &vulkan_renderer.render_context(application),
-                vulkan_renderer.swapchain.images.len(),
+                vulkan_renderer.frame_count(),
                 unsafe { size_of::<ViewProjectionMatrixUniformObject>() },
             )
             .add_uniform_buffer(
+                0,
                 3,
-                vk::ShaderStageFlags::FRAGMENT | vk::ShaderStageFlags::VERTEX,
                 &vulkan_renderer.render_context(application),
-                vulkan_renderer.swapchain.images.len(),
+                vulkan_renderer.frame_count(),
                 unsafe { size_of::<GLTFLightUniformObject>() },
             )
-            .add_push_constant_ranges(&push_const_ranges)
-            .add_input_buffer_layout(input_buffer_layout)
-            .add_specialization_constants(specialisation_constant_data, specialization_constants)
-            .sampler(
-                1,
-                vk::ShaderStageFlags::FRAGMENT,
-                vulkan_renderer.texture_sampler.unwrap(),
-            )
-            .texture_array(
-                2,
-                vk::ShaderStageFlags::FRAGMENT,
-                &textures,
-                vulkan_renderer.texture_sampler.unwrap(),
-            );
-
-        let shaderio = descriptors.build(
-            &vulkan_renderer.render_context(application),
-            vulkan_renderer.swapchain.images.len(),
-        );
+            .add_specialization_constants(specialisation_constant_data)
+            .sampler(0, 1, vulkan_renderer.texture_sampler.unwrap())
+            .bindless_textures(vulkan_renderer.bindless_textures.descriptors());
 
-        ShaderBuilder::builder(
+        ShaderBuilder::glsl(
             application,
-            "assets\\shaders\\build\\pbr_gltf.vert.spv",
-            "assets\\shaders\\build\\pbr_gltf.frag.spv",
+            "assets/shaders/pbr_gltf.vert",
+            "assets/shaders/pbr_gltf.frag",
         )
-        .with_descriptors(shaderio)
+        .with_descriptors(descriptors, vulkan_renderer.frame_count())
+        .with_debug_name("glTF PBR")
         .build()
     }
 
     pub fn setup_main_shader(
         application: &VulkanApplication,
         vulkan_renderer: &RenderLayer,
-        textures: &[Texture],
-    ) -> ShaderSet {
+    ) -> Result<ShaderSet> {
+        // The vertices also hold the tangent and bitangent, which the shader does not read.
         let input_buffer_layout = BufferLayout::new()
             .add_float_vec3(0)
             .add_float_vec4(1)
@@ -399,40 +373,21 @@ impl VulkanApp {
             .add_float_vec3(4)
             .add_float_vec3(5);
 
-        let push_const_ranges = [vk::PushConstantRange {
-            stage_flags: vk::ShaderStageFlags::VERTEX,
-            offset: 0,
-            size: mem::size_of::<MeshPushConstants>() as u32,
-        }];
-
         let descriptors = ShaderIOBuilder::builder()
             .add_uniform_buffer(
                 0,
-                vk::ShaderStageFlags::VERTEX,
+                0,
                 &vulkan_renderer.render_context(application),
-                vulkan_renderer.swapchain.images.len(),
+                vulkan_renderer.frame_count(),
                 unsafe { size_of::<ViewProjectionMatrixUniformObject>() },
             )
-            .sampler(
-                1,
-                vk::ShaderStageFlags::FRAGMENT,
-                vulkan_renderer.texture_sampler.unwrap(),
-            )
-            .texture_array(
-                2,
-                vk::ShaderStageFlags::FRAGMENT,
-                &textures,
-                vulkan_renderer.texture_sampler.unwrap(),
-            )
-            .add_input_buffer_layout(input_buffer_layout)
-            .add_push_constant_ranges(&push_const_ranges)
-            .build(
-                &vulkan_renderer.render_context(application),
-                vulkan_renderer.swapchain.images.len(),
-            );
+            .sampler(0, 1, vulkan_renderer.texture_sampler.unwrap())
+            .bindless_textures(vulkan_renderer.bindless_textures.descriptors())
+            .add_input_buffer_layout(input_buffer_layout);
 
-        ShaderBuilder::builder(application, VERTEX_SHADER, FRAGMENT_SHADER)
-            .with_descriptors(descriptors)
+        ShaderBuilder::glsl(application, VERTEX_SHADER, FRAGMENT_SHADER)
+            .with_descriptors(descriptors, vulkan_renderer.frame_count())
+            .with_debug_name("Main")
             .build()
     }
 
@@ -440,41 +395,21 @@ impl VulkanApp {
         application: &VulkanApplication,
         vulkan_renderer: &RenderLayer,
         texture: &Texture,
-    ) -> ShaderSet {
-        let input_buffer_layout = BufferLayout::new()
-            .add_float_vec3(0)
-            .add_float_vec4(1)
-            .add_float_vec2(2)
-            .add_float_vec3(3)
-            .add_float_vec3(4)
-            .add_float_vec3(5);
-
-        let push_const_ranges = [vk::PushConstantRange {
-            stage_flags: vk::ShaderStageFlags::VERTEX,
-            offset: 0,
-            size: mem::size_of::<UIPushConstants>() as u32,
-        }];
-
-        let descriptors = ShaderIOBuilder::builder()
-            .add_static_image(
-                1,
-                vk::ShaderStageFlags::FRAGMENT,
-                &texture,
-                vulkan_renderer.texture_sampler.unwrap(),
-            )
-            .add_input_buffer_layout(input_buffer_layout)
-            .add_push_constant_ranges(&push_const_ranges)
-            .build(
-                &vulkan_renderer.render_context(application),
-                vulkan_renderer.swapchain.images.len(),
-            );
+    ) -> Result<ShaderSet> {
+        let descriptors = ShaderIOBuilder::builder().add_static_image(
+            0,
+            1,
+            &texture,
+            vulkan_renderer.texture_sampler.unwrap(),
+        );
 
-        ShaderBuilder::builder(
+        ShaderBuilder::glsl(
             application,
-            "assets\\shaders\\build\\ui_vert.vert.spv",
-            "assets\\shaders\\build\\ui_frag.frag.spv",
+            "assets/shaders/ui_vert.vert",
+            "assets/shaders/ui_frag.frag",
         )
-        .with_descriptors(descriptors)
+        .with_descriptors(descriptors, vulkan_renderer.frame_count())
+        .with_debug_name("UI")
         .build()
     }
 
@@ -517,7 +452,7 @@ impl VulkanApp {
     #[profile(Sandbox)]
     fn update_uniform(
         vulkan_renderer: &mut RenderLayer,
-        application: &VulkanApplication,
+        _application: &VulkanApplication,
         _imgui_layer: &ImguiLayer,
         light_entity: Entity,
     ) {
@@ -527,18 +462,17 @@ impl VulkanApp {
             (camera.view(), camera.projection())
         };
 
-        for pipeline in vulkan_renderer.pipelines.iter_mut() {
+        let frame = vulkan_renderer.frame_data();
+
+        for pipeline in vulkan_renderer.pipelines.iter() {
+            let resources = frame.shader_resources(pipeline.pipeline_id());
+
             pipeline
                 .shader
-                .update_uniform::<ViewProjectionMatrixUniformObject>(
-                    &application.device,
-                    vulkan_renderer.current_frame,
-                    0,
-                    &move |obj| {
-                        obj.view_matrix = view.clone();
-                        obj.projection_matrix = perspective.clone();
-                    },
-                );
+                .update_uniform::<ViewProjectionMatrixUniformObject>(resources, 0, &move |obj| {
+                    obj.view_matrix = view.clone();
+                    obj.projection_matrix = perspective.clone();
+                });
 
             if pipeline.pipeline_id() >= START_GLFT_PIPELINE_ID
                 && pipeline.pipeline_id() <= START_GLFT_PIPELINE_ID + 5
@@ -551,8 +485,7 @@ impl VulkanApp {
                 let camera_pos = vulkan_renderer.camera.position();
 
                 pipeline.shader.update_uniform::<GLTFLightUniformObject>(
-                    &application.device,
-                    vulkan_renderer.current_frame,
+                    resources,
                     1,
                     &move |obj| {
                         obj.view_pos =
@@ -573,17 +506,14 @@ impl VulkanApp {
                 let light_pos = light_entity.translate_factor();
                 let camera_pos = vulkan_renderer.camera.position();
 
-                pipeline.shader.update_uniform::<LightUniformObject>(
-                    &application.device,
-                    vulkan_renderer.current_frame,
-                    1,
-                    &move |obj| {
+                pipeline
+                    .shader
+                    .update_uniform::<LightUniformObject>(resources, 1, &move |obj| {
                         obj.view_pos =
                             Vector4::new(camera_pos[0], camera_pos[1], camera_pos[2], 1.0);
                         obj.position = Vector4::new(light_pos[0], light_pos[1], light_pos[2], 1.0);
                         obj.light_color = Vector4::new(1.0, 1.0, 1.0, 1.0);
-                    },
-                );
+                    });
             }
         }
     }
@@ -595,17 +525,20 @@ impl VulkanApp {
         let mut game_layer = Application::new();
 
         let mut ui_layer = ImguiLayer::new(&mut application, &mut vulkan_renderer);
-        ui_layer.initialize(&application.window, &render_context);
+        ui_layer.initialize(application.window(), &render_context);
         let ui_shader =
-            Self::setup_ui_shader(&application, &vulkan_renderer, &ui_layer.ui_font_texture);
-        let pipeline = VkPipeline::ui_pipeline(
+            Self::setup_ui_shader(&application, &vulkan_renderer, &ui_layer.ui_font_texture)
+                .expect("Failed to build the ui shader!");
+        let mut pipeline = VkPipeline::ui_pipeline(
             &application.device,
-            &vulkan_renderer.render_pass,
+            vulkan_renderer.render_pass(UI_PASS),
             ui_shader,
             UI_PIPELINE_ID,
-        );
+        )
+        .expect("Failed to create the ui pipeline!");
+        pipeline.set_debug_name(&application.device, "UI");
 
-        vulkan_renderer.ui_pipeline = Some(pipeline);
+        vulkan_renderer.set_ui_pipeline(pipeline);
 
         let mut render_layers = vec![vulkan_renderer];
 
@@ -621,6 +554,14 @@ impl VulkanApp {
 
             game_layer.before_frame();
             game_layer.run_layers(&mut render_layers, &render_context, &application);
+
+            ui_layers[0].shader_errors = render_layers[0]
+                .shader_errors()
+                .into_iter()
+                .map(String::from)
+                .collect();
+            // The render layer waited for the next frame, its ui mesh and uniforms can be written.
+            ui_layers[0].current_frame = render_layers[0].current_frame();
             game_layer.run_layers(&mut ui_layers, &render_context, &application);
             game_layer.after_frame();
 
@@ -632,75 +573,19 @@ impl VulkanApp {
             );
 
             render_layers[0].ui_data = ui_layers[0].draw_data;
-            render_layers[0].ui_mesh = ui_layers[0].ui_mesh.as_ref().unwrap();
+            render_layers[0].ui_mesh = ui_layers[0].ui_mesh().unwrap();
+        }
+
+        // Waits for the frames in flight, the ui meshes are destroyed after.
+        render_layers[0].destroy(&application.device);
+        ui_layers[0].destroy(&application.device);
+        for texture in self.textures.iter() {
+            unsafe { texture.destroy(&application.device) };
         }
+        application.destroy();
     }
 
     fn destroy(&self) {
         self.vulkan_renderer.destroy(&self.application.device);
     }
 }
-
-pub fn setup_debug_utils(
-    is_enable_debug: bool,
-    entry: &ash::Entry,
-    instance: &ash::Instance,
-) -> (ash::extensions::ext::DebugUtils, vk::DebugUtilsMessengerEXT) {
-    let debug_utils_loader = ash::extensions::ext::DebugUtils::new(entry, instance);
-
-    if is_enable_debug == false {
-        (debug_utils_loader, ash::vk::DebugUtilsMessengerEXT::null())
-    } else {
-        let messenger_ci = populate_debug_messenger_create_info();
-
-        let utils_messenger = unsafe {
-            debug_utils_loader
-                .create_debug_utils_messenger(&messenger_ci, None)
-                .expect("Debug Utils Callback")
-        };
-
-        (debug_utils_loader, utils_messenger)
-    }
-}
-
-pub fn populate_debug_messenger_create_info() -> vk::DebugUtilsMessengerCreateInfoEXT {
-    vk::DebugUtilsMessengerCreateInfoEXT {
-        s_type: vk::StructureType::DEBUG_UTILS_MESSENGER_CREATE_INFO_EXT,
-        p_next: ptr::null(),
-        flags: vk::DebugUtilsMessengerCreateFlagsEXT::empty(),
-        message_severity: vk::DebugUtilsMessageSeverityFlagsEXT::WARNING |
-            // vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE |
-            // vk::DebugUtilsMessageSeverityFlagsEXT::INFO |
-            vk::DebugUtilsMessageSeverityFlagsEXT::ERROR,
-        message_type: vk::DebugUtilsMessageTypeFlagsEXT::GENERAL
-            | vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE
-            | vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION,
-        pfn_user_callback: Some(vulkan_debug_utils_callback),
-        p_user_data: ptr::null_mut(),
-    }
-}
-
-unsafe extern "system" fn vulkan_debug_utils_callback(
-    message_severity: vk::DebugUtilsMessageSeverityFlagsEXT,
-    message_type: vk::DebugUtilsMessageTypeFlagsEXT,
-    p_callback_data: *const vk::DebugUtilsMessengerCallbackDataEXT,
-    _p_user_data: *mut c_void,
-) -> vk::Bool32 {
-    let severity = match message_severity {
-        vk::DebugUtilsMessageSeverityFlagsEXT::VERBOSE => "[Verbose]",
-        vk::DebugUtilsMessageSeverityFlagsEXT::WARNING => "[Warning]",
-        vk::DebugUtilsMessageSeverityFlagsEXT::ERROR => "[Error]",
-        vk::DebugUtilsMessageSeverityFlagsEXT::INFO => "[Info]",
-        _ => "[Unknown]",
-    };
-    let types = match message_type {
-        vk::DebugUtilsMessageTypeFlagsEXT::GENERAL => "[General]",
-        vk::DebugUtilsMessageTypeFlagsEXT::PERFORMANCE => "[Performance]",
-        vk::DebugUtilsMessageTypeFlagsEXT::VALIDATION => "[Validation]",
-        _ => "[Unknown]",
-    };
-    let message = CStr::from_ptr((*p_callback_data).p_message);
-    println!("[Debug]{}{}{:?}", severity, types, message);
-
-    vk::FALSE
-}
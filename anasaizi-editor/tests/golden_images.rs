@@ -0,0 +1,198 @@
+//! Golden-image regression tests.
+//!
+//! Each test renders a scene headless with a fixed camera, reads the frame back and compares it
+//! against a reference PNG in `tests/golden`. A missing reference fails the test, run with
+//! `ANASAIZI_UPDATE_GOLDEN=1` to record it or to re-record after an intended change in shading.
+//!
+//! These tests need a Vulkan implementation (for example lavapipe) and compiled shaders,
+//! run them with `cargo test -- --ignored`.
+
+use anasaizi_core::{
+    engine::{
+        gltf::load_gltf_scene,
+        golden_image::assert_golden_image,
+        image::{Texture, TextureColorSpace},
+        GLTFLightUniformObject, GltfPBRShaderConstants, Layer, RenderLayer,
+        ViewProjectionMatrixUniformObject, VulkanApplication,
+    },
+    libs::{
+        image::RgbaImage,
+        nalgebra::{Vector3, Vector4},
+        tokio,
+    },
+    vulkan::{PipelineDescription, ShaderBuilder, ShaderIOBuilder, ShaderSet},
+    Result,
+};
+use std::{mem::size_of, path::Path};
+
+/// Maximum difference per color channel, absorbs rounding differences between drivers.
+const TOLERANCE: u8 = 3;
+
+const FIRST_GLTF_PIPELINE_ID: u32 = 0;
+
+fn setup_gltf_shader(
+    application: &VulkanApplication,
+    renderer: &RenderLayer,
+    specialization_constant_data: GltfPBRShaderConstants,
+) -> Result<ShaderSet> {
+    let render_context = renderer.render_context(application);
+    let frames = renderer.frame_count();
+
+    let descriptors = ShaderIOBuilder::builder()
+        .add_uniform_buffer(
+            0,
+            0,
+            &render_context,
+            frames,
+            size_of::<ViewProjectionMatrixUniformObject>(),
+        )
+        .add_uniform_buffer(
+            0,
+            3,
+            &render_context,
+            frames,
+            size_of::<GLTFLightUniformObject>(),
+        )
+        .add_specialization_constants(specialization_constant_data)
+        .sampler(0, 1, renderer.texture_sampler.unwrap())
+        .bindless_textures(renderer.bindless_textures.descriptors());
+
+    ShaderBuilder::builder(
+        application,
+        "assets/shaders/build/pbr_gltf.vert.spv",
+        "assets/shaders/build/pbr_gltf.frag.spv",
+    )
+    .with_descriptors(descriptors, frames)
+    .build()
+}
+
+/// Renders the given glTF scene from a fixed camera and returns the frame.
+async fn render_gltf_scene(
+    path: &str,
+    camera_position: Vector3<f32>,
+    camera_target: Vector3<f32>,
+) -> RgbaImage {
+    let application = VulkanApplication::headless_for_tests("Golden Image Test")
+        .expect("Failed to create application!");
+    let mut renderer = RenderLayer::new(&application).expect("Failed to create renderer!");
+    let render_context = renderer.render_context(&application);
+
+    let fallback_textures = [Texture::from_path(
+        &render_context,
+        Path::new("assets/textures/white.png"),
+        TextureColorSpace::Linear,
+    )
+    .expect("Failed to load fallback texture!")];
+
+    let (root, _scene) = load_gltf_scene(renderer.render_context(&application), path, 0)
+        .await
+        .expect("Failed to load glTF scene!");
+
+    renderer
+        .bindless_textures
+        .register_all(&application.device, &fallback_textures)
+        .expect("Failed to register fallback texture!");
+    let first_texture = renderer
+        .bindless_textures
+        .register_all(&application.device, &root.textures)
+        .expect("Failed to register scene textures!");
+
+    let mut pipeline_id = FIRST_GLTF_PIPELINE_ID;
+
+    for (flags, entities) in root.entities.iter() {
+        let mut constants = GltfPBRShaderConstants::from(*flags);
+        constants.texture_array_lenght = renderer.bindless_textures.capacity();
+
+        let shader = setup_gltf_shader(&application, &renderer, constants)
+            .expect("Failed to build glTF shader!");
+
+        for (memory, transform, material) in entities {
+            let mut material = material.clone();
+            material.offset_textures(first_texture as i32);
+
+            renderer
+                .world
+                .spawn((memory.clone(), transform.clone(), pipeline_id, material));
+        }
+
+        renderer
+            .create_pipeline(
+                &application,
+                shader,
+                PipelineDescription::default(),
+                pipeline_id,
+                &format!("glTF {:?}", flags),
+            )
+            .expect("Failed to create glTF pipeline!");
+        pipeline_id += 1;
+    }
+
+    let (view, projection) = {
+        let camera = renderer.camera();
+        camera.look_at(camera_position, camera_target);
+        camera.reload();
+        (camera.view(), camera.projection())
+    };
+
+    for pipeline in renderer.pipelines.iter_mut() {
+        pipeline
+            .shader
+            .add_uniform_object(ViewProjectionMatrixUniformObject {
+                projection_matrix: projection,
+                view_matrix: view,
+            });
+
+        let mut light = GLTFLightUniformObject::default();
+        light.view_pos = Vector4::new(camera_position.x, camera_position.y, camera_position.z, 1.0);
+        light.ambient_color = Vector4::new(1.0, 1.0, 1.0, 1.0);
+        light.ambient_light_intensity = 0.2;
+        light.light_direction = Vector4::new(0.0, 0.5, 0.5, 1.0);
+        light.light_color = Vector4::new(5.0, 5.0, 5.0, 1.0);
+        pipeline.shader.add_uniform_object(light);
+    }
+
+    let frame = renderer.frame_data();
+
+    for pipeline in renderer.pipelines.iter() {
+        let resources = frame.shader_resources(pipeline.pipeline_id());
+
+        pipeline
+            .shader
+            .update_uniform::<ViewProjectionMatrixUniformObject>(resources, 0, &|_| {});
+        pipeline
+            .shader
+            .update_uniform::<GLTFLightUniformObject>(resources, 1, &|_| {});
+    }
+
+    renderer.on_update(0, &render_context, &application);
+
+    let extent = renderer.target.extent();
+    let pixels = renderer
+        .read_back_frame(&render_context)
+        .expect("Headless renderer should render offscreen!");
+
+    renderer.destroy(&application.device);
+    for texture in fallback_textures.iter().chain(root.textures.iter()) {
+        unsafe { texture.destroy(&application.device) };
+    }
+    application.destroy();
+
+    RgbaImage::from_raw(extent.width, extent.height, pixels).unwrap()
+}
+
+#[tokio::test]
+#[ignore]
+async fn box_multi_scene() {
+    let frame = render_gltf_scene(
+        "assets/gltf/basic/BoxMultiScene.gltf",
+        Vector3::new(2.0, 2.0, 3.0),
+        Vector3::new(0.0, 0.0, 0.0),
+    )
+    .await;
+
+    assert_golden_image(
+        &frame,
+        Path::new("tests/golden/box_multi_scene.png"),
+        TOLERANCE,
+    );
+}